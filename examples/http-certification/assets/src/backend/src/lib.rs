@@ -67,7 +67,8 @@ static ASSETS_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../frontend/dist"
 const ASSET_CEL_EXPR_PATH: &str = "assets";
 
 const INDEX_REQ_PATH: &str = "";
-const INDEX_TREE_PATH: HttpCertificationPath = HttpCertificationPath::Wildcard(INDEX_REQ_PATH);
+const INDEX_TREE_PATH: HttpCertificationPath =
+    HttpCertificationPath::Wildcard(Cow::Borrowed(INDEX_REQ_PATH));
 const INDEX_FILE_PATH: &str = "index.html";
 
 // Certification
@@ -135,7 +136,7 @@ fn certify_asset_glob(glob: &str, content_type: &str) {
         } else {
             asset_file_path.clone()
         };
-        let asset_tree_path = HttpCertificationPath::Exact(&asset_req_path);
+        let asset_tree_path = HttpCertificationPath::exact(&asset_req_path);
 
         let additional_headers = vec![
             ("content-type".to_string(), content_type.to_string()),
@@ -166,12 +167,12 @@ fn certify_asset(
     certify_asset_response(
         body,
         additional_headers.clone(),
-        asset_tree_path,
+        asset_tree_path.clone(),
         asset_req_path.to_string(),
     );
     certify_asset_with_encoding(
         &asset_file_path,
-        asset_tree_path,
+        asset_tree_path.clone(),
         asset_req_path.to_string(),
         "gzip",
         additional_headers.clone(),
@@ -236,7 +237,7 @@ fn certify_asset_response(
 
         // certify the response
         let certification =
-            HttpCertification::response_only(cel_expr_def, &response.clone().into(), None);
+            HttpCertification::response_only(cel_expr_def, &response.clone().into(), None).unwrap();
 
         // store the response for later retrieval
         RESPONSES.with_borrow_mut(|responses| {
@@ -270,7 +271,7 @@ fn asset_handler(req: &HttpRequest) -> HttpResponse {
             if let Some(identity_response) = responses.get(&req_path) {
                 (
                     req_path.to_string(),
-                    HttpCertificationPath::Exact(&req_path),
+                    HttpCertificationPath::exact(&req_path),
                     identity_response,
                 )
             } else {
@@ -10,7 +10,7 @@ use ic_http_certification::{
     HttpRequest, HttpResponse,
 };
 use serde::{Deserialize, Serialize};
-use std::{cell::RefCell, collections::HashMap};
+use std::{borrow::Cow, cell::RefCell, collections::HashMap};
 
 // Public methods
 
@@ -71,10 +71,12 @@ thread_local! {
 }
 
 const TODOS_PATH: &str = "todos";
-const TODOS_TREE_PATH: HttpCertificationPath = HttpCertificationPath::Exact(TODOS_PATH);
+const TODOS_TREE_PATH: HttpCertificationPath =
+    HttpCertificationPath::Exact(Cow::Borrowed(TODOS_PATH));
 
 const NOT_FOUND_PATH: &str = "";
-const NOT_FOUND_TREE_PATH: HttpCertificationPath = HttpCertificationPath::Wildcard(NOT_FOUND_PATH);
+const NOT_FOUND_TREE_PATH: HttpCertificationPath =
+    HttpCertificationPath::Wildcard(Cow::Borrowed(NOT_FOUND_PATH));
 
 #[derive(Debug, Clone, Serialize)]
 struct TodoItem {
@@ -155,7 +157,7 @@ fn certify_response(
         ));
 
         // create the certification for this response and CEL expression pair
-        HttpCertification::response_only(cel_expr_def, &response, None)
+        HttpCertification::response_only(cel_expr_def, &response, None).unwrap()
     });
 
     RESPONSES.with_borrow_mut(|responses| {
@@ -1,18 +1,40 @@
-use crate::{parse_cbor, CborError, CborHashTree, CborResult, CborValue};
+use crate::{extract_cbor_value, peek_cbor_type, CborError, CborHashTree, CborResult, CborValue};
 use ic_certification::{
-    hash_tree::{empty, fork, label, leaf, pruned, Hash, Label},
+    hash_tree::{self, empty, fork, label, leaf, pruned, Hash, HashTreeNode, Label},
     HashTree,
 };
 
+/// The tag that marks the start of a self-described CBOR value, see
+/// [RFC 8949](https://www.rfc-editor.org/rfc/rfc8949.html#name-self-described-cbor).
+const SELF_DESCRIBE_TAG: usize = 55799;
+
 pub trait HashTreeToCbor {
     fn from_cbor(cbor: &[u8]) -> CborResult<HashTree>;
 }
 
 impl HashTreeToCbor for HashTree {
     fn from_cbor(cbor: &[u8]) -> CborResult<HashTree> {
-        let parsed_cbor = parse_cbor(cbor).map_err(|e| CborError::MalformedCbor(e.to_string()))?;
+        let tree = parse_borrowed_hash_tree(cbor)?;
 
-        parsed_cbor_to_tree(&parsed_cbor)
+        Ok(borrowed_node_to_tree(tree.into()))
+    }
+}
+
+/// Copies the labels and leaves of a node of a [BorrowedHashTree] into an owned [HashTree].
+fn borrowed_node_to_tree(node: HashTreeNode<&[u8]>) -> HashTree {
+    match node {
+        HashTreeNode::Empty() => empty(),
+        HashTreeNode::Fork(children) => {
+            let (left, right) = *children;
+
+            fork(borrowed_node_to_tree(left), borrowed_node_to_tree(right))
+        }
+        HashTreeNode::Labeled(node_label, child) => label(
+            node_label.as_bytes().to_vec(),
+            borrowed_node_to_tree(*child),
+        ),
+        HashTreeNode::Leaf(data) => leaf(data.to_vec()),
+        HashTreeNode::Pruned(digest) => pruned(digest),
     }
 }
 
@@ -87,6 +109,124 @@ pub fn parsed_cbor_to_tree(parsed_cbor: &CborValue) -> CborResult<HashTree> {
     }
 }
 
+/// A hash tree that borrows its labels and leaves from the CBOR it was decoded from.
+pub type BorrowedHashTree<'a> = hash_tree::HashTree<&'a [u8]>;
+
+/// Decodes a hash tree from CBOR without copying its labels and leaves, which are borrowed
+/// from the provided buffer instead.
+///
+/// The tree may be preceded by the self-describe tag, any other tag is rejected.
+pub fn parse_borrowed_hash_tree(cbor: &[u8]) -> CborResult<BorrowedHashTree<'_>> {
+    let i = match peek_cbor_type(cbor) {
+        Ok((i, 6)) => {
+            let (i, tag) =
+                extract_cbor_value(i).map_err(|e| CborError::MalformedCbor(e.to_string()))?;
+            if tag.to_usize() != SELF_DESCRIBE_TAG {
+                return Err(CborError::MalformedHashTree(String::from(
+                    "Unexpected cbor tag",
+                )));
+            }
+
+            i
+        }
+        _ => cbor,
+    };
+    let (i, tree) = borrowed_hash_tree_parser(i)?;
+
+    if !i.is_empty() {
+        return Err(CborError::MalformedCbor(String::from(
+            "Unexpected data after hash tree",
+        )));
+    }
+
+    Ok(tree)
+}
+
+fn borrowed_hash_tree_parser(i: &[u8]) -> CborResult<(&[u8], BorrowedHashTree<'_>)> {
+    let (i, cbor_type) = peek_cbor_type(i).map_err(|e| CborError::MalformedCbor(e.to_string()))?;
+    let (i, cbor_value) =
+        extract_cbor_value(i).map_err(|e| CborError::MalformedCbor(e.to_string()))?;
+
+    if cbor_type != 4 {
+        return Err(CborError::MalformedHashTree(String::from(
+            "Expected Array cbor tag",
+        )));
+    }
+    let num_elements = cbor_value.to_usize();
+
+    let (i, hash_tree_tag) = match peek_cbor_type(i) {
+        Ok((i, 0)) => extract_cbor_value(i)
+            .map(|(i, tag)| (i, tag.to_u8().ok()))
+            .map_err(|e| CborError::MalformedCbor(e.to_string()))?,
+        _ => (i, None),
+    };
+
+    match (hash_tree_tag, num_elements) {
+        (Some(0), 1) => Ok((i, empty())),
+
+        (Some(1), 3) => {
+            let (i, left) = borrowed_hash_tree_parser(i)?;
+            let (i, right) = borrowed_hash_tree_parser(i)?;
+
+            Ok((i, fork(left, right)))
+        }
+        (Some(1), _) => Err(CborError::MalformedHashTree(String::from(
+            "Missing child nodes for Fork node",
+        ))),
+
+        (Some(2), 3) => {
+            let (i, data) = borrowed_byte_string(i, "Missing ByteString for Labelled node")?;
+            let (i, child_node) = borrowed_hash_tree_parser(i)?;
+
+            Ok((i, label(data, child_node)))
+        }
+        (Some(2), _) => Err(CborError::MalformedHashTree(String::from(
+            "Missing ByteString or child node for Labelled node",
+        ))),
+
+        (Some(3), 2) => {
+            let (i, data) = borrowed_byte_string(i, "Missing ByteString for Leaf node")?;
+
+            Ok((i, leaf(data)))
+        }
+        (Some(3), _) => Err(CborError::MalformedHashTree(String::from(
+            "Missing ByteString for Leaf node",
+        ))),
+
+        (Some(4), 2) => {
+            let (i, data) = borrowed_byte_string(i, "Missing ByteString for Pruned node")?;
+            let digest: Hash =
+                TryFrom::<&[u8]>::try_from(data).map_err(CborError::IncorrectPrunedDataLength)?;
+
+            Ok((i, pruned(digest)))
+        }
+        (Some(4), _) => Err(CborError::MalformedHashTree(String::from(
+            "Missing ByteString for Pruned node",
+        ))),
+
+        _ => Err(CborError::MalformedHashTree(String::from(
+            "Expected Hash Tree cbor tag",
+        ))),
+    }
+}
+
+fn borrowed_byte_string<'a>(i: &'a [u8], error: &str) -> CborResult<(&'a [u8], &'a [u8])> {
+    match peek_cbor_type(i) {
+        Ok((i, 2 | 3)) => {
+            let (i, data_len) =
+                extract_cbor_value(i).map_err(|e| CborError::MalformedCbor(e.to_string()))?;
+            let data_len = data_len.to_usize();
+
+            if i.len() < data_len {
+                return Err(CborError::UnexpectedEndOfInput);
+            }
+
+            Ok((&i[data_len..], &i[..data_len]))
+        }
+        _ => Err(CborError::MalformedHashTree(String::from(error))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -526,4 +666,92 @@ mod tests {
             LookupResult::Unknown
         );
     }
+
+    #[test]
+    fn borrowed_tree_matches_owned_tree() {
+        let original_tree: HashTree = fork(
+            fork(
+                label(
+                    "a",
+                    fork(
+                        fork(label("x", leaf(b"hello".to_vec())), empty()),
+                        label("y", leaf(b"world".to_vec())),
+                    ),
+                ),
+                label("b", pruned([1; 32])),
+            ),
+            fork(label("c", empty()), label("d", leaf(vec![7; 300]))),
+        );
+        let tree_cbor = cbor_encode(&original_tree);
+
+        let owned_tree = HashTree::from_cbor(&tree_cbor).expect("Failed to deserialize tree");
+        let tree = parse_borrowed_hash_tree(&tree_cbor).expect("Failed to deserialize tree");
+
+        assert_eq!(tree.digest(), owned_tree.digest());
+        assert_eq!(
+            tree.list_paths(),
+            vec![
+                vec![Label::from(b"a".as_slice()), Label::from(b"x".as_slice())],
+                vec![Label::from(b"a".as_slice()), Label::from(b"y".as_slice())],
+                vec![Label::from(b"d".as_slice())],
+            ]
+        );
+
+        let LookupResult::Found(value) = tree.lookup_path(["d"]) else {
+            panic!("Expected leaf to be found");
+        };
+        assert_eq!(value, &[7; 300]);
+        assert!(tree_cbor.as_ptr_range().contains(&value.as_ptr()));
+    }
+
+    #[test]
+    fn borrowed_tree_rejects_malformed_cbor() {
+        let tree_cbor = cbor_encode(&fork::<Vec<u8>>(empty(), leaf(vec![1, 2, 3])));
+
+        assert!(matches!(
+            parse_borrowed_hash_tree(&tree_cbor[..tree_cbor.len() - 1]),
+            Err(CborError::UnexpectedEndOfInput)
+        ));
+        assert!(matches!(
+            parse_borrowed_hash_tree(&[tree_cbor.as_slice(), &[0]].concat()),
+            Err(CborError::MalformedCbor(_))
+        ));
+        assert!(matches!(
+            parse_borrowed_hash_tree(&cbor_encode(&pruned::<Vec<u8>, _>([1; 32]))[..6]),
+            Err(CborError::MalformedCbor(_))
+        ));
+        assert!(matches!(
+            parse_borrowed_hash_tree(&cbor_encode(&vec![5u8, 1])),
+            Err(CborError::MalformedHashTree(_))
+        ));
+    }
+
+    #[test]
+    fn only_accepts_self_describe_tag() {
+        let tree: HashTree = fork(label("a", leaf(vec![1, 2, 3])), empty());
+        let tree_cbor = cbor_encode(&tree);
+        // cbor_encode prefixes the tree with the self-describe tag
+        assert_eq!(tree_cbor[..3], [0xd9, 0xd9, 0xf7]);
+        let untagged_cbor = &tree_cbor[3..];
+        let other_tag_cbor = [&[0xd9, 0xd9, 0xf8][..], untagged_cbor].concat();
+        let nested_tag_cbor = [&tree_cbor[..3], &tree_cbor].concat();
+
+        assert_eq!(HashTree::from_cbor(&tree_cbor).unwrap(), tree);
+        assert_eq!(HashTree::from_cbor(untagged_cbor).unwrap(), tree);
+        assert_eq!(
+            parse_borrowed_hash_tree(untagged_cbor).unwrap().digest(),
+            tree.digest()
+        );
+
+        for cbor in [other_tag_cbor, nested_tag_cbor] {
+            assert!(matches!(
+                parse_borrowed_hash_tree(&cbor),
+                Err(CborError::MalformedHashTree(_))
+            ));
+            assert!(matches!(
+                HashTree::from_cbor(&cbor),
+                Err(CborError::MalformedHashTree(_))
+            ));
+        }
+    }
 }
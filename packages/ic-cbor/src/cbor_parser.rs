@@ -28,7 +28,7 @@ pub enum CborUnsignedInt {
 }
 
 impl CborUnsignedInt {
-    fn to_usize(self) -> usize {
+    pub(crate) fn to_usize(self) -> usize {
         match self {
             CborUnsignedInt::UInt8(v) => v as usize,
             CborUnsignedInt::UInt16(v) => v as usize,
@@ -51,7 +51,7 @@ impl CborUnsignedInt {
         }
     }
 
-    fn to_u8(self) -> Result<u8, String> {
+    pub(crate) fn to_u8(self) -> Result<u8, String> {
         Ok(match self {
             CborUnsignedInt::UInt8(n) => n,
             _ => return Err(String::from("Expected u8")),
@@ -93,7 +93,7 @@ fn extract_cbor_type(i: &[u8]) -> IResult<&[u8], u8> {
     map(be_u8, get_cbor_type)(i)
 }
 
-fn peek_cbor_type(i: &[u8]) -> IResult<&[u8], u8> {
+pub(crate) fn peek_cbor_type(i: &[u8]) -> IResult<&[u8], u8> {
     peek(extract_cbor_type)(i)
 }
 
@@ -108,7 +108,7 @@ fn extract_cbor_info(i: &[u8]) -> IResult<&[u8], u8> {
     map(be_u8, get_cbor_info)(i)
 }
 
-fn extract_cbor_value(i: &[u8]) -> IResult<&[u8], CborUnsignedInt> {
+pub(crate) fn extract_cbor_value(i: &[u8]) -> IResult<&[u8], CborUnsignedInt> {
     let (i, cbor_info) = extract_cbor_info(i)?;
 
     match cbor_info {
@@ -5,6 +5,7 @@ use sha2::Digest;
 use std::{
     borrow::{Borrow, Cow},
     fmt,
+    ops::{Bound, RangeBounds},
 };
 
 /// Sha256 Digest: 32 bytes
@@ -168,6 +169,53 @@ pub enum SubtreeLookupResult<Storage: AsRef<[u8]>> {
     Found(HashTree<Storage>),
 }
 
+/// A result of verifying that a path is absent from a tree.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AbsenceResult {
+    /// The path is guaranteed to be absent in the original state tree.
+    Absent,
+
+    /// This partial view does not include information about this path, and the original
+    /// tree may or may not include a subtree at this path.
+    Unknown,
+
+    /// The path is present in the original state tree, either as a leaf or as a subtree.
+    Present,
+}
+
+/// A result of looking up all the labels in a range.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RangeLookupResult<Storage: AsRef<[u8]>> {
+    /// The subtree at the provided path is guaranteed to be absent in the original state tree.
+    Absent,
+
+    /// This partial view does not include information about this path, or some labels in the range
+    /// may have been pruned from it.
+    Unknown,
+
+    /// The labels in the range together with their subtrees, in ascending order. The original state
+    /// tree is guaranteed not to contain any other labels in the range at the provided path.
+    Found(Vec<(Label<Storage>, HashTree<Storage>)>),
+}
+
+/// A difference between two hash trees, see [HashTree::diff].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum HashTreeChange<Storage: AsRef<[u8]>> {
+    /// The path is only present in the new tree.
+    Added(Vec<Label<Storage>>),
+
+    /// The path is only present in the old tree.
+    Removed(Vec<Label<Storage>>),
+
+    /// The path is present in both trees, but its leaf differs, or it is a leaf in one of the trees
+    /// and a subtree in the other one.
+    Changed(Vec<Label<Storage>>),
+
+    /// The subtree at the path differs, but parts of it are pruned from one of the trees, so the
+    /// paths below it that were added, removed or changed are not known.
+    Unknown(Vec<Label<Storage>>),
+}
+
 /// A HashTree representing a full tree.
 #[derive(Clone, PartialEq, Eq)]
 pub struct HashTree<Storage: AsRef<[u8]>> {
@@ -199,6 +247,63 @@ impl<Storage: AsRef<[u8]>> HashTree<Storage> {
     {
         self.root.lookup_path(&mut path.into_iter())
     }
+
+    /// Given a (verified) tree, the client can check that there is no leaf or subtree at a given path,
+    /// which is a sequence of labels (blobs).
+    ///
+    /// [AbsenceResult::Absent] is only returned if the tree proves that the path is absent, for example
+    /// because it is a witness produced by [RbTree::absence_proof](crate::RbTree::absence_proof). If the
+    /// tree does not contain enough information to decide, [AbsenceResult::Unknown] is returned.
+    pub fn verify_absent<P>(&self, path: P) -> AbsenceResult
+    where
+        P: IntoIterator,
+        P::Item: AsRef<[u8]>,
+    {
+        let mut node = &self.root;
+        for segment in path {
+            match node.lookup_label(segment.as_ref()) {
+                LookupLabelResult::Found(child) => node = child,
+                LookupLabelResult::Unknown => return AbsenceResult::Unknown,
+                LookupLabelResult::Absent
+                | LookupLabelResult::Greater
+                | LookupLabelResult::Less => return AbsenceResult::Absent,
+            }
+        }
+
+        AbsenceResult::Present
+    }
+
+    /// Returns the number of bytes of the CBOR encoding of this tree, as it is serialized in
+    /// certificates and in the `IC-Certificate` header, without encoding it.
+    ///
+    /// This does not include the self-describing CBOR tag, nor the base64 encoding that is applied
+    /// to the tree in the `IC-Certificate` header.
+    pub fn encoded_size(&self) -> usize {
+        self.root.encoded_size()
+    }
+
+    /// Folds the tree bottom-up using the provided visitor, see [HashTreeVisitor].
+    pub fn fold<V: HashTreeVisitor<Storage>>(&self, visitor: &mut V) -> V::Output {
+        self.root.fold(visitor)
+    }
+
+    /// Creates an iterator over the leaves of the tree together with their paths, in the order
+    /// in which they appear in the tree.
+    pub fn leaves(&self) -> Leaves<'_, Storage> {
+        Leaves {
+            stack: vec![(0, &self.root)],
+            path: vec![],
+        }
+    }
+
+    /// Collapses every fork whose children are both pruned into a single pruned node, which
+    /// reduces the size of the tree without changing its digest or the paths that can be looked up
+    /// in it.
+    pub fn compact(self) -> Self {
+        HashTree {
+            root: self.root.compact(),
+        }
+    }
 }
 
 impl<Storage: Clone + AsRef<[u8]>> HashTree<Storage> {
@@ -213,9 +318,110 @@ impl<Storage: Clone + AsRef<[u8]>> HashTree<Storage> {
             .lookup_subtree(&mut path.into_iter().map(|v| v.borrow()))
     }
 
+    /// Given a (verified) tree, the client can fetch all the labels in the given range of the subtree
+    /// at a given path, which is a sequence of labels (blobs).
+    ///
+    /// [RangeLookupResult::Found] is only returned if the tree proves that there are no other labels in
+    /// the range, for example because it is a page produced by
+    /// [NestedTree::paginated_witness](crate::NestedTree::paginated_witness). The range for such a page
+    /// starts at the cursor that was used to request it and ends before the cursor for the next page.
+    pub fn lookup_range<P, R>(&self, path: P, range: R) -> RangeLookupResult<Storage>
+    where
+        P: IntoIterator,
+        P::Item: AsRef<[u8]>,
+        R: RangeBounds<[u8]>,
+    {
+        let mut node = &self.root;
+        for segment in path {
+            match node.lookup_label(segment.as_ref()) {
+                LookupLabelResult::Found(child) => node = child,
+                LookupLabelResult::Unknown => return RangeLookupResult::Unknown,
+                LookupLabelResult::Absent
+                | LookupLabelResult::Greater
+                | LookupLabelResult::Less => return RangeLookupResult::Absent,
+            }
+        }
+
+        node.lookup_range(&range)
+    }
+
+    /// Compares the tree to a newer version of it and returns the paths that differ between them,
+    /// in ascending order.
+    ///
+    /// Subtrees with equal hashes are skipped without being traversed, and a changed subtree is only
+    /// reported at its top-most differing path, so adding a subtree results in a single
+    /// [HashTreeChange::Added] for its label. Subtrees that differ but are pruned from either tree
+    /// are reported as [HashTreeChange::Unknown].
+    pub fn diff(&self, other: &Self) -> Vec<HashTreeChange<Storage>> {
+        let mut changes = vec![];
+        self.root.diff(&other.root, &mut vec![], &mut changes);
+
+        changes
+    }
+
     /// List all paths in the [HashTree]
     pub fn list_paths(&self) -> Vec<Vec<Label<Storage>>> {
-        self.root.list_paths(&vec![])
+        self.leaves()
+            .map(|(path, _)| path.into_iter().cloned().collect())
+            .collect()
+    }
+}
+
+/// A visitor that folds a [HashTree] bottom-up, see [HashTree::fold].
+///
+/// Every node is visited after its children, and the outputs for the children are passed to the
+/// method for their parent. This can be used to render a tree, to count its nodes or to transform
+/// it into another tree.
+pub trait HashTreeVisitor<Storage: AsRef<[u8]>> {
+    /// The output of visiting a node.
+    type Output;
+
+    /// Visits an empty node.
+    fn visit_empty(&mut self) -> Self::Output;
+
+    /// Visits a fork, given the outputs for its left and right children.
+    fn visit_fork(&mut self, left: Self::Output, right: Self::Output) -> Self::Output;
+
+    /// Visits a labeled node, given the output for its subtree.
+    fn visit_labeled(&mut self, label: &Label<Storage>, subtree: Self::Output) -> Self::Output;
+
+    /// Visits a leaf.
+    fn visit_leaf(&mut self, value: &[u8]) -> Self::Output;
+
+    /// Visits a pruned node.
+    fn visit_pruned(&mut self, digest: &Hash) -> Self::Output;
+}
+
+/// Iterator over the leaves of a [HashTree] and their paths, see [HashTree::leaves].
+#[derive(Debug)]
+pub struct Leaves<'a, Storage: AsRef<[u8]>> {
+    /// Nodes that are yet to be visited, together with the length of their path.
+    stack: Vec<(usize, &'a HashTreeNode<Storage>)>,
+    path: Vec<&'a Label<Storage>>,
+}
+
+impl<'a, Storage: AsRef<[u8]>> Iterator for Leaves<'a, Storage> {
+    type Item = (Vec<&'a Label<Storage>>, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((depth, node)) = self.stack.pop() {
+            self.path.truncate(depth);
+
+            match node {
+                HashTreeNode::Fork(nodes) => {
+                    self.stack.push((depth, &nodes.1));
+                    self.stack.push((depth, &nodes.0));
+                }
+                HashTreeNode::Labeled(label, node) => {
+                    self.path.push(label);
+                    self.stack.push((depth + 1, node));
+                }
+                HashTreeNode::Leaf(value) => return Some((self.path.clone(), value.as_ref())),
+                HashTreeNode::Empty() | HashTreeNode::Pruned(_) => {}
+            }
+        }
+
+        None
     }
 }
 
@@ -426,6 +632,72 @@ impl<Storage: AsRef<[u8]>> HashTreeNode<Storage> {
         hasher.finalize().into()
     }
 
+    /// Calculate the size of the CBOR encoding of this node and its children.
+    fn encoded_size(&self) -> usize {
+        // The size of the header of a CBOR data item with the given argument, such as the length
+        // of an array or a byte string.
+        fn header_size(argument: usize) -> usize {
+            match argument {
+                0..=23 => 1,
+                24..=0xff => 2,
+                0x100..=0xffff => 3,
+                0x1_0000..=0xffff_ffff => 5,
+                _ => 9,
+            }
+        }
+
+        fn bytes_size(bytes: &[u8]) -> usize {
+            header_size(bytes.len()) + bytes.len()
+        }
+
+        // Every node is encoded as an array starting with a small integer tag.
+        let (len, contents_size) = match self {
+            HashTreeNode::Empty() => (1, 0),
+            HashTreeNode::Fork(nodes) => (3, nodes.0.encoded_size() + nodes.1.encoded_size()),
+            HashTreeNode::Labeled(label, node) => {
+                (3, bytes_size(label.as_bytes()) + node.encoded_size())
+            }
+            HashTreeNode::Leaf(bytes) => (2, bytes_size(bytes.as_ref())),
+            HashTreeNode::Pruned(digest) => (2, bytes_size(digest)),
+        };
+
+        header_size(len) + 1 + contents_size
+    }
+
+    /// Fold this node and its children bottom-up using the provided visitor.
+    fn fold<V: HashTreeVisitor<Storage>>(&self, visitor: &mut V) -> V::Output {
+        match self {
+            HashTreeNode::Empty() => visitor.visit_empty(),
+            HashTreeNode::Fork(nodes) => {
+                let left = nodes.0.fold(visitor);
+                let right = nodes.1.fold(visitor);
+                visitor.visit_fork(left, right)
+            }
+            HashTreeNode::Labeled(label, node) => {
+                let subtree = node.fold(visitor);
+                visitor.visit_labeled(label, subtree)
+            }
+            HashTreeNode::Leaf(value) => visitor.visit_leaf(value.as_ref()),
+            HashTreeNode::Pruned(digest) => visitor.visit_pruned(digest),
+        }
+    }
+
+    /// Collapse every fork whose children are both pruned into a single pruned node.
+    fn compact(self) -> Self {
+        match self {
+            HashTreeNode::Fork(nodes) => match (nodes.0.compact(), nodes.1.compact()) {
+                (HashTreeNode::Pruned(l), HashTreeNode::Pruned(r)) => {
+                    HashTreeNode::Pruned(fork_hash(&l, &r))
+                }
+                (l, r) => HashTreeNode::Fork(Box::new((l, r))),
+            },
+            HashTreeNode::Labeled(label, node) => {
+                HashTreeNode::Labeled(label, Box::new(node.compact()))
+            }
+            node => node,
+        }
+    }
+
     /// Lookup a single label, returning a reference to the labeled [HashTreeNode] node if found.
     ///
     /// This assumes a sorted hash tree, which is what the spec says the system should
@@ -530,19 +802,153 @@ impl<Storage: Clone + AsRef<[u8]>> HashTreeNode<Storage> {
         }
     }
 
-    fn list_paths(&self, path: &Vec<Label<Storage>>) -> Vec<Vec<Label<Storage>>> {
-        match self {
-            HashTreeNode::Empty() => vec![],
-            HashTreeNode::Fork(nodes) => {
-                [nodes.0.list_paths(path), nodes.1.list_paths(path)].concat()
+    /// Lookup the labels in a range for the current node only. This assumes a sorted hash tree, so
+    /// that any labels hidden by a pruned fork lie between the labels surrounding it.
+    fn lookup_range(&self, range: &impl RangeBounds<[u8]>) -> RangeLookupResult<Storage> {
+        fn flatten<'a, Storage: AsRef<[u8]>>(
+            node: &'a HashTreeNode<Storage>,
+            children: &mut Vec<Option<(&'a Label<Storage>, &'a HashTreeNode<Storage>)>>,
+        ) {
+            match node {
+                HashTreeNode::Fork(nodes) => {
+                    flatten(&nodes.0, children);
+                    flatten(&nodes.1, children);
+                }
+                HashTreeNode::Labeled(l, node) => children.push(Some((l, node))),
+                HashTreeNode::Pruned(_) => children.push(None),
+                HashTreeNode::Empty() | HashTreeNode::Leaf(_) => {}
             }
-            HashTreeNode::Leaf(_) => vec![path.clone()],
-            HashTreeNode::Labeled(l, node) => {
-                let mut path = path.clone();
-                path.push(l.clone());
-                node.list_paths(&path)
+        }
+
+        let mut children = vec![];
+        flatten(self, &mut children);
+
+        for (i, child) in children.iter().enumerate() {
+            if child.is_some() {
+                continue;
+            }
+
+            let before = children[..i].iter().rev().find_map(|child| *child);
+            let after = children[i + 1..].iter().find_map(|child| *child);
+
+            let may_hide_labels_in_range =
+                before.map_or(true, |(l, _)| match range.end_bound() {
+                    Bound::Included(end) | Bound::Excluded(end) => l.as_bytes() < end,
+                    Bound::Unbounded => true,
+                }) && after.map_or(true, |(l, _)| match range.start_bound() {
+                    Bound::Included(start) | Bound::Excluded(start) => l.as_bytes() > start,
+                    Bound::Unbounded => true,
+                });
+
+            if may_hide_labels_in_range {
+                return RangeLookupResult::Unknown;
+            }
+        }
+
+        RangeLookupResult::Found(
+            children
+                .into_iter()
+                .flatten()
+                .filter(|(l, _)| range.contains(l.as_bytes()))
+                .map(|(l, node)| (l.clone(), HashTree { root: node.clone() }))
+                .collect(),
+        )
+    }
+
+    /// Appends the differences between the current node and a newer version of it to `changes`,
+    /// prefixing their paths with `path`.
+    fn diff(
+        &self,
+        other: &Self,
+        path: &mut Vec<Label<Storage>>,
+        changes: &mut Vec<HashTreeChange<Storage>>,
+    ) {
+        use std::cmp::Ordering;
+
+        /// Collects the labeled children of a node, and the hashes of the nodes that may hide
+        /// other labeled children.
+        fn flatten<'a, Storage: AsRef<[u8]>>(
+            node: &'a HashTreeNode<Storage>,
+            labeled: &mut Vec<(&'a Label<Storage>, &'a HashTreeNode<Storage>)>,
+            pruned: &mut Vec<Hash>,
+        ) {
+            match node {
+                HashTreeNode::Fork(nodes) => {
+                    flatten(&nodes.0, labeled, pruned);
+                    flatten(&nodes.1, labeled, pruned);
+                }
+                HashTreeNode::Labeled(l, node) => labeled.push((l, node)),
+                HashTreeNode::Pruned(_) | HashTreeNode::Leaf(_) => pruned.push(node.digest()),
+                HashTreeNode::Empty() => {}
+            }
+        }
+
+        if self.digest() == other.digest() {
+            return;
+        }
+
+        match (self, other) {
+            (HashTreeNode::Pruned(_), _) | (_, HashTreeNode::Pruned(_)) => {
+                changes.push(HashTreeChange::Unknown(path.clone()))
+            }
+            (HashTreeNode::Leaf(_), _) | (_, HashTreeNode::Leaf(_)) => {
+                changes.push(HashTreeChange::Changed(path.clone()))
+            }
+            _ => {
+                let (mut old_labeled, mut old_pruned) = (vec![], vec![]);
+                flatten(self, &mut old_labeled, &mut old_pruned);
+                let (mut new_labeled, mut new_pruned) = (vec![], vec![]);
+                flatten(other, &mut new_labeled, &mut new_pruned);
+
+                old_labeled.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+                new_labeled.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+                old_pruned.sort_unstable();
+                new_pruned.sort_unstable();
+
+                // labels that are only present in one of the trees may be hidden by
+                // the pruned nodes of the other one, unless those are the same
+                let pruned_differ = old_pruned != new_pruned;
+                if pruned_differ {
+                    changes.push(HashTreeChange::Unknown(path.clone()));
+                }
+
+                let mut old_labeled = old_labeled.into_iter().peekable();
+                let mut new_labeled = new_labeled.into_iter().peekable();
+                loop {
+                    let order = match (old_labeled.peek(), new_labeled.peek()) {
+                        (None, None) => break,
+                        (Some(_), None) => Ordering::Less,
+                        (None, Some(_)) => Ordering::Greater,
+                        (Some((old, _)), Some((new, _))) => old.as_bytes().cmp(new.as_bytes()),
+                    };
+
+                    match order {
+                        Ordering::Less => {
+                            let (l, _) = old_labeled.next().unwrap();
+                            if !pruned_differ {
+                                path.push(l.clone());
+                                changes.push(HashTreeChange::Removed(path.clone()));
+                                path.pop();
+                            }
+                        }
+                        Ordering::Greater => {
+                            let (l, _) = new_labeled.next().unwrap();
+                            if !pruned_differ {
+                                path.push(l.clone());
+                                changes.push(HashTreeChange::Added(path.clone()));
+                                path.pop();
+                            }
+                        }
+                        Ordering::Equal => {
+                            let (l, old) = old_labeled.next().unwrap();
+                            let (_, new) = new_labeled.next().unwrap();
+                            path.push(l.clone());
+                            old.diff(new, path, changes);
+                            path.pop();
+                        }
+                    }
+                }
             }
-            HashTreeNode::Pruned(_) => vec![],
         }
     }
 }
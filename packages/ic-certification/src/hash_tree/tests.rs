@@ -1,8 +1,10 @@
 #![cfg(test)]
 
 use crate::hash_tree::{
-    empty, fork, label, leaf, pruned, pruned_from_hex, HashTree, LookupResult, SubtreeLookupResult,
+    empty, fork, fork_hash, label, leaf, pruned, pruned_from_hex, AbsenceResult, HashTree,
+    HashTreeChange, HashTreeVisitor, Label, LookupResult, RangeLookupResult, SubtreeLookupResult,
 };
+use std::ops::Bound;
 
 fn lookup_path<P: AsRef<[&'static str]>>(tree: &HashTree<Vec<u8>>, path: P) -> LookupResult {
     tree.lookup_path(path.as_ref().iter().map(|s| s.as_bytes()))
@@ -506,3 +508,356 @@ fn can_lookup_subtrees_8() {
     assert_eq!(lookup_subtree(&tree, ["label 5"]), Found(empty()));
     assert_eq!(lookup_subtree(&tree, ["label 6"]), Unknown);
 }
+
+#[test]
+fn can_verify_absent_paths() {
+    let tree: HashTree<Vec<u8>> = fork(
+        label("label 1", leaf(vec![1, 2, 3])),
+        fork(pruned([1; 32]), label("label 4", label("label 5", empty()))),
+    );
+
+    assert_eq!(tree.verify_absent(["label 0"]), AbsenceResult::Absent);
+    assert_eq!(tree.verify_absent(["label 1"]), AbsenceResult::Present);
+    assert_eq!(
+        tree.verify_absent(["label 1", "label 2"]),
+        AbsenceResult::Absent
+    );
+    assert_eq!(tree.verify_absent(["label 2"]), AbsenceResult::Unknown);
+    assert_eq!(
+        tree.verify_absent(["label 4", "label 5"]),
+        AbsenceResult::Present
+    );
+    assert_eq!(
+        tree.verify_absent(["label 4", "label 5", "label 6"]),
+        AbsenceResult::Absent
+    );
+    assert_eq!(tree.verify_absent(["label 6"]), AbsenceResult::Absent);
+    assert_eq!(
+        tree.verify_absent(Vec::<&str>::new()),
+        AbsenceResult::Present
+    );
+}
+
+#[test]
+fn can_lookup_ranges() {
+    use std::ops::Bound::*;
+    use RangeLookupResult::*;
+
+    let tree: HashTree<Vec<u8>> = label(
+        "todos",
+        fork(
+            fork(pruned([0; 32]), label("b", leaf(vec![2]))),
+            fork(
+                label("c", leaf(vec![3])),
+                fork(label("e", pruned([1; 32])), pruned([2; 32])),
+            ),
+        ),
+    );
+
+    let lookup_range = |range: (Bound<&[u8]>, Bound<&[u8]>)| tree.lookup_range(["todos"], range);
+
+    assert_eq!(
+        lookup_range((Included(b"b"), Included(b"e"))),
+        Found(vec![
+            ("b".into(), leaf(vec![2])),
+            ("c".into(), leaf(vec![3])),
+            ("e".into(), pruned([1; 32])),
+        ])
+    );
+    assert_eq!(
+        lookup_range((Excluded(b"b"), Excluded(b"e"))),
+        Found(vec![("c".into(), leaf(vec![3]))])
+    );
+    assert_eq!(lookup_range((Included(b"a"), Included(b"c"))), Unknown);
+    assert_eq!(lookup_range((Excluded(b"b"), Unbounded)), Unknown);
+    assert_eq!(lookup_range((Unbounded, Unbounded)), Unknown);
+    assert_eq!(
+        tree.lookup_range(["todos", "b"], (Unbounded, Unbounded)),
+        Found(vec![])
+    );
+    assert_eq!(tree.lookup_range(["todos", "d"], ..), Absent);
+    assert_eq!(tree.lookup_range(["todos", "f"], ..), Unknown);
+    assert_eq!(tree.lookup_range(["users"], ..), Absent);
+    assert_eq!(empty::<Vec<u8>>().lookup_range(["todos"], ..), Absent);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn encoded_size_matches_cbor_encoding() {
+    let trees: Vec<HashTree<Vec<u8>>> = vec![
+        empty(),
+        leaf(vec![]),
+        leaf(vec![1; 23]),
+        leaf(vec![1; 24]),
+        leaf(vec![1; 255]),
+        leaf(vec![1; 256]),
+        leaf(vec![1; 70_000]),
+        pruned([0; 32]),
+        label(vec![2; 300], leaf(*b"hello")),
+        fork(
+            fork(label("a", pruned([1; 32])), label("b", empty())),
+            fork(pruned([2; 32]), label("c", leaf(*b"world"))),
+        ),
+    ];
+
+    for tree in trees {
+        assert_eq!(
+            tree.encoded_size(),
+            serde_cbor::to_vec(&tree).unwrap().len(),
+            "tree: {:?}",
+            tree
+        );
+    }
+}
+
+#[test]
+fn compact_collapses_pruned_forks() {
+    let tree: HashTree<Vec<u8>> = fork(
+        fork(
+            fork(pruned([0; 32]), pruned([1; 32])),
+            label(
+                "a",
+                fork(pruned([2; 32]), fork(pruned([3; 32]), pruned([4; 32]))),
+            ),
+        ),
+        fork(label("b", leaf(*b"hello")), pruned([5; 32])),
+    );
+
+    let compacted = tree.clone().compact();
+
+    assert_eq!(compacted.digest(), tree.digest());
+    assert!(compacted.encoded_size() < tree.encoded_size());
+    assert_eq!(
+        compacted,
+        fork(
+            fork(
+                pruned(fork_hash(&[0; 32], &[1; 32])),
+                label(
+                    "a",
+                    pruned(fork_hash(&[2; 32], &fork_hash(&[3; 32], &[4; 32]))),
+                ),
+            ),
+            fork(label("b", leaf(*b"hello")), pruned([5; 32])),
+        )
+    );
+    assert_eq!(compacted.lookup_path(["b"]), LookupResult::Found(b"hello"));
+    assert_eq!(compacted.lookup_path(["a"]), LookupResult::Unknown);
+}
+
+fn visitor_test_tree() -> HashTree<Vec<u8>> {
+    fork(
+        fork(
+            label(
+                "a",
+                fork(label("x", leaf(*b"hello")), label("y", leaf(*b"world"))),
+            ),
+            label("b", pruned([1; 32])),
+        ),
+        fork(
+            pruned([2; 32]),
+            fork(label("c", empty()), label("d", leaf(*b"morning"))),
+        ),
+    )
+}
+
+#[test]
+fn can_iterate_leaves() {
+    let tree = visitor_test_tree();
+
+    let leaves: Vec<(Vec<&[u8]>, &[u8])> = tree
+        .leaves()
+        .map(|(path, value)| (path.into_iter().map(|l| l.as_bytes()).collect(), value))
+        .collect();
+
+    assert_eq!(
+        leaves,
+        vec![
+            (vec![b"a" as &[u8], b"x"], b"hello" as &[u8]),
+            (vec![b"a", b"y"], b"world"),
+            (vec![b"d"], b"morning"),
+        ]
+    );
+    assert_eq!(
+        tree.list_paths(),
+        vec![
+            vec![label_of("a"), label_of("x")],
+            vec![label_of("a"), label_of("y")],
+            vec![label_of("d")],
+        ]
+    );
+    assert_eq!(
+        leaf::<Vec<u8>, _>(*b"root").leaves().next(),
+        Some((vec![], b"root" as &[u8]))
+    );
+    assert_eq!(empty::<Vec<u8>>().leaves().next(), None);
+}
+
+fn label_of(l: &str) -> Label<Vec<u8>> {
+    l.into()
+}
+
+#[test]
+fn can_fold_trees() {
+    struct CountPruned;
+
+    impl HashTreeVisitor<Vec<u8>> for CountPruned {
+        type Output = usize;
+
+        fn visit_empty(&mut self) -> usize {
+            0
+        }
+
+        fn visit_fork(&mut self, left: usize, right: usize) -> usize {
+            left + right
+        }
+
+        fn visit_labeled(&mut self, _label: &Label<Vec<u8>>, subtree: usize) -> usize {
+            subtree
+        }
+
+        fn visit_leaf(&mut self, _value: &[u8]) -> usize {
+            0
+        }
+
+        fn visit_pruned(&mut self, _digest: &[u8; 32]) -> usize {
+            1
+        }
+    }
+
+    struct UppercaseLabels;
+
+    impl HashTreeVisitor<Vec<u8>> for UppercaseLabels {
+        type Output = HashTree<Vec<u8>>;
+
+        fn visit_empty(&mut self) -> Self::Output {
+            empty()
+        }
+
+        fn visit_fork(&mut self, left: Self::Output, right: Self::Output) -> Self::Output {
+            fork(left, right)
+        }
+
+        fn visit_labeled(&mut self, l: &Label<Vec<u8>>, subtree: Self::Output) -> Self::Output {
+            label(l.as_bytes().to_ascii_uppercase(), subtree)
+        }
+
+        fn visit_leaf(&mut self, value: &[u8]) -> Self::Output {
+            leaf(value)
+        }
+
+        fn visit_pruned(&mut self, digest: &[u8; 32]) -> Self::Output {
+            pruned(*digest)
+        }
+    }
+
+    let tree = visitor_test_tree();
+
+    assert_eq!(tree.fold(&mut CountPruned), 2);
+
+    let uppercase = tree.fold(&mut UppercaseLabels);
+    assert_eq!(
+        uppercase.lookup_path(["A", "Y"]),
+        LookupResult::Found(b"world")
+    );
+    assert_eq!(uppercase.lookup_path(["a", "y"]), LookupResult::Absent);
+    assert_eq!(uppercase.leaves().count(), 3);
+}
+
+#[test]
+fn can_diff_trees() {
+    fn path(labels: &[&str]) -> Vec<Label<Vec<u8>>> {
+        labels.iter().map(|l| Label::from(*l)).collect()
+    }
+
+    let old: HashTree<Vec<u8>> = fork(
+        label(
+            "a",
+            fork(label("x", leaf(b"hello")), label("y", leaf(b"world"))),
+        ),
+        fork(label("b", leaf(b"good")), label("f", leaf(b"leaf"))),
+    );
+    let new: HashTree<Vec<u8>> = fork(
+        fork(
+            label(
+                "a",
+                fork(label("x", leaf(b"hello")), label("y", leaf(b"there"))),
+            ),
+            label("c", leaf(b"new")),
+        ),
+        fork(
+            label("d", label("e", leaf(b"deep"))),
+            label("f", label("g", leaf(b"leaf"))),
+        ),
+    );
+
+    assert_eq!(old.diff(&old), vec![]);
+    assert_eq!(
+        old.diff(&new),
+        vec![
+            HashTreeChange::Changed(path(&["a", "y"])),
+            HashTreeChange::Removed(path(&["b"])),
+            HashTreeChange::Added(path(&["c"])),
+            HashTreeChange::Added(path(&["d"])),
+            HashTreeChange::Changed(path(&["f"])),
+        ]
+    );
+    assert_eq!(
+        new.diff(&old),
+        vec![
+            HashTreeChange::Changed(path(&["a", "y"])),
+            HashTreeChange::Added(path(&["b"])),
+            HashTreeChange::Removed(path(&["c"])),
+            HashTreeChange::Removed(path(&["d"])),
+            HashTreeChange::Changed(path(&["f"])),
+        ]
+    );
+    assert_eq!(
+        empty().diff(&old),
+        vec![
+            HashTreeChange::Added(path(&["a"])),
+            HashTreeChange::Added(path(&["b"])),
+            HashTreeChange::Added(path(&["f"])),
+        ]
+    );
+}
+
+#[test]
+fn can_diff_pruned_trees() {
+    fn path(labels: &[&str]) -> Vec<Label<Vec<u8>>> {
+        labels.iter().map(|l| Label::from(*l)).collect()
+    }
+
+    let full: HashTree<Vec<u8>> = fork(
+        label(
+            "a",
+            fork(label("x", leaf(b"hello")), label("y", leaf(b"world"))),
+        ),
+        label("b", leaf(b"good")),
+    );
+    let a_digest = label::<Vec<u8>, _, _>("a", leaf(b"other")).digest();
+
+    let pruned_subtree = fork(label("a", pruned([0u8; 32])), label("b", leaf(b"good")));
+    assert_eq!(
+        pruned_subtree.diff(&full),
+        vec![HashTreeChange::Unknown(path(&["a"]))]
+    );
+
+    let pruned_label = fork(pruned(a_digest), label("b", leaf(b"bad")));
+    assert_eq!(
+        pruned_label.diff(&full),
+        vec![
+            HashTreeChange::Unknown(path(&[])),
+            HashTreeChange::Changed(path(&["b"])),
+        ]
+    );
+
+    let same_pruned_label = fork(pruned(a_digest), label("c", leaf(b"new")));
+    assert_eq!(
+        pruned_label.diff(&same_pruned_label),
+        vec![
+            HashTreeChange::Removed(path(&["b"])),
+            HashTreeChange::Added(path(&["c"])),
+        ]
+    );
+    assert_eq!(pruned_label.diff(&pruned(pruned_label.digest())), vec![]);
+}
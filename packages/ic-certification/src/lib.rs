@@ -24,6 +24,10 @@ pub type HashTreeNode = hash_tree::HashTreeNode<Vec<u8>>;
 pub type Label = hash_tree::Label<Vec<u8>>;
 /// A result of looking up for a subtree.
 pub type SubtreeLookupResult = hash_tree::SubtreeLookupResult<Vec<u8>>;
+/// A result of looking up all the labels in a range.
+pub type RangeLookupResult = hash_tree::RangeLookupResult<Vec<u8>>;
+/// A difference between two hash trees.
+pub type HashTreeChange = hash_tree::HashTreeChange<Vec<u8>>;
 
 /// A `Delegation` as defined in <https://internetcomputer.org/docs/current/references/ic-interface-spec/#certification-delegation>
 pub type Delegation = certificate::Delegation<Vec<u8>>;
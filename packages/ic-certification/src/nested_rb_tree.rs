@@ -1,4 +1,5 @@
-use crate::{empty, fork, labeled, leaf, pruned, AsHashTree, Hash, HashTree, HashTreeNode, RbTree};
+use crate::{AbsenceResult, AsHashTree, Hash, HashTree, HashTreeNode, Label, RbTree, RbTreeChange};
+use std::collections::BTreeMap;
 
 pub trait NestedTreeKeyRequirements: Clone + AsRef<[u8]> + 'static {}
 pub trait NestedTreeValueRequirements: AsHashTree + 'static {}
@@ -6,6 +7,7 @@ impl<T> NestedTreeKeyRequirements for T where T: Clone + AsRef<[u8]> + 'static {
 impl<T> NestedTreeValueRequirements for T where T: AsHashTree + 'static {}
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NestedTree<K: NestedTreeKeyRequirements, V: NestedTreeValueRequirements> {
     Leaf(V),
     Nested(RbTree<K, NestedTree<K, V>>),
@@ -50,6 +52,20 @@ impl<K: NestedTreeKeyRequirements, V: NestedTreeValueRequirements> NestedTree<K,
         }
     }
 
+    /// Returns the leaf or subtree at the specified path
+    pub fn get_subtree(&self, path: &[K]) -> Option<&NestedTree<K, V>> {
+        if let Some(key) = path.first() {
+            match self {
+                NestedTree::Leaf(_) => None,
+                NestedTree::Nested(tree) => tree
+                    .get(key.as_ref())
+                    .and_then(|child| child.get_subtree(&path[1..])),
+            }
+        } else {
+            Some(self)
+        }
+    }
+
     /// Returns true if there is a leaf at the specified path
     pub fn contains_leaf(&self, path: &[K]) -> bool {
         if let Some(key) = path.first() {
@@ -101,12 +117,75 @@ impl<K: NestedTreeKeyRequirements, V: NestedTreeValueRequirements> NestedTree<K,
         }
     }
 
+    /// Inserts multiple values at the specified paths.
+    ///
+    /// This is equivalent to calling [`insert`](NestedTree::insert) for every entry in order,
+    /// but the hashes of every affected subtree are only recomputed once for the whole batch.
+    /// If the tree is empty, it is built using [`from_sorted`](NestedTree::from_sorted).
+    pub fn insert_many<'p, I>(&mut self, entries: I)
+    where
+        I: IntoIterator<Item = (&'p [K], V)>,
+    {
+        let entries: PathEntries<K, V> = entries.into_iter().collect();
+
+        if self.is_empty() {
+            *self = Self::from_sorted(
+                entries
+                    .into_iter()
+                    .map(|(path, value)| (path.to_vec(), value)),
+            );
+            return;
+        }
+
+        self.insert_entries(entries);
+    }
+
+    fn insert_entries(&mut self, mut entries: PathEntries<K, V>) {
+        // A value inserted at this node overwrites everything that was inserted before it.
+        if let Some(last_leaf) = entries.iter().rposition(|(path, _)| path.is_empty()) {
+            let remaining_entries = entries.split_off(last_leaf + 1);
+            let (_, value) = entries.pop().expect("the leaf entry was not split off");
+
+            *self = NestedTree::Leaf(value);
+            entries = remaining_entries;
+        }
+
+        if entries.is_empty() {
+            return;
+        }
+        if let NestedTree::Leaf(_) = self {
+            *self = NestedTree::default();
+        }
+        let NestedTree::Nested(tree) = self else {
+            unreachable!("leaves have been replaced with subtrees");
+        };
+
+        for (key, child_entries) in group_by_first_segment(entries) {
+            if tree.get(key.as_ref()).is_none() {
+                tree.lazy_insert(key.clone(), NestedTree::default());
+            }
+            tree.lazy_modify(key.as_ref(), |child| child.insert_entries(child_entries));
+        }
+        tree.rehash();
+    }
+
+    /// Deletes the value or subtree at the specified path.
+    ///
+    /// Subtrees that become empty are removed as well, so the tree never contains an empty subtree
+    /// that would prove the presence of a path without any values under it.
     pub fn delete(&mut self, path: &[K]) {
         if let Some(key) = path.first() {
             match self {
                 NestedTree::Leaf(_) => {}
                 NestedTree::Nested(tree) => {
-                    tree.modify(key.as_ref(), |child| child.delete(&path[1..]));
+                    let mut is_child_empty = false;
+                    tree.modify(key.as_ref(), |child| {
+                        child.delete(&path[1..]);
+                        is_child_empty = child.is_empty();
+                    });
+                    if is_child_empty {
+                        tree.delete(key.as_ref());
+                    }
                 }
             }
         } else {
@@ -114,6 +193,201 @@ impl<K: NestedTreeKeyRequirements, V: NestedTreeValueRequirements> NestedTree<K,
         }
     }
 
+    /// Deletes the values or subtrees at multiple paths.
+    ///
+    /// This is equivalent to calling [`delete`](NestedTree::delete) for every path,
+    /// but the hashes of every affected subtree are only recomputed once for the whole batch.
+    pub fn delete_many<'p, I>(&mut self, paths: I)
+    where
+        I: IntoIterator<Item = &'p [K]>,
+    {
+        self.delete_paths(paths.into_iter().map(|path| (path, ())).collect());
+    }
+
+    fn delete_paths(&mut self, paths: PathEntries<K, ()>) {
+        if paths.iter().any(|(path, _)| path.is_empty()) {
+            *self = NestedTree::default();
+            return;
+        }
+
+        let NestedTree::Nested(tree) = self else {
+            return;
+        };
+
+        for (key, child_paths) in group_by_first_segment(paths) {
+            let mut is_child_empty = false;
+            tree.lazy_modify(key.as_ref(), |child| {
+                child.delete_paths(child_paths);
+                is_child_empty = child.is_empty();
+            });
+            if is_child_empty {
+                tree.lazy_delete(key.as_ref());
+            }
+        }
+        tree.rehash();
+    }
+
+    /// Constructs a tree from values and their paths, sorted by path in strictly ascending
+    /// order, in linear time.
+    ///
+    /// The resulting tree has the same root hash as a tree that is built by
+    /// [inserting](NestedTree::insert) the values one at a time. If the paths are not sorted in
+    /// strictly ascending order, the values are inserted one at a time instead.
+    pub fn from_sorted<I>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (Vec<K>, V)>,
+    {
+        let entries: Vec<(Vec<K>, V)> = entries.into_iter().collect();
+
+        let is_sorted = entries.windows(2).all(|pair| {
+            let lhs = pair[0].0.iter().map(AsRef::as_ref);
+            let rhs = pair[1].0.iter().map(AsRef::as_ref);
+
+            lhs.lt(rhs)
+        });
+        if !is_sorted {
+            let (paths, values): (Vec<Vec<K>>, Vec<V>) = entries.into_iter().unzip();
+
+            let mut tree = NestedTree::default();
+            tree.insert_entries(paths.iter().map(Vec::as_slice).zip(values).collect());
+            return tree;
+        }
+
+        Self::build_sorted(entries, 0)
+    }
+
+    fn build_sorted(entries: Vec<(Vec<K>, V)>, depth: usize) -> Self {
+        let mut entries = entries.into_iter().peekable();
+
+        // A value at this node is overwritten by any value that is inserted below it.
+        if let Some((path, _)) = entries.peek() {
+            if path.len() == depth {
+                let (_, value) = entries.next().expect("the leaf entry was peeked");
+                if entries.peek().is_none() {
+                    return NestedTree::Leaf(value);
+                }
+            }
+        }
+
+        let mut children = vec![];
+        let mut group: Vec<(Vec<K>, V)> = vec![];
+        for entry in entries {
+            if let Some((path, _)) = group.first() {
+                if path[depth].as_ref() != entry.0[depth].as_ref() {
+                    let key = path[depth].clone();
+                    children.push((
+                        key,
+                        Self::build_sorted(std::mem::take(&mut group), depth + 1),
+                    ));
+                }
+            }
+            group.push(entry);
+        }
+        if let Some((path, _)) = group.first() {
+            let key = path[depth].clone();
+            children.push((key, Self::build_sorted(group, depth + 1)));
+        }
+
+        NestedTree::Nested(RbTree::from_sorted(children))
+    }
+
+    /// Enumerates all the leaves in the tree together with their paths,
+    /// in ascending lexicographic order of the paths.
+    pub fn for_each_leaf<'a, F>(&'a self, mut f: F)
+    where
+        F: FnMut(&[&'a K], &'a V),
+    {
+        self.visit_leaves(&mut vec![], &mut f)
+    }
+
+    fn visit_leaves<'a, F>(&'a self, path: &mut Vec<&'a K>, f: &mut F)
+    where
+        F: FnMut(&[&'a K], &'a V),
+    {
+        match self {
+            NestedTree::Leaf(value) => f(path, value),
+            NestedTree::Nested(tree) => {
+                for (key, child) in tree.iter() {
+                    path.push(key);
+                    child.visit_leaves(path, f);
+                    path.pop();
+                }
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, NestedTree::Nested(tree) if tree.is_empty())
+    }
+
+    /// Compares the tree to a newer version of it and returns the paths that were added, removed
+    /// or changed, in ascending order.
+    ///
+    /// Subtrees with equal hashes are skipped without being traversed, and an added or removed
+    /// subtree is only reported at its path, not at the paths of its leaves.
+    pub fn diff(&self, other: &Self) -> Vec<NestedTreeChange<K>> {
+        let mut changes = vec![];
+        self.diff_at(other, &mut vec![], &mut changes);
+
+        changes
+    }
+
+    fn diff_at(&self, other: &Self, path: &mut Vec<K>, changes: &mut Vec<NestedTreeChange<K>>) {
+        let (NestedTree::Nested(old), NestedTree::Nested(new)) = (self, other) else {
+            if self.root_hash() != other.root_hash() {
+                changes.push(NestedTreeChange::Changed(path.clone()));
+            }
+            return;
+        };
+
+        for change in old.diff(new) {
+            match change {
+                RbTreeChange::Added(key, _) => {
+                    path.push(key.clone());
+                    changes.push(NestedTreeChange::Added(path.clone()));
+                    path.pop();
+                }
+                RbTreeChange::Removed(key, _) => {
+                    path.push(key.clone());
+                    changes.push(NestedTreeChange::Removed(path.clone()));
+                    path.pop();
+                }
+                RbTreeChange::Changed(key, old, new) => {
+                    path.push(key.clone());
+                    old.diff_at(new, path, changes);
+                    path.pop();
+                }
+            }
+        }
+    }
+
+    /// Constructs a hash tree that acts as a proof that there is no leaf or subtree at the
+    /// specified path, which can be checked using
+    /// [`HashTree::verify_absent`](crate::hash_tree::HashTree::verify_absent).
+    ///
+    /// The proof contains the path up to its longest prefix that is present in the tree, together
+    /// with the keys that are adjacent to the first missing segment, or the leaf that the path
+    /// passes through. Returns `None` if the path is present in the tree.
+    pub fn absence_proof(&self, path: &[K]) -> Option<HashTree> {
+        let (key, rest) = path.split_first()?;
+
+        match self {
+            NestedTree::Leaf(value) => {
+                let proof = value.as_hash_tree();
+
+                matches!(proof.verify_absent(path), AbsenceResult::Absent).then_some(proof)
+            }
+            NestedTree::Nested(tree) => match tree.get(key.as_ref()) {
+                None => tree.absence_proof(key.as_ref()),
+                Some(child) => {
+                    let child_proof = child.absence_proof(rest)?;
+
+                    Some(tree.nested_witness(key.as_ref(), |_| child_proof))
+                }
+            },
+        }
+    }
+
     pub fn witness(&self, path: &[K]) -> HashTree {
         if let Some(key) = path.first() {
             match self {
@@ -126,51 +400,305 @@ impl<K: NestedTreeKeyRequirements, V: NestedTreeValueRequirements> NestedTree<K,
             self.as_hash_tree()
         }
     }
+
+    /// Constructs a hash tree that acts as a proof of all the children of the subtree at the
+    /// specified path whose keys are in the range from `first` to `last`, inclusive. The proof
+    /// contains the children together with their full subtrees.
+    ///
+    /// If there is no subtree at the specified path, this is equivalent to
+    /// [`witness`](NestedTree::witness).
+    pub fn range_witness(&self, path: &[K], first: &[u8], last: &[u8]) -> HashTree {
+        self.subtree_witness(path, |tree| tree.value_range(first, last))
+    }
+
+    /// Constructs a hash tree that acts as a proof of all the children of the subtree at the
+    /// specified path whose keys start with the specified prefix. The proof contains the
+    /// children together with their full subtrees.
+    ///
+    /// If there is no subtree at the specified path, this is equivalent to
+    /// [`witness`](NestedTree::witness).
+    pub fn prefix_witness(&self, path: &[K], prefix: &[u8]) -> HashTree {
+        self.subtree_witness(path, |tree| tree.values_with_prefix(prefix))
+    }
+
+    /// Constructs a hash tree that acts as a proof of a page of at most `limit` children of the
+    /// subtree at the specified path, starting at the first child whose key is greater than or
+    /// equal to `cursor`. The proof contains the children together with their full subtrees.
+    ///
+    /// The returned page also contains the cursor for the next page. A client can check that the
+    /// page is complete using [`HashTree::lookup_range`](crate::hash_tree::HashTree::lookup_range)
+    /// with the range from the requested cursor up to, but excluding, the next one.
+    pub fn paginated_witness(
+        &self,
+        path: &[K],
+        cursor: Option<&[u8]>,
+        limit: usize,
+    ) -> NestedTreePage<K> {
+        let mut next_cursor = None;
+        let witness = self.subtree_witness(path, |tree| {
+            let (witness, next) = tree.value_page(cursor, limit);
+            next_cursor = next.cloned();
+
+            witness
+        });
+
+        NestedTreePage {
+            witness,
+            next_cursor,
+        }
+    }
+
+    fn subtree_witness(
+        &self,
+        path: &[K],
+        f: impl FnOnce(&RbTree<K, NestedTree<K, V>>) -> HashTree,
+    ) -> HashTree {
+        match (path.split_first(), self) {
+            (_, NestedTree::Leaf(value)) => value.as_hash_tree(),
+            (Some((key, rest)), NestedTree::Nested(tree)) => {
+                tree.nested_witness(key.as_ref(), |child| child.subtree_witness(rest, f))
+            }
+            (None, NestedTree::Nested(tree)) => f(tree),
+        }
+    }
+}
+
+/// A page of children of a [NestedTree], see [NestedTree::paginated_witness].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NestedTreePage<K> {
+    /// The witness for the children in this page.
+    pub witness: HashTree,
+
+    /// The cursor for the next page, or `None` if this is the last page.
+    pub next_cursor: Option<K>,
+}
+
+/// A difference between two [NestedTree]s, see [NestedTree::diff].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NestedTreeChange<K> {
+    /// The path is only present in the new tree.
+    Added(Vec<K>),
+
+    /// The path is only present in the old tree.
+    Removed(Vec<K>),
+
+    /// The path is present in both trees, but its leaf differs, or it is a leaf in one of the trees
+    /// and a subtree in the other one.
+    Changed(Vec<K>),
+}
+
+/// Values, or other data, associated with paths in a [NestedTree].
+type PathEntries<'p, K, T> = Vec<(&'p [K], T)>;
+
+/// Groups entries by the first segment of their paths, in order of first appearance,
+/// and strips that segment from the paths.
+fn group_by_first_segment<K: AsRef<[u8]>, T>(
+    entries: PathEntries<K, T>,
+) -> Vec<(&K, PathEntries<K, T>)> {
+    let mut groups: Vec<(&K, PathEntries<K, T>)> = vec![];
+    let mut group_indices: BTreeMap<&[u8], usize> = BTreeMap::new();
+
+    for (path, value) in entries {
+        let (key, rest) = path
+            .split_first()
+            .expect("grouped paths have at least one segment");
+        let index = *group_indices.entry(key.as_ref()).or_insert_with(|| {
+            groups.push((key, vec![]));
+            groups.len() - 1
+        });
+
+        groups[index].1.push((rest, value));
+    }
+
+    groups
+}
+
+/// The reason why two [HashTree]s could not be [merged](try_merge_hash_trees), together with the path of labels
+/// from the root of the trees to the node where they diverged.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum MergeHashTreesError {
+    /// One of the trees contains a pruned node at a position where the other tree contains a node
+    /// with a different hash, which is either another pruned node or an expanded subtree.
+    #[error(
+        "inconsistent hashes at {}: {} != {}",
+        display_path(path),
+        hex::encode(lhs),
+        hex::encode(rhs)
+    )]
+    InconsistentHashes {
+        /// The path of labels to the nodes.
+        path: Vec<Label>,
+        /// The hash of the node in the left tree.
+        lhs: Hash,
+        /// The hash of the node in the right tree.
+        rhs: Hash,
+    },
+
+    /// Both trees contain a labeled node at the same position, but with different labels.
+    #[error(
+        "inconsistent hash tree labels at {}: {lhs} != {rhs}",
+        display_path(path)
+    )]
+    InconsistentLabels {
+        /// The path of labels to the labeled nodes.
+        path: Vec<Label>,
+        /// The label of the node in the left tree.
+        lhs: Label,
+        /// The label of the node in the right tree.
+        rhs: Label,
+    },
+
+    /// Both trees contain a leaf at the same position, but with different values.
+    #[error(
+        "inconsistent leaves at {}: {} != {}",
+        display_path(path),
+        hex::encode(lhs),
+        hex::encode(rhs)
+    )]
+    InconsistentLeaves {
+        /// The path of labels to the leaves.
+        path: Vec<Label>,
+        /// The value of the leaf in the left tree.
+        lhs: Vec<u8>,
+        /// The value of the leaf in the right tree.
+        rhs: Vec<u8>,
+    },
+
+    /// The trees contain different kinds of nodes at the same position, neither of which is pruned or empty.
+    #[error(
+        "inconsistent tree structure at {}: {lhs} != {rhs}",
+        display_path(path)
+    )]
+    InconsistentStructure {
+        /// The path of labels to the nodes.
+        path: Vec<Label>,
+        /// The kind of the node in the left tree.
+        lhs: &'static str,
+        /// The kind of the node in the right tree.
+        rhs: &'static str,
+    },
+}
+
+fn display_path(path: &[Label]) -> String {
+    let labels: Vec<_> = path.iter().map(Label::to_string).collect();
+
+    format!("[{}]", labels.join(", "))
 }
 
+fn node_kind(node: &HashTreeNode) -> &'static str {
+    match node {
+        HashTreeNode::Empty() => "empty",
+        HashTreeNode::Fork(_) => "fork",
+        HashTreeNode::Labeled(_, _) => "labeled",
+        HashTreeNode::Leaf(_) => "leaf",
+        HashTreeNode::Pruned(_) => "pruned",
+    }
+}
+
+/// Merges two [HashTree]s that are witnesses of the same tree into a single witness.
+///
+/// # Panics
+///
+/// Panics if the trees are inconsistent, use [try_merge_hash_trees] to merge trees that are not trusted.
 pub fn merge_hash_trees(lhs: HashTree, rhs: HashTree) -> HashTree {
-    match (lhs.root, rhs.root) {
+    try_merge_hash_trees(lhs, rhs).unwrap_or_else(|err| panic!("merge_hash_trees: {}", err))
+}
+
+/// Merges two [HashTree]s that are witnesses of the same tree into a single witness, or returns a
+/// [MergeHashTreesError] that describes where the trees diverged if they are inconsistent.
+pub fn try_merge_hash_trees(lhs: HashTree, rhs: HashTree) -> Result<HashTree, MergeHashTreesError> {
+    merge_hash_tree_nodes(lhs.root, rhs.root, &mut vec![]).map(|root| HashTree { root })
+}
+
+fn merge_hash_tree_nodes(
+    lhs: HashTreeNode,
+    rhs: HashTreeNode,
+    path: &mut Vec<Label>,
+) -> Result<HashTreeNode, MergeHashTreesError> {
+    match (lhs, rhs) {
         (HashTreeNode::Pruned(l), HashTreeNode::Pruned(r)) => {
             if l != r {
-                panic!("merge_hash_trees: inconsistent hashes");
+                return Err(MergeHashTreesError::InconsistentHashes {
+                    path: path.clone(),
+                    lhs: l,
+                    rhs: r,
+                });
+            }
+            Ok(HashTreeNode::Pruned(l))
+        }
+        (HashTreeNode::Pruned(l), r) => {
+            let r_hash = r.digest();
+            if l != r_hash {
+                return Err(MergeHashTreesError::InconsistentHashes {
+                    path: path.clone(),
+                    lhs: l,
+                    rhs: r_hash,
+                });
+            }
+            Ok(r)
+        }
+        (l, HashTreeNode::Pruned(r)) => {
+            let l_hash = l.digest();
+            if l_hash != r {
+                return Err(MergeHashTreesError::InconsistentHashes {
+                    path: path.clone(),
+                    lhs: l_hash,
+                    rhs: r,
+                });
             }
-            pruned(l)
+            Ok(l)
+        }
+        (HashTreeNode::Fork(l), HashTreeNode::Fork(r)) => {
+            let (l_left, l_right) = *l;
+            let (r_left, r_right) = *r;
+
+            Ok(HashTreeNode::Fork(Box::new((
+                merge_hash_tree_nodes(l_left, r_left, path)?,
+                merge_hash_tree_nodes(l_right, r_right, path)?,
+            ))))
         }
-        (HashTreeNode::Pruned(_), r) => HashTree { root: r },
-        (l, HashTreeNode::Pruned(_)) => HashTree { root: l },
-        (HashTreeNode::Fork(l), HashTreeNode::Fork(r)) => fork(
-            merge_hash_trees(HashTree { root: l.0 }, HashTree { root: r.0 }),
-            merge_hash_trees(HashTree { root: l.1 }, HashTree { root: r.1 }),
-        ),
         (HashTreeNode::Labeled(l_label, l), HashTreeNode::Labeled(r_label, r)) => {
             if l_label != r_label {
-                panic!("merge_hash_trees: inconsistent hash tree labels");
+                return Err(MergeHashTreesError::InconsistentLabels {
+                    path: path.clone(),
+                    lhs: l_label,
+                    rhs: r_label,
+                });
             }
-            labeled(
-                l_label,
-                merge_hash_trees(HashTree { root: *l }, HashTree { root: *r }),
-            )
+
+            path.push(l_label);
+            let merged = merge_hash_tree_nodes(*l, *r, path)?;
+            let l_label = path.pop().expect("the label was pushed above");
+
+            Ok(HashTreeNode::Labeled(l_label, Box::new(merged)))
         }
-        (HashTreeNode::Empty(), HashTreeNode::Empty()) => empty(),
-        (HashTreeNode::Empty(), r) => HashTree { root: r },
-        (l, HashTreeNode::Empty()) => HashTree { root: l },
+        (HashTreeNode::Empty(), HashTreeNode::Empty()) => Ok(HashTreeNode::Empty()),
+        (HashTreeNode::Empty(), r) => Ok(r),
+        (l, HashTreeNode::Empty()) => Ok(l),
         (HashTreeNode::Leaf(l), HashTreeNode::Leaf(r)) => {
             if l != r {
-                panic!("merge_hash_trees: inconsistent leaves");
+                return Err(MergeHashTreesError::InconsistentLeaves {
+                    path: path.clone(),
+                    lhs: l,
+                    rhs: r,
+                });
             }
-            leaf(l)
-        }
-        (_l, _r) => {
-            panic!("merge_hash_trees: inconsistent tree structure");
+            Ok(HashTreeNode::Leaf(l))
         }
+        (l, r) => Err(MergeHashTreesError::InconsistentStructure {
+            path: path.clone(),
+            lhs: node_kind(&l),
+            rhs: node_kind(&r),
+        }),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::LookupResult;
+    use crate::{empty, fork, labeled, leaf, pruned, LookupResult, RangeLookupResult};
     use rstest::*;
+    use std::ops::Bound;
 
     #[rstest]
     fn nested_tree_operation() {
@@ -207,6 +735,541 @@ mod tests {
         assert!(!tree.contains_leaf(&["one"]));
     }
 
+    #[rstest]
+    #[case::disjoint(vec![vec!["one", "two"], vec!["three", "four"], vec!["five"]])]
+    #[case::shared_prefixes(vec![vec!["one", "two"], vec!["one", "three"], vec!["two", "one"], vec!["one", "four", "five"]])]
+    #[case::duplicates(vec![vec!["one", "two"], vec!["three"], vec!["one", "two"]])]
+    #[case::leaf_overwritten_by_subtree(vec![vec!["one"], vec!["one", "two"], vec!["two"]])]
+    #[case::subtree_overwritten_by_leaf(vec![vec!["one", "two"], vec!["one"], vec!["one", "three"], vec!["one"]])]
+    #[case::root_leaf(vec![vec!["one", "two"], vec![], vec!["three"]])]
+    fn nested_tree_insert_many(#[case] paths: Vec<Vec<&'static str>>) {
+        let mut initial_tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        initial_tree.insert(&["one", "six"], vec![0]);
+        initial_tree.insert(&["seven"], vec![0]);
+
+        for initial_tree in [NestedTree::default(), initial_tree] {
+            let mut expected_tree = initial_tree.clone();
+            for (i, path) in paths.iter().enumerate() {
+                expected_tree.insert(path, vec![i as u8]);
+            }
+
+            let mut tree = initial_tree;
+            tree.insert_many(
+                paths
+                    .iter()
+                    .enumerate()
+                    .map(|(i, path)| (path.as_slice(), vec![i as u8])),
+            );
+
+            assert_eq!(tree.root_hash(), expected_tree.root_hash());
+            assert_eq!(tree.as_hash_tree(), expected_tree.as_hash_tree());
+            for path in paths.iter() {
+                assert_eq!(tree.get(path), expected_tree.get(path));
+                assert_eq!(tree.witness(path).digest(), tree.root_hash());
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::leaves(vec![vec!["one", "two"], vec!["two", "two"]])]
+    #[case::subtrees(vec![vec!["one"], vec!["two", "three"]])]
+    #[case::missing_paths(vec![vec!["one", "two", "three"], vec!["four"]])]
+    #[case::root(vec![vec!["one", "two"], vec![]])]
+    fn nested_tree_delete_many(#[case] paths: Vec<Vec<&'static str>>) {
+        let mut tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        tree.insert(&["one", "two"], vec![1]);
+        tree.insert(&["one", "three"], vec![2]);
+        tree.insert(&["two", "two"], vec![3]);
+        tree.insert(&["two", "three"], vec![4]);
+
+        let mut expected_tree = tree.clone();
+        for path in paths.iter() {
+            expected_tree.delete(path);
+        }
+
+        tree.delete_many(paths.iter().map(Vec::as_slice));
+
+        assert_eq!(tree.root_hash(), expected_tree.root_hash());
+        assert_eq!(tree.as_hash_tree(), expected_tree.as_hash_tree());
+    }
+
+    #[rstest]
+    fn nested_tree_delete_prunes_empty_subtrees() {
+        let mut initial_tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        initial_tree.insert(&["one", "two"], vec![1]);
+
+        let mut tree = initial_tree.clone();
+        tree.insert(&["two", "three", "four"], vec![2]);
+        tree.insert(&["one", "three", "four"], vec![3]);
+        tree.delete(&["two", "three", "four"]);
+        tree.delete(&["one", "three", "four"]);
+
+        assert!(!tree.contains_path(&["two"]));
+        assert!(!tree.contains_path(&["one", "three"]));
+        assert_eq!(tree.root_hash(), initial_tree.root_hash());
+        assert_eq!(tree.as_hash_tree(), initial_tree.as_hash_tree());
+
+        tree.delete_many([&["one", "two"][..]]);
+        assert!(!tree.contains_path(&["one"]));
+        assert_eq!(
+            tree.root_hash(),
+            NestedTree::<&str, Vec<u8>>::default().root_hash()
+        );
+    }
+
+    /// A small deterministic pseudo-random number generator, so that the property tests are reproducible.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+
+            (self.0 >> 33) as usize % bound
+        }
+    }
+
+    /// Generates up to `count` distinct paths of varying length, none of which is a prefix of another,
+    /// so that inserting any of them does not overwrite any of the others.
+    fn prefix_free_paths(rng: &mut Lcg, count: usize) -> Vec<Vec<Vec<u8>>> {
+        let mut paths: Vec<Vec<Vec<u8>>> = vec![];
+        for _ in 0..count * 10 {
+            if paths.len() == count {
+                break;
+            }
+
+            let path: Vec<Vec<u8>> = (0..=rng.next(3)).map(|_| vec![rng.next(4) as u8]).collect();
+
+            if !paths
+                .iter()
+                .any(|other| other.starts_with(&path) || path.starts_with(other))
+            {
+                paths.push(path);
+            }
+        }
+
+        paths
+    }
+
+    /// Builds a tree by inserting all of the given paths and then deleting some of them, in the given order.
+    fn build_nested_tree(
+        inserted_paths: &[Vec<Vec<u8>>],
+        deleted_paths: &[Vec<Vec<u8>>],
+    ) -> NestedTree<Vec<u8>, Vec<u8>> {
+        let mut tree = NestedTree::default();
+        for path in inserted_paths {
+            tree.insert(path, path.concat());
+        }
+        for path in deleted_paths {
+            tree.delete(path);
+        }
+
+        tree
+    }
+
+    fn nested_tree_leaves(tree: &NestedTree<Vec<u8>, Vec<u8>>) -> Vec<(Vec<Vec<u8>>, Vec<u8>)> {
+        let mut leaves = vec![];
+        tree.for_each_leaf(|path, value| {
+            leaves.push((path.iter().map(|key| key.to_vec()).collect(), value.clone()))
+        });
+
+        leaves
+    }
+
+    fn has_empty_subtree(tree: &NestedTree<Vec<u8>, Vec<u8>>) -> bool {
+        match tree {
+            NestedTree::Leaf(_) => false,
+            NestedTree::Nested(tree) => tree
+                .iter()
+                .any(|(_, child)| child.is_empty() || has_empty_subtree(child)),
+        }
+    }
+
+    #[rstest]
+    fn nested_tree_insert_then_delete_removes_empty_subtrees() {
+        for seed in 0..200 {
+            let mut rng = Lcg(seed);
+            let count = 1 + rng.next(12);
+            let paths = prefix_free_paths(&mut rng, count);
+            let (initial_paths, inserted_paths) = paths.split_at(rng.next(paths.len() + 1));
+
+            // delete in a different order than the paths were inserted in
+            let mut deleted_paths = inserted_paths.to_vec();
+            for i in (1..deleted_paths.len()).rev() {
+                deleted_paths.swap(i, rng.next(i + 1));
+            }
+
+            let initial_tree = build_nested_tree(initial_paths, &[]);
+            let tree = build_nested_tree(&paths, &deleted_paths);
+
+            assert_eq!(
+                nested_tree_leaves(&tree),
+                nested_tree_leaves(&initial_tree),
+                "seed {}",
+                seed
+            );
+            assert!(!has_empty_subtree(&tree), "seed {}", seed);
+
+            // deleting paths that are absent from the tree leaves it unchanged
+            let absent_deletes_tree = build_nested_tree(initial_paths, &deleted_paths);
+            assert_eq!(
+                absent_deletes_tree.root_hash(),
+                initial_tree.root_hash(),
+                "seed {}",
+                seed
+            );
+            assert_eq!(
+                nested_tree_leaves(&absent_deletes_tree),
+                nested_tree_leaves(&initial_tree),
+                "seed {}",
+                seed
+            );
+
+            assert_eq!(
+                tree.as_hash_tree().digest(),
+                tree.root_hash(),
+                "seed {}",
+                seed
+            );
+            for path in initial_paths {
+                assert_eq!(
+                    tree.witness(path).digest(),
+                    tree.root_hash(),
+                    "seed {}",
+                    seed
+                );
+            }
+
+            let mut batch_tree = build_nested_tree(&paths, &[]);
+            batch_tree.delete_many(deleted_paths.iter().map(Vec::as_slice));
+
+            assert_eq!(
+                nested_tree_leaves(&batch_tree),
+                nested_tree_leaves(&initial_tree),
+                "seed {}",
+                seed
+            );
+            assert!(!has_empty_subtree(&batch_tree), "seed {}", seed);
+        }
+    }
+
+    #[rstest]
+    fn nested_tree_insert_then_delete_does_not_restore_tree_shape() {
+        let initial_paths: Vec<Vec<Vec<u8>>> = [12, 5, 2, 13].map(|key| vec![vec![key]]).to_vec();
+        let inserted_path = vec![vec![8]];
+        let paths = [initial_paths.clone(), vec![inserted_path.clone()]].concat();
+
+        let initial_tree = build_nested_tree(&initial_paths, &[]);
+        let tree = build_nested_tree(&paths, &[inserted_path]);
+
+        // the red-black tree is rebalanced differently by the deletion, so the root hash depends on
+        // the history of the tree rather than only on its values
+        assert_ne!(tree.root_hash(), initial_tree.root_hash());
+        assert_eq!(nested_tree_leaves(&tree), nested_tree_leaves(&initial_tree));
+        assert!(!has_empty_subtree(&tree));
+    }
+
+    #[rstest]
+    #[case::missing_first_segment(&["zero"])]
+    #[case::missing_last_segment(&["one", "four"])]
+    #[case::missing_intermediate_segment(&["one", "four", "five"])]
+    #[case::through_leaf(&["one", "two", "three"])]
+    fn nested_tree_absence_proof(#[case] path: &[&'static str]) {
+        let mut tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        tree.insert(&["one", "two"], vec![1]);
+        tree.insert(&["one", "three"], vec![2]);
+        tree.insert(&["two"], vec![3]);
+
+        let proof = tree.absence_proof(path).unwrap();
+
+        assert_eq!(proof.digest(), tree.root_hash());
+        assert_eq!(proof.verify_absent(path), AbsenceResult::Absent);
+        assert_eq!(proof.lookup_path(path), LookupResult::Absent);
+    }
+
+    #[rstest]
+    #[case::leaf(&["one", "two"])]
+    #[case::subtree(&["one"])]
+    #[case::root(&[])]
+    fn nested_tree_absence_proof_for_present_path(#[case] path: &[&'static str]) {
+        let mut tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        tree.insert(&["one", "two"], vec![1]);
+
+        assert_eq!(tree.absence_proof(path), None);
+        assert_eq!(
+            tree.witness(path).verify_absent(path),
+            AbsenceResult::Present
+        );
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    #[case(3)]
+    #[case(10)]
+    fn nested_tree_paginated_witness(#[case] limit: usize) {
+        let mut tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        let todos = ["a", "b", "c", "d", "e", "f", "g"];
+        for todo in todos {
+            tree.insert(&["todos", todo, "title"], todo.as_bytes().to_vec());
+        }
+        tree.insert(&["users", "alice"], vec![1]);
+
+        let mut cursor: Option<&str> = None;
+        let mut listed = vec![];
+        loop {
+            let page = tree.paginated_witness(&["todos"], cursor.map(str::as_bytes), limit);
+            assert_eq!(page.witness.digest(), tree.root_hash());
+
+            let start = cursor.map_or(Bound::Unbounded, |cursor| {
+                Bound::Included(cursor.as_bytes())
+            });
+            let end = page
+                .next_cursor
+                .map_or(Bound::Unbounded, |next| Bound::Excluded(next.as_bytes()));
+            let RangeLookupResult::Found(children) =
+                page.witness.lookup_range(["todos"], (start, end))
+            else {
+                panic!("incomplete page: {:?}", page.witness);
+            };
+            assert!(children.len() <= limit);
+
+            for (label, subtree) in children {
+                assert_eq!(
+                    subtree.lookup_path(["title"]),
+                    LookupResult::Found(label.as_bytes())
+                );
+                listed.push(label);
+            }
+
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        assert_eq!(listed, todos.map(Label::from));
+    }
+
+    #[rstest]
+    fn nested_tree_range_and_prefix_witness() {
+        let mut tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        for asset in ["index.html", "img/a.png", "img/b.png", "js/app.js"] {
+            tree.insert(&["assets", asset], asset.as_bytes().to_vec());
+        }
+
+        let witness = tree.prefix_witness(&["assets"], b"img/");
+        assert_eq!(witness.digest(), tree.root_hash());
+        assert_eq!(
+            witness.lookup_range(
+                ["assets"],
+                (
+                    Bound::Included(b"img/" as &[u8]),
+                    Bound::Excluded(b"img0" as &[u8])
+                )
+            ),
+            RangeLookupResult::Found(vec![
+                ("img/a.png".into(), leaf("img/a.png")),
+                ("img/b.png".into(), leaf("img/b.png")),
+            ])
+        );
+        assert_eq!(
+            witness.lookup_path(["assets", "index.html"]),
+            LookupResult::Unknown
+        );
+
+        let witness = tree.range_witness(&["assets"], b"img/b.png", b"index.html");
+        assert_eq!(witness.digest(), tree.root_hash());
+        assert_eq!(
+            witness.lookup_range(
+                ["assets"],
+                (
+                    Bound::Included(b"img/b.png" as &[u8]),
+                    Bound::Included(b"index.html" as &[u8])
+                )
+            ),
+            RangeLookupResult::Found(vec![
+                ("img/b.png".into(), leaf("img/b.png")),
+                ("index.html".into(), leaf("index.html")),
+            ])
+        );
+
+        let witness = tree.range_witness(&["missing"], b"a", b"z");
+        assert_eq!(witness.digest(), tree.root_hash());
+        assert_eq!(
+            witness.lookup_range(["missing"], ..),
+            RangeLookupResult::Absent
+        );
+    }
+
+    #[rstest]
+    fn nested_tree_from_sorted() {
+        let mut paths = vec![];
+        for i in 0..20u8 {
+            for j in 0..(i % 5) {
+                paths.push(vec![vec![i], vec![j]]);
+            }
+            paths.push(vec![vec![i], vec![10], vec![i, i]]);
+        }
+
+        let mut expected_tree: NestedTree<Vec<u8>, Vec<u8>> = NestedTree::default();
+        for path in paths.iter() {
+            expected_tree.insert(path, path.concat());
+        }
+
+        let tree = NestedTree::from_sorted(paths.iter().map(|path| (path.clone(), path.concat())));
+        assert_eq!(tree.root_hash(), expected_tree.root_hash());
+        assert_eq!(tree.as_hash_tree(), expected_tree.as_hash_tree());
+
+        paths.reverse();
+        let mut expected_tree: NestedTree<Vec<u8>, Vec<u8>> = NestedTree::default();
+        for path in paths.iter() {
+            expected_tree.insert(path, path.concat());
+        }
+
+        let tree = NestedTree::from_sorted(paths.iter().map(|path| (path.clone(), path.concat())));
+        assert_eq!(tree.root_hash(), expected_tree.root_hash());
+        assert_eq!(tree.as_hash_tree(), expected_tree.as_hash_tree());
+    }
+
+    #[rstest]
+    fn nested_tree_for_each_leaf() {
+        let mut tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        tree.insert(&["two", "one"], vec![3]);
+        tree.insert(&["one", "two"], vec![2]);
+        tree.insert(&["one", "three", "four"], vec![1]);
+        tree.insert(&["three"], vec![4]);
+
+        let mut leaves = vec![];
+        tree.for_each_leaf(|path, value| {
+            let path: Vec<_> = path.iter().map(|key| **key).collect();
+            leaves.push((path, value.clone()));
+        });
+
+        assert_eq!(
+            leaves,
+            vec![
+                (vec!["one", "three", "four"], vec![1]),
+                (vec!["one", "two"], vec![2]),
+                (vec!["three"], vec![4]),
+                (vec!["two", "one"], vec![3]),
+            ]
+        );
+
+        let mut leaves = vec![];
+        NestedTree::<&str, Vec<u8>>::Leaf(vec![5])
+            .for_each_leaf(|path, value| leaves.push((path.len(), value.clone())));
+        assert_eq!(leaves, vec![(0, vec![5])]);
+
+        let mut leaves = 0;
+        NestedTree::<&str, Vec<u8>>::default().for_each_leaf(|_, _| leaves += 1);
+        assert_eq!(leaves, 0);
+
+        let mut leaves = vec![];
+        tree.get_subtree(&["one"])
+            .unwrap()
+            .for_each_leaf(|path, value| leaves.push((path.len(), value.clone())));
+        assert_eq!(leaves, vec![(2, vec![1]), (1, vec![2])]);
+        assert!(matches!(
+            tree.get_subtree(&["three"]),
+            Some(NestedTree::Leaf(value)) if value == &vec![4]
+        ));
+        assert!(tree.get_subtree(&["three", "four"]).is_none());
+        assert!(tree.get_subtree(&["four"]).is_none());
+    }
+
+    #[rstest]
+    fn nested_tree_clone_is_a_snapshot() {
+        let mut tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        tree.insert(&["one", "two"], vec![1]);
+        tree.insert(&["one", "three"], vec![2]);
+        tree.insert(&["two"], vec![3]);
+
+        let snapshot = tree.clone();
+        let snapshot_hash = snapshot.root_hash();
+        let snapshot_witness = snapshot.witness(&["one", "two"]);
+
+        tree.insert(&["one", "two"], vec![4]);
+        tree.insert(&["one", "four"], vec![5]);
+        tree.delete(&["two"]);
+
+        assert_ne!(tree.root_hash(), snapshot_hash);
+        assert_eq!(snapshot.root_hash(), snapshot_hash);
+        assert_eq!(snapshot.witness(&["one", "two"]), snapshot_witness);
+        assert!(matches!(
+            snapshot.get_subtree(&["one", "two"]),
+            Some(NestedTree::Leaf(value)) if value == &vec![1]
+        ));
+        assert!(snapshot.contains_leaf(&["two"]));
+        assert!(!tree.contains_leaf(&["two"]));
+    }
+
+    #[rstest]
+    fn nested_tree_diff() {
+        let mut old: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        old.insert(&["one", "two"], vec![1]);
+        old.insert(&["one", "three"], vec![2]);
+        old.insert(&["two", "one"], vec![3]);
+        old.insert(&["three"], vec![4]);
+        old.insert(&["four"], vec![5]);
+
+        let mut new = old.clone();
+        new.insert(&["one", "two"], vec![6]);
+        new.insert(&["one", "four", "five"], vec![7]);
+        new.delete(&["two"]);
+        new.insert(&["three", "six"], vec![8]);
+        new.insert(&["five"], vec![9]);
+
+        assert_eq!(old.diff(&old), vec![]);
+        assert_eq!(
+            old.diff(&new),
+            vec![
+                NestedTreeChange::Added(vec!["five"]),
+                NestedTreeChange::Added(vec!["one", "four"]),
+                NestedTreeChange::Changed(vec!["one", "two"]),
+                NestedTreeChange::Changed(vec!["three"]),
+                NestedTreeChange::Removed(vec!["two"]),
+            ]
+        );
+        assert_eq!(
+            new.diff(&old),
+            vec![
+                NestedTreeChange::Removed(vec!["five"]),
+                NestedTreeChange::Removed(vec!["one", "four"]),
+                NestedTreeChange::Changed(vec!["one", "two"]),
+                NestedTreeChange::Changed(vec!["three"]),
+                NestedTreeChange::Added(vec!["two"]),
+            ]
+        );
+        assert_eq!(
+            NestedTree::<&str, Vec<u8>>::Leaf(vec![1]).diff(&NestedTree::Leaf(vec![2])),
+            vec![NestedTreeChange::Changed(vec![])]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn nested_tree_serde_round_trip() {
+        let mut tree: NestedTree<String, Vec<u8>> = NestedTree::default();
+        for i in (0..20u8).rev() {
+            tree.insert(&[format!("{}", i % 3), format!("{}", i)], vec![i]);
+        }
+        tree.insert(&["leaf".to_string()], vec![20]);
+        tree.delete(&["1".to_string(), "4".to_string()]);
+
+        let deserialized: NestedTree<String, Vec<u8>> =
+            serde_cbor::from_slice(&serde_cbor::to_vec(&tree).unwrap()).unwrap();
+
+        assert_eq!(deserialized.root_hash(), tree.root_hash());
+        assert_eq!(deserialized.diff(&tree), vec![]);
+        assert_eq!(
+            deserialized.witness(&["2".to_string(), "5".to_string()]),
+            tree.witness(&["2".to_string(), "5".to_string()])
+        );
+    }
+
     #[rstest]
     fn merge_hash_trees_merge_witness() {
         let mut tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
@@ -251,12 +1314,12 @@ mod tests {
     #[case::leaf_empty(leaf_a(), empty(), leaf_a())]
     // pruned
     #[case::pruned_pruned(pruned_a(), pruned_a(), pruned_a())]
-    #[case::pruned_labeled(pruned_a(), labeled_a(), labeled_a())]
-    #[case::labeled_pruned(labeled_a(), pruned_a(), labeled_a())]
-    #[case::pruned_leaf(pruned_a(), leaf_a(), leaf_a())]
-    #[case::leaf_pruned(leaf_a(), pruned_a(), leaf_a())]
-    #[case::empty_pruned(empty(), pruned_a(), empty())]
-    #[case::pruned_empty(pruned_a(), empty(), empty())]
+    #[case::pruned_labeled(pruned(labeled_a().digest()), labeled_a(), labeled_a())]
+    #[case::labeled_pruned(labeled_a(), pruned(labeled_a().digest()), labeled_a())]
+    #[case::pruned_leaf(pruned(leaf_a().digest()), leaf_a(), leaf_a())]
+    #[case::leaf_pruned(leaf_a(), pruned(leaf_a().digest()), leaf_a())]
+    #[case::empty_pruned(empty(), pruned(empty().digest()), empty())]
+    #[case::pruned_empty(pruned(empty().digest()), empty(), empty())]
     // matching
     #[case::empty_empty(empty(), empty(), empty())]
     #[case::fork_fork(fork_a(), fork_a(), fork_a())]
@@ -293,6 +1356,88 @@ mod tests {
         merge_hash_trees(lhs, rhs);
     }
 
+    #[rstest]
+    #[case::mismatched_pruned(pruned_a(), pruned_b(), MergeHashTreesError::InconsistentHashes { path: vec![], lhs: [0u8; 32], rhs: [1u8; 32] })]
+    #[case::mismatched_pruned_and_leaf(pruned_a(), leaf_a(), MergeHashTreesError::InconsistentHashes { path: vec![], lhs: [0u8; 32], rhs: leaf_a().digest() })]
+    #[case::mismatched_labeled_and_pruned(labeled_a(), pruned_b(), MergeHashTreesError::InconsistentHashes { path: vec![], lhs: labeled_a().digest(), rhs: [1u8; 32] })]
+    #[case::mismatched_labeled(labeled_a(), labeled_b(), MergeHashTreesError::InconsistentLabels { path: vec![], lhs: "foo".into(), rhs: "bar".into() })]
+    #[case::mismatched_leaves(leaf_a(), leaf_b(), MergeHashTreesError::InconsistentLeaves { path: vec![], lhs: vec![0u8; 32], rhs: vec![1u8; 32] })]
+    #[case::mismatched_leaf_and_fork(leaf_a(), fork_a(), MergeHashTreesError::InconsistentStructure { path: vec![], lhs: "leaf", rhs: "fork" })]
+    #[case::mismatched_fork_and_label(fork_a(), labeled_a(), MergeHashTreesError::InconsistentStructure { path: vec![], lhs: "fork", rhs: "labeled" })]
+    fn try_merge_hash_trees_inconsistent_structure(
+        #[case] lhs: HashTree,
+        #[case] rhs: HashTree,
+        #[case] expected_error: MergeHashTreesError,
+    ) {
+        assert_eq!(try_merge_hash_trees(lhs, rhs), Err(expected_error));
+    }
+
+    #[rstest]
+    fn try_merge_hash_trees_reports_divergence_path() {
+        let lhs = labeled("one", fork(labeled("two", leaf_a()), pruned_a()));
+        let rhs = labeled("one", fork(labeled("two", leaf_b()), pruned_a()));
+
+        let error = try_merge_hash_trees(lhs, rhs).unwrap_err();
+
+        assert_eq!(
+            error,
+            MergeHashTreesError::InconsistentLeaves {
+                path: vec!["one".into(), "two".into()],
+                lhs: vec![0u8; 32],
+                rhs: vec![1u8; 32],
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                r#"inconsistent leaves at ["one", "two"]: {} != {}"#,
+                hex::encode([0u8; 32]),
+                hex::encode([1u8; 32])
+            )
+        );
+    }
+
+    #[rstest]
+    fn try_merge_hash_trees_rejects_subtree_that_does_not_match_pruned_hash() {
+        let mut tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        tree.insert(&["one", "two"], vec![2]);
+        tree.insert(&["one", "three"], vec![3]);
+        tree.insert(&["two"], vec![4]);
+        let witness = tree.witness(&["two"]);
+
+        let mut other_tree = tree.clone();
+        other_tree.insert(&["one", "two"], vec![5]);
+        let other_witness = other_tree.witness(&["one", "two"]);
+
+        let error = try_merge_hash_trees(witness.clone(), other_witness.clone()).unwrap_err();
+        assert!(matches!(
+            error,
+            MergeHashTreesError::InconsistentHashes { ref path, .. } if path.is_empty()
+        ));
+
+        let error = try_merge_hash_trees(other_witness, witness).unwrap_err();
+        assert!(matches!(
+            error,
+            MergeHashTreesError::InconsistentHashes { ref path, .. } if path.is_empty()
+        ));
+    }
+
+    #[rstest]
+    fn try_merge_hash_trees_merge_witness() {
+        let mut tree: NestedTree<&str, Vec<u8>> = NestedTree::default();
+        tree.insert(&["one", "two"], vec![2]);
+        tree.insert(&["one", "three"], vec![3]);
+        tree.insert(&["two"], vec![4]);
+
+        let witness_one_two = tree.witness(&["one", "two"]);
+        let witness_two = tree.witness(&["two"]);
+
+        assert_eq!(
+            try_merge_hash_trees(witness_one_two.clone(), witness_two.clone()),
+            Ok(merge_hash_trees(witness_one_two, witness_two))
+        );
+    }
+
     #[fixture]
     fn pruned_a() -> HashTree {
         pruned(Hash::from([0u8; 32]))
@@ -6,6 +6,7 @@ use crate::{
 use std::borrow::Cow;
 use std::cmp::Ordering::{self, Equal, Greater, Less};
 use std::fmt;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Color {
@@ -84,7 +85,31 @@ impl<'a> AsRef<[u8]> for KeyBound<'a> {
     }
 }
 
-type NodeRef<K, V> = Option<Box<Node<K, V>>>;
+type NodeRef<K, V> = Option<Arc<Node<K, V>>>;
+
+/// Gives mutable access to the nodes of a tree while it is being updated.
+trait NodeMut<K, V> {
+    fn make_mut(n: &mut Arc<Node<K, V>>) -> &mut Node<K, V>;
+}
+
+/// Mutates the nodes of an [RbTree] in place, as they are never shared.
+struct Exclusive;
+
+impl<K, V> NodeMut<K, V> for Exclusive {
+    fn make_mut(n: &mut Arc<Node<K, V>>) -> &mut Node<K, V> {
+        Arc::get_mut(n).expect("the nodes of an RbTree are never shared")
+    }
+}
+
+/// Copies the nodes of a [PersistentRbTree] that are shared with its clones
+/// before mutating them.
+struct CopyOnWrite;
+
+impl<K: Clone, V: Clone> NodeMut<K, V> for CopyOnWrite {
+    fn make_mut(n: &mut Arc<Node<K, V>>) -> &mut Node<K, V> {
+        Arc::make_mut(n)
+    }
+}
 
 // 1. All leaves are black.
 // 2. Children of a red node are black.
@@ -99,22 +124,26 @@ struct Node<K, V> {
     color: Color,
 
     /// Hash of the full hash tree built from this node and its
-    /// children. It needs to be recomputed after every rotation.
+    /// children. It needs to be recomputed after every rotation, which
+    /// is deferred by marking the node as [`dirty`](Node::dirty).
     subtree_hash: Hash,
+
+    /// Whether [`subtree_hash`](Node::subtree_hash) is stale and needs to be
+    /// recomputed. If a node is not dirty, then none of its children are dirty.
+    dirty: bool,
 }
 
 impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> Node<K, V> {
-    fn new(key: K, value: V) -> Box<Node<K, V>> {
-        let value_hash = value.root_hash();
-        let data_hash = labeled_hash(key.as_ref(), &value_hash);
-        Box::new(Self {
+    fn new(key: K, value: V) -> Node<K, V> {
+        Self {
             key,
             value,
             left: None,
             right: None,
             color: Color::Red,
-            subtree_hash: data_hash,
-        })
+            subtree_hash: [0; 32],
+            dirty: true,
+        }
     }
 
     fn data_hash(&self) -> Hash {
@@ -173,6 +202,26 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> Node<K, V> {
         self.subtree_hash = self.compute_subtree_hash();
     }
 
+    /// Marks the subtree hash of this node as stale. The hash will be recomputed
+    /// by the next call to [`rehash`](Node::rehash).
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Recomputes the subtree hashes of all dirty nodes reachable from `n`,
+    /// hashing each of them exactly once.
+    fn rehash<M: NodeMut<K, V>>(n: &mut NodeRef<K, V>) {
+        if let Some(n) = n {
+            if n.dirty {
+                let n = M::make_mut(n);
+                Self::rehash::<M>(&mut n.left);
+                Self::rehash::<M>(&mut n.right);
+                n.update_subtree_hash();
+                n.dirty = false;
+            }
+        }
+    }
+
     fn compute_subtree_hash(&self) -> Hash {
         let h = self.data_hash();
 
@@ -185,6 +234,19 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> Node<K, V> {
     }
 }
 
+/// A difference between two [RbTree]s, see [RbTree::diff].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RbTreeChange<'a, K, V> {
+    /// The key is only present in the new tree, with the given value.
+    Added(&'a K, &'a V),
+
+    /// The key is only present in the old tree, with the given value.
+    Removed(&'a K, &'a V),
+
+    /// The key is present in both trees, but the root hashes of its old and new values differ.
+    Changed(&'a K, &'a V, &'a V),
+}
+
 #[derive(PartialEq, Debug)]
 enum Visit {
     Pre,
@@ -267,11 +329,35 @@ impl<'a, K, V> std::iter::Iterator for Iter<'a, K, V> {
 
 /// Implements mutable left-leaning red-black trees as defined in
 /// <https://www.cs.princeton.edu/~rs/talks/LLRB/LLRB.pdf>
-#[derive(Default, Clone)]
+///
+/// See [PersistentRbTree] for a variant that shares its nodes between clones.
+#[derive(Default)]
 pub struct RbTree<K, V> {
     root: NodeRef<K, V>,
 }
 
+impl<K: Clone, V: Clone> Clone for RbTree<K, V> {
+    fn clone(&self) -> Self {
+        fn go<K: Clone, V: Clone>(n: &NodeRef<K, V>) -> NodeRef<K, V> {
+            n.as_ref().map(|n| {
+                Arc::new(Node {
+                    key: n.key.clone(),
+                    value: n.value.clone(),
+                    left: go(&n.left),
+                    right: go(&n.right),
+                    color: n.color,
+                    subtree_hash: n.subtree_hash,
+                    dirty: n.dirty,
+                })
+            })
+        }
+
+        Self {
+            root: go(&self.root),
+        }
+    }
+}
+
 impl<K, V> PartialEq for RbTree<K, V>
 where
     K: 'static + AsRef<[u8]> + PartialEq,
@@ -373,29 +459,45 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> RbTree<K, V> {
 
     /// Updates the value corresponding to the specified key.
     pub fn modify(&mut self, key: &[u8], f: impl FnOnce(&mut V)) {
-        fn go<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
+        self.lazy_modify(key, f);
+        self.rehash();
+    }
+
+    /// Like [`modify`](RbTree::modify), but leaves the hashes of the updated
+    /// nodes stale until the next call to [`rehash`](RbTree::rehash).
+    pub(crate) fn lazy_modify(&mut self, key: &[u8], f: impl FnOnce(&mut V)) {
+        self.lazy_modify_with::<Exclusive>(key, f)
+    }
+
+    fn lazy_modify_with<M: NodeMut<K, V>>(&mut self, key: &[u8], f: impl FnOnce(&mut V)) {
+        fn go<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static, M: NodeMut<K, V>>(
             h: &mut NodeRef<K, V>,
             k: &[u8],
             f: impl FnOnce(&mut V),
         ) {
             if let Some(h) = h {
+                let h = M::make_mut(h);
                 match k.as_ref().cmp(h.key.as_ref()) {
                     Equal => {
                         f(&mut h.value);
-                        h.update_subtree_hash();
+                        h.mark_dirty();
                     }
                     Less => {
-                        go(&mut h.left, k, f);
-                        h.update_subtree_hash();
+                        go::<K, V, M>(&mut h.left, k, f);
+                        h.mark_dirty();
                     }
                     Greater => {
-                        go(&mut h.right, k, f);
-                        h.update_subtree_hash();
+                        go::<K, V, M>(&mut h.right, k, f);
+                        h.mark_dirty();
                     }
                 }
             }
         }
-        go(&mut self.root, key, f)
+        if self.get(key).is_none() {
+            return;
+        }
+
+        go::<K, V, M>(&mut self.root, key, f)
     }
 
     fn range_witness<'a>(
@@ -416,11 +518,21 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> RbTree<K, V> {
     /// entry with the specified key in this map.  The proof also
     /// contains the value in question.
     ///
-    /// If the key is not in the map, returns a proof of absence.
+    /// If the key is not in the map, returns a proof of absence,
+    /// see [`absence_proof`](RbTree::absence_proof).
     pub fn witness(&self, key: &[u8]) -> HashTree {
         self.nested_witness(key, |v| v.as_hash_tree())
     }
 
+    /// Returns the number of bytes of the CBOR encoding of the witness
+    /// for the specified key, see [`HashTree::encoded_size`](crate::hash_tree::HashTree::encoded_size).
+    ///
+    /// This is useful to check that a witness fits in the size budget of
+    /// a response header before including it.
+    pub fn witness_size(&self, key: &[u8]) -> usize {
+        self.witness(key).encoded_size()
+    }
+
     /// Like `witness`, but gives the caller more control over the
     /// construction of the value witness.  This method is useful for
     /// constructing witnesses for nested certified maps.
@@ -435,6 +547,24 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> RbTree<K, V> {
         )
     }
 
+    /// Constructs a hash tree that acts as a proof that there is no entry
+    /// with the specified key in this map.
+    ///
+    /// The proof contains the keys that are adjacent to the missing key, so a
+    /// verifier can check it using [`HashTree::verify_absent`](crate::hash_tree::HashTree::verify_absent).
+    /// Returns `None` if the key is in the map.
+    pub fn absence_proof(&self, key: &[u8]) -> Option<HashTree> {
+        if self.get(key).is_some() {
+            return None;
+        }
+
+        Some(self.range_witness(
+            self.lower_bound(key),
+            self.upper_bound(key),
+            Node::witness_tree,
+        ))
+    }
+
     /// Returns a witness enumerating all the keys in this map.  The
     /// resulting tree doesn't include values, they are replaced with
     /// "Pruned" nodes.
@@ -473,6 +603,39 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> RbTree<K, V> {
         )
     }
 
+    /// Returns a witness for the key-value pairs whose keys start with
+    /// the specified prefix.  The resulting tree contains both keys and
+    /// values.
+    pub fn values_with_prefix(&self, prefix: &[u8]) -> HashTree {
+        self.range_witness(
+            self.lower_bound(prefix),
+            self.right_prefix_neighbor(prefix),
+            Node::data_tree,
+        )
+    }
+
+    /// Returns a witness for a page of at most `limit` key-value pairs,
+    /// starting at the first key that is greater than or equal to `cursor`,
+    /// or at the first key in the map if no cursor is given.  A `limit` of
+    /// zero is treated as one.
+    ///
+    /// Also returns the key to pass as the cursor for the next page, or
+    /// `None` if this is the last page.  The witness includes that key
+    /// without its value, so a verifier can check that no keys were
+    /// omitted between the cursor and the next one, see
+    /// [`HashTree::lookup_range`](crate::hash_tree::HashTree::lookup_range).
+    pub fn value_page(&self, cursor: Option<&[u8]>, limit: usize) -> (HashTree, Option<&K>) {
+        let next = self.iter_from(cursor).map(|(key, _)| key).nth(limit.max(1));
+
+        let witness = self.range_witness(
+            cursor.and_then(|cursor| self.lower_bound(cursor)),
+            next.map(|key| KeyBound::Neighbor(key.as_ref())),
+            Node::data_tree,
+        );
+
+        (witness, next)
+    }
+
     /// Creates an iterator over the map's keys and values.
     pub fn iter(&self) -> Iter<'_, K, V> {
         match &self.root {
@@ -487,6 +650,112 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> RbTree<K, V> {
         }
     }
 
+    /// Creates an iterator over the map's keys and values, starting at the first key that is greater
+    /// than or equal to `key`, or at the first key in the map if no key is given.
+    ///
+    /// The iterator is positioned by descending from the root in the same way as
+    /// [`lower_bound`](RbTree::lower_bound), so skipping the smaller keys takes logarithmic time.
+    fn iter_from(&self, key: Option<&[u8]>) -> impl Iterator<Item = (&K, &V)> {
+        let mut parents = vec![];
+        let mut lower_bound_len = 0;
+        let mut n = &self.root;
+        while let Some(node) = n {
+            parents.push(node.as_ref());
+            match key.map_or(Greater, |key| node.key.as_ref().cmp(key)) {
+                Less => n = &node.right,
+                Equal => {
+                    lower_bound_len = parents.len();
+                    break;
+                }
+                Greater => {
+                    lower_bound_len = parents.len();
+                    n = &node.left;
+                }
+            }
+        }
+        parents.truncate(lower_bound_len);
+
+        // the nodes on the path that are smaller than the lower bound were passed by going right,
+        // so they are treated as visited, while the larger ones are visited after the lower bound
+        let first = parents.last().map(|n| (&n.key, &n.value));
+        first.into_iter().chain(Iter {
+            visit: Visit::In,
+            parents,
+        })
+    }
+
+    /// Compares the tree to a newer version of it and returns the keys that were added, removed or
+    /// changed, in ascending order.
+    ///
+    /// Subtrees with equal hashes are skipped without being traversed, so comparing a tree to an
+    /// updated [clone](Clone) of it only visits the nodes on the paths to the updated keys.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> Vec<RbTreeChange<'a, K, V>> {
+        fn collect<'a, K, V>(n: &'a NodeRef<K, V>, nodes: &mut Vec<&'a Node<K, V>>) {
+            if let Some(n) = n {
+                collect(&n.left, nodes);
+                nodes.push(n);
+                collect(&n.right, nodes);
+            }
+        }
+
+        fn go<'a, K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
+            old: &'a NodeRef<K, V>,
+            new: &'a NodeRef<K, V>,
+            changes: &mut Vec<RbTreeChange<'a, K, V>>,
+        ) {
+            match (old, new) {
+                (Some(o), Some(n)) if o.subtree_hash == n.subtree_hash => {}
+                (Some(o), Some(n)) if o.key.as_ref() == n.key.as_ref() => {
+                    go(&o.left, &n.left, changes);
+                    if o.value.root_hash() != n.value.root_hash() {
+                        changes.push(RbTreeChange::Changed(&o.key, &o.value, &n.value));
+                    }
+                    go(&o.right, &n.right, changes);
+                }
+                _ => {
+                    let mut old_nodes = vec![];
+                    collect(old, &mut old_nodes);
+                    let mut new_nodes = vec![];
+                    collect(new, &mut new_nodes);
+
+                    let mut old_nodes = old_nodes.into_iter().peekable();
+                    let mut new_nodes = new_nodes.into_iter().peekable();
+                    loop {
+                        let order = match (old_nodes.peek(), new_nodes.peek()) {
+                            (None, None) => break,
+                            (Some(_), None) => Less,
+                            (None, Some(_)) => Greater,
+                            (Some(o), Some(n)) => o.key.as_ref().cmp(n.key.as_ref()),
+                        };
+
+                        match order {
+                            Less => {
+                                let o = old_nodes.next().unwrap();
+                                changes.push(RbTreeChange::Removed(&o.key, &o.value));
+                            }
+                            Greater => {
+                                let n = new_nodes.next().unwrap();
+                                changes.push(RbTreeChange::Added(&n.key, &n.value));
+                            }
+                            Equal => {
+                                let o = old_nodes.next().unwrap();
+                                let n = new_nodes.next().unwrap();
+                                if o.value.root_hash() != n.value.root_hash() {
+                                    changes.push(RbTreeChange::Changed(&o.key, &o.value, &n.value));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut changes = vec![];
+        go(&self.root, &other.root, &mut changes);
+
+        changes
+    }
+
     /// Enumerates all the key-value pairs in the tree.
     pub fn for_each<'a, F>(&'a self, mut f: F)
     where
@@ -734,32 +1003,65 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> RbTree<K, V> {
 
     /// Inserts a key-value entry into the map.
     pub fn insert(&mut self, key: K, value: V) {
-        fn go<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
+        self.lazy_insert(key, value);
+        self.rehash();
+    }
+
+    /// Inserts multiple key-value entries into the map.
+    ///
+    /// This is equivalent to calling [`insert`](RbTree::insert) for every entry in order,
+    /// but the hash of every affected node is only recomputed once for the whole batch.
+    /// If the map is empty and the keys are sorted in strictly ascending order, the map is
+    /// built in linear time using [`from_sorted`](RbTree::from_sorted).
+    pub fn insert_many<I>(&mut self, entries: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        if self.is_empty() {
+            *self = Self::from_sorted(entries);
+            return;
+        }
+
+        for (key, value) in entries {
+            self.lazy_insert(key, value);
+        }
+        self.rehash();
+    }
+
+    /// Like [`insert`](RbTree::insert), but leaves the hashes of the updated
+    /// nodes stale until the next call to [`rehash`](RbTree::rehash).
+    pub(crate) fn lazy_insert(&mut self, key: K, value: V) {
+        self.lazy_insert_with::<Exclusive>(key, value)
+    }
+
+    fn lazy_insert_with<M: NodeMut<K, V>>(&mut self, key: K, value: V) {
+        fn go<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static, M: NodeMut<K, V>>(
             h: NodeRef<K, V>,
             k: K,
             v: V,
-        ) -> Box<Node<K, V>> {
+        ) -> Arc<Node<K, V>> {
             match h {
-                None => Node::new(k, v),
+                None => Arc::new(Node::new(k, v)),
                 Some(mut h) => {
-                    match k.as_ref().cmp(h.key.as_ref()) {
+                    let n = M::make_mut(&mut h);
+                    match k.as_ref().cmp(n.key.as_ref()) {
                         Equal => {
-                            h.value = v;
+                            n.value = v;
                         }
                         Less => {
-                            h.left = Some(go(h.left, k, v));
+                            n.left = Some(go::<K, V, M>(n.left.take(), k, v));
                         }
                         Greater => {
-                            h.right = Some(go(h.right, k, v));
+                            n.right = Some(go::<K, V, M>(n.right.take(), k, v));
                         }
                     }
-                    h.update_subtree_hash();
-                    balance(h)
+                    n.mark_dirty();
+                    balance::<K, V, M>(h)
                 }
             }
         }
-        let mut root = go(self.root.take(), key, value);
-        root.color = Color::Black;
+        let mut root = go::<K, V, M>(self.root.take(), key, value);
+        M::make_mut(&mut root).color = Color::Black;
         self.root = Some(root);
 
         #[cfg(test)]
@@ -772,41 +1074,69 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> RbTree<K, V> {
 
     /// Removes the specified key from the map.
     pub fn delete(&mut self, key: &[u8]) {
-        fn move_red_left<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
-            mut h: Box<Node<K, V>>,
-        ) -> Box<Node<K, V>> {
-            flip_colors(&mut h);
+        self.lazy_delete(key);
+        self.rehash();
+    }
+
+    /// Removes multiple keys from the map.
+    ///
+    /// This is equivalent to calling [`delete`](RbTree::delete) for every key in order,
+    /// but the hash of every affected node is only recomputed once for the whole batch.
+    pub fn delete_many<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        for key in keys {
+            self.lazy_delete(key.as_ref());
+        }
+        self.rehash();
+    }
+
+    /// Like [`delete`](RbTree::delete), but leaves the hashes of the updated
+    /// nodes stale until the next call to [`rehash`](RbTree::rehash).
+    pub(crate) fn lazy_delete(&mut self, key: &[u8]) {
+        self.lazy_delete_with::<Exclusive>(key)
+    }
+
+    fn lazy_delete_with<M: NodeMut<K, V>>(&mut self, key: &[u8]) {
+        fn move_red_left<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static, M: NodeMut<K, V>>(
+            mut h: Arc<Node<K, V>>,
+        ) -> Arc<Node<K, V>> {
+            flip_colors::<K, V, M>(&mut h);
             if is_red(&h.right.as_ref().unwrap().left) {
-                h.right = Some(rotate_right(h.right.take().unwrap()));
-                h = rotate_left(h);
-                flip_colors(&mut h);
+                let n = M::make_mut(&mut h);
+                n.right = Some(rotate_right::<K, V, M>(n.right.take().unwrap()));
+                h = rotate_left::<K, V, M>(h);
+                flip_colors::<K, V, M>(&mut h);
             }
             h
         }
 
-        fn move_red_right<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
-            mut h: Box<Node<K, V>>,
-        ) -> Box<Node<K, V>> {
-            flip_colors(&mut h);
+        fn move_red_right<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static, M: NodeMut<K, V>>(
+            mut h: Arc<Node<K, V>>,
+        ) -> Arc<Node<K, V>> {
+            flip_colors::<K, V, M>(&mut h);
             if is_red(&h.left.as_ref().unwrap().left) {
-                h = rotate_right(h);
-                flip_colors(&mut h);
+                h = rotate_right::<K, V, M>(h);
+                flip_colors::<K, V, M>(&mut h);
             }
             h
         }
 
         #[inline]
-        fn min<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
-            mut h: &mut Box<Node<K, V>>,
-        ) -> &mut Box<Node<K, V>> {
-            while h.left.is_some() {
-                h = h.left.as_mut().unwrap();
+        fn min<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static, M: NodeMut<K, V>>(
+            h: &mut Arc<Node<K, V>>,
+        ) -> &mut Node<K, V> {
+            let mut n = M::make_mut(h);
+            while n.left.is_some() {
+                n = M::make_mut(n.left.as_mut().unwrap());
             }
-            h
+            n
         }
 
-        fn delete_min<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
-            mut h: Box<Node<K, V>>,
+        fn delete_min<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static, M: NodeMut<K, V>>(
+            mut h: Arc<Node<K, V>>,
         ) -> NodeRef<K, V> {
             if h.left.is_none() {
                 debug_assert!(h.right.is_none());
@@ -814,26 +1144,28 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> RbTree<K, V> {
                 return None;
             }
             if !is_red(&h.left) && !is_red(&h.left.as_ref().unwrap().left) {
-                h = move_red_left(h);
+                h = move_red_left::<K, V, M>(h);
             }
-            h.left = delete_min(h.left.unwrap());
-            h.update_subtree_hash();
-            Some(balance(h))
+            let n = M::make_mut(&mut h);
+            n.left = delete_min::<K, V, M>(n.left.take().unwrap());
+            n.mark_dirty();
+            Some(balance::<K, V, M>(h))
         }
 
-        fn go<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
-            mut h: Box<Node<K, V>>,
+        fn go<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static, M: NodeMut<K, V>>(
+            mut h: Arc<Node<K, V>>,
             key: &[u8],
         ) -> NodeRef<K, V> {
             if key < h.key.as_ref() {
                 debug_assert!(h.left.is_some(), "the key must be present in the tree");
                 if !is_red(&h.left) && !is_red(&h.left.as_ref().unwrap().left) {
-                    h = move_red_left(h);
+                    h = move_red_left::<K, V, M>(h);
                 }
-                h.left = go(h.left.take().unwrap(), key);
+                let n = M::make_mut(&mut h);
+                n.left = go::<K, V, M>(n.left.take().unwrap(), key);
             } else {
                 if is_red(&h.left) {
-                    h = rotate_right(h);
+                    h = rotate_right::<K, V, M>(h);
                 }
                 if key == h.key.as_ref() && h.right.is_none() {
                     debug_assert!(h.left.is_none());
@@ -842,33 +1174,34 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> RbTree<K, V> {
                 }
 
                 if !is_red(&h.right) && !is_red(&h.right.as_ref().unwrap().left) {
-                    h = move_red_right(h);
+                    h = move_red_right::<K, V, M>(h);
                 }
 
-                if key == h.key.as_ref() {
-                    let m = min(h.right.as_mut().unwrap());
-                    std::mem::swap(&mut h.key, &mut m.key);
-                    std::mem::swap(&mut h.value, &mut m.value);
-                    h.right = delete_min(h.right.take().unwrap());
+                let n = M::make_mut(&mut h);
+                if key == n.key.as_ref() {
+                    let m = min::<K, V, M>(n.right.as_mut().unwrap());
+                    std::mem::swap(&mut n.key, &mut m.key);
+                    std::mem::swap(&mut n.value, &mut m.value);
+                    n.right = delete_min::<K, V, M>(n.right.take().unwrap());
                 } else {
-                    h.right = go(h.right.take().unwrap(), key);
+                    n.right = go::<K, V, M>(n.right.take().unwrap(), key);
                 }
             }
-            h.update_subtree_hash();
-            Some(balance(h))
+            M::make_mut(&mut h).mark_dirty();
+            Some(balance::<K, V, M>(h))
         }
 
         if self.get(key).is_none() {
             return;
         }
 
-        if !is_red(&self.root.as_ref().unwrap().left) && !is_red(&self.root.as_ref().unwrap().right)
-        {
-            self.root.as_mut().unwrap().color = Color::Red;
+        let root = self.root.as_mut().unwrap();
+        if !is_red(&root.left) && !is_red(&root.right) {
+            M::make_mut(root).color = Color::Red;
         }
-        self.root = go(self.root.take().unwrap(), key);
+        self.root = go::<K, V, M>(self.root.take().unwrap(), key);
         if let Some(n) = self.root.as_mut() {
-            n.color = Color::Black;
+            M::make_mut(n).color = Color::Black;
         }
 
         #[cfg(test)]
@@ -880,6 +1213,431 @@ impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> RbTree<K, V> {
 
         debug_assert!(self.get(key).is_none());
     }
+
+    /// Recomputes the hashes of all nodes that were updated by one of the lazy
+    /// operations since the last call to this function.
+    pub(crate) fn rehash(&mut self) {
+        self.rehash_with::<Exclusive>()
+    }
+
+    fn rehash_with<M: NodeMut<K, V>>(&mut self) {
+        Node::rehash::<M>(&mut self.root);
+    }
+
+    /// Constructs a tree from key-value entries that are sorted by key in strictly
+    /// ascending order, in linear time.
+    ///
+    /// The resulting tree has exactly the same shape, and therefore the same root hash,
+    /// as a tree that is built by [inserting](RbTree::insert) the entries one at a time.
+    /// If the keys are not sorted in strictly ascending order, the entries are inserted
+    /// one at a time instead.
+    pub fn from_sorted<I>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let entries: Vec<(K, V)> = entries.into_iter().collect();
+        if !entries
+            .windows(2)
+            .all(|pair| pair[0].0.as_ref() < pair[1].0.as_ref())
+        {
+            let mut tree = Self::new();
+            for (key, value) in entries {
+                tree.lazy_insert(key, value);
+            }
+            tree.rehash();
+            return tree;
+        }
+
+        let mut tree = Self {
+            root: build_sorted(entries),
+        };
+        tree.rehash();
+
+        tree
+    }
+
+    /// Returns all the key-value pairs in the tree in ascending order of their keys, together with
+    /// their levels, which are the numbers of black nodes below them on any path to a leaf.
+    ///
+    /// The levels determine the shape and the colors of the tree, so that it can be restored with
+    /// the same root hash using [`from_entries_with_levels`](RbTree::from_entries_with_levels), for
+    /// example after it was persisted across a canister upgrade.
+    pub fn entries_with_levels(&self) -> Vec<(&K, &V, u8)> {
+        /// Collects the entries of a subtree and returns its black height.
+        fn go<'a, K, V>(n: &'a NodeRef<K, V>, entries: &mut Vec<(&'a K, &'a V, u8)>) -> u8 {
+            match n {
+                None => 0,
+                Some(n) => {
+                    let level = go(&n.left, entries);
+                    entries.push((&n.key, &n.value, level));
+                    go(&n.right, entries);
+
+                    level + u8::from(n.color == Color::Black)
+                }
+            }
+        }
+
+        let mut entries = vec![];
+        go(&self.root, &mut entries);
+
+        entries
+    }
+
+    /// Constructs a tree from key-value entries and their levels, as returned by
+    /// [`entries_with_levels`](RbTree::entries_with_levels), in linear time.
+    ///
+    /// The resulting tree has exactly the same shape, and therefore the same root hash, as the tree
+    /// that the entries were taken from. An error is returned if the keys are not sorted in strictly
+    /// ascending order, or if the levels do not describe a left-leaning red-black tree.
+    pub fn from_entries_with_levels<I>(entries: I) -> Result<Self, RbTreeLevelsError>
+    where
+        I: IntoIterator<Item = (K, V, u8)>,
+    {
+        let entries: Vec<(K, V, u8)> = entries.into_iter().collect();
+        if !entries
+            .windows(2)
+            .all(|pair| pair[0].0.as_ref() < pair[1].0.as_ref())
+        {
+            return Err(RbTreeLevelsError::UnsortedKeys);
+        }
+
+        let root = build_with_levels(entries);
+        if !is_valid(&root) {
+            return Err(RbTreeLevelsError::InvalidLevels);
+        }
+
+        let mut tree = Self { root };
+        tree.rehash();
+
+        Ok(tree)
+    }
+}
+
+/// A persistent variant of [RbTree], whose nodes are reference counted and shared
+/// between its clones.
+///
+/// Cloning the tree is cheap, and updating it only copies the nodes on the path to the
+/// updated key, leaving its clones unchanged. This makes it suitable for keeping
+/// snapshots of a tree that is updated over time. All the read-only operations of
+/// [RbTree] are available through [`Deref`](std::ops::Deref).
+pub struct PersistentRbTree<K, V> {
+    tree: RbTree<K, V>,
+}
+
+impl<K, V> Default for PersistentRbTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Clone for PersistentRbTree<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: RbTree {
+                root: self.tree.root.clone(),
+            },
+        }
+    }
+}
+
+impl<K, V> std::ops::Deref for PersistentRbTree<K, V> {
+    type Target = RbTree<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tree
+    }
+}
+
+impl<K, V> From<RbTree<K, V>> for PersistentRbTree<K, V> {
+    fn from(tree: RbTree<K, V>) -> Self {
+        Self { tree }
+    }
+}
+
+impl<K, V> PartialEq for PersistentRbTree<K, V>
+where
+    K: 'static + AsRef<[u8]> + PartialEq,
+    V: 'static + AsHashTree + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.tree == other.tree
+    }
+}
+
+impl<K, V> Eq for PersistentRbTree<K, V>
+where
+    K: 'static + AsRef<[u8]> + Eq,
+    V: 'static + AsHashTree + Eq,
+{
+}
+
+impl<K, V> std::fmt::Debug for PersistentRbTree<K, V>
+where
+    K: 'static + AsRef<[u8]> + std::fmt::Debug,
+    V: 'static + AsHashTree + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.tree.fmt(f)
+    }
+}
+
+impl<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static> AsHashTree for PersistentRbTree<K, V> {
+    fn root_hash(&self) -> Hash {
+        self.tree.root_hash()
+    }
+
+    fn as_hash_tree(&self) -> HashTree {
+        self.tree.as_hash_tree()
+    }
+}
+
+impl<K, V> PersistentRbTree<K, V> {
+    /// Constructs a new empty tree.
+    pub const fn new() -> Self {
+        Self {
+            tree: RbTree::new(),
+        }
+    }
+}
+
+impl<K, V> PersistentRbTree<K, V>
+where
+    K: 'static + AsRef<[u8]> + Clone,
+    V: AsHashTree + 'static + Clone,
+{
+    /// Updates the value corresponding to the specified key.
+    pub fn modify(&mut self, key: &[u8], f: impl FnOnce(&mut V)) {
+        self.tree.lazy_modify_with::<CopyOnWrite>(key, f);
+        self.tree.rehash_with::<CopyOnWrite>();
+    }
+
+    /// Inserts a key-value entry into the map.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.tree.lazy_insert_with::<CopyOnWrite>(key, value);
+        self.tree.rehash_with::<CopyOnWrite>();
+    }
+
+    /// Inserts multiple key-value entries into the map, recomputing the hash of every
+    /// affected node only once for the whole batch.
+    pub fn insert_many<I>(&mut self, entries: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (key, value) in entries {
+            self.tree.lazy_insert_with::<CopyOnWrite>(key, value);
+        }
+        self.tree.rehash_with::<CopyOnWrite>();
+    }
+
+    /// Removes the specified key from the map.
+    pub fn delete(&mut self, key: &[u8]) {
+        self.tree.lazy_delete_with::<CopyOnWrite>(key);
+        self.tree.rehash_with::<CopyOnWrite>();
+    }
+
+    /// Removes multiple keys from the map, recomputing the hash of every affected node
+    /// only once for the whole batch.
+    pub fn delete_many<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        for key in keys {
+            self.tree.lazy_delete_with::<CopyOnWrite>(key.as_ref());
+        }
+        self.tree.rehash_with::<CopyOnWrite>();
+    }
+}
+
+/// The reason why a tree could not be [constructed](RbTree::from_entries_with_levels) from entries and
+/// their levels.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum RbTreeLevelsError {
+    /// The keys of the entries are not sorted in strictly ascending order.
+    #[error("the keys are not sorted in strictly ascending order")]
+    UnsortedKeys,
+
+    /// The levels of the entries do not describe a left-leaning red-black tree.
+    #[error("the levels do not describe a left-leaning red-black tree")]
+    InvalidLevels,
+}
+
+/// Builds the nodes of a tree from entries that are sorted by key, where every entry is the parent of
+/// the preceding entries with a lower or equal level up to the previous entry with a higher level.
+/// Entries are red if they are the left child of an entry with the same level.
+///
+/// The hashes of the returned nodes are not computed, all of them are marked as dirty.
+fn build_with_levels<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
+    entries: Vec<(K, V, u8)>,
+) -> NodeRef<K, V> {
+    /// Links the nodes on top of the stack for as long as their level is at most `level`,
+    /// each node becoming the right child of the one below it.
+    fn pop<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
+        stack: &mut Vec<(Node<K, V>, u8)>,
+        level: u8,
+    ) -> Option<(Node<K, V>, u8)> {
+        let mut last: Option<(Node<K, V>, u8)> = None;
+        while stack.last().is_some_and(|(_, l)| *l <= level) {
+            let (mut node, l) = stack.pop().unwrap();
+            node.right = last.map(|(mut right, _)| {
+                right.color = Color::Black;
+                Arc::new(right)
+            });
+            last = Some((node, l));
+        }
+
+        last
+    }
+
+    let mut stack = vec![];
+    for (key, value, level) in entries {
+        let mut node = Node::new(key, value);
+        node.left = pop(&mut stack, level).map(|(mut left, left_level)| {
+            left.color = if left_level == level {
+                Color::Red
+            } else {
+                Color::Black
+            };
+            Arc::new(left)
+        });
+        stack.push((node, level));
+    }
+
+    pop(&mut stack, u8::MAX).map(|(mut root, _)| {
+        root.color = Color::Black;
+        Arc::new(root)
+    })
+}
+
+/// Checks that the tree is balanced and only has red nodes as left children of black nodes.
+fn is_valid<K, V>(root: &NodeRef<K, V>) -> bool {
+    fn black_height<K, V>(n: &NodeRef<K, V>) -> Option<usize> {
+        match n {
+            None => Some(0),
+            Some(n) => {
+                if is_red(&n.right) || (n.color == Color::Red && is_red(&n.left)) {
+                    return None;
+                }
+
+                let left = black_height(&n.left)?;
+                let right = black_height(&n.right)?;
+                (left == right).then_some(left + usize::from(n.color == Color::Black))
+            }
+        }
+    }
+
+    black_height(root).is_some()
+}
+
+/// Builds the nodes of a tree from entries that are sorted by key in strictly ascending order.
+///
+/// Left-leaning red-black trees are isomorphic to 2-3 trees, so the shape that results from
+/// inserting the entries one at a time is reproduced by appending every entry to the right-most
+/// leaf of a 2-3 tree and splitting full nodes along the right spine. Every split moves one key up
+/// a level, so the amortized cost of each append is constant. The 2-3 tree is then converted to
+/// its red-black representation, where the smaller key of every 3-node becomes a red left child.
+///
+/// The hashes of the returned nodes are not computed, all of them are marked as dirty.
+fn build_sorted<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
+    entries: Vec<(K, V)>,
+) -> NodeRef<K, V> {
+    /// A node of a 2-3 tree, referencing entries and other nodes by their index.
+    struct TwoThreeNode {
+        entries: Vec<usize>,
+        children: Vec<usize>,
+    }
+
+    fn convert<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
+        nodes: &[TwoThreeNode],
+        entries: &mut [Option<(K, V)>],
+        index: usize,
+    ) -> Node<K, V> {
+        let node = &nodes[index];
+        let mut children = node
+            .children
+            .iter()
+            .map(|child| Arc::new(convert(nodes, entries, *child)))
+            .collect::<Vec<_>>()
+            .into_iter();
+        let mut next_child = || children.next();
+
+        let mut new_node = |entry: usize, color: Color| {
+            let (key, value) = entries[entry].take().expect("every entry is used once");
+            let mut node = Node::new(key, value);
+            node.color = color;
+            node
+        };
+
+        let mut last = match node.entries[..] {
+            [entry] => new_node(entry, Color::Black),
+            [left, right] => {
+                let mut left = new_node(left, Color::Red);
+                left.left = next_child();
+                left.right = next_child();
+
+                let mut right = new_node(right, Color::Black);
+                right.left = Some(Arc::new(left));
+                right
+            }
+            _ => unreachable!("2-3 tree nodes have one or two entries"),
+        };
+        if last.left.is_none() {
+            last.left = next_child();
+        }
+        last.right = next_child();
+
+        last
+    }
+
+    if entries.is_empty() {
+        return None;
+    }
+
+    let mut nodes: Vec<TwoThreeNode> = vec![];
+    // indices of the nodes on the path from the root to the right-most leaf
+    let mut spine: Vec<usize> = vec![];
+
+    for entry in 0..entries.len() {
+        let mut entry = entry;
+        let mut left_child: Option<usize> = None;
+        let mut right_child: Option<usize> = None;
+        let mut level = spine.len();
+
+        loop {
+            if level == 0 {
+                nodes.push(TwoThreeNode {
+                    entries: vec![entry],
+                    children: left_child.into_iter().chain(right_child).collect(),
+                });
+                spine.insert(0, nodes.len() - 1);
+                break;
+            }
+            level -= 1;
+
+            let node = &mut nodes[spine[level]];
+            if node.entries.len() == 1 {
+                node.entries.push(entry);
+                node.children.extend(right_child);
+                break;
+            }
+
+            // Split the temporary 4-node, the middle entry moves up a level.
+            let middle = node.entries.pop().expect("full nodes have two entries");
+            let children = node.children.pop().into_iter().chain(right_child).collect();
+            nodes.push(TwoThreeNode {
+                entries: vec![entry],
+                children,
+            });
+            left_child = Some(spine[level]);
+            right_child = Some(nodes.len() - 1);
+            spine[level] = nodes.len() - 1;
+
+            entry = middle;
+        }
+    }
+
+    let mut entries: Vec<Option<(K, V)>> = entries.into_iter().map(Some).collect();
+    Some(Arc::new(convert(&nodes, &mut entries, spine[0])))
 }
 
 fn three_way_fork(l: HashTree, m: HashTree, r: HashTree) -> HashTree {
@@ -905,60 +1663,65 @@ fn is_red<K, V>(x: &NodeRef<K, V>) -> bool {
     x.as_ref().map(|h| h.color == Color::Red).unwrap_or(false)
 }
 
-fn balance<K: AsRef<[u8]> + 'static, V: AsHashTree + 'static>(
-    mut h: Box<Node<K, V>>,
-) -> Box<Node<K, V>> {
+fn balance<K: AsRef<[u8]> + 'static, V: AsHashTree + 'static, M: NodeMut<K, V>>(
+    mut h: Arc<Node<K, V>>,
+) -> Arc<Node<K, V>> {
     if is_red(&h.right) && !is_red(&h.left) {
-        h = rotate_left(h);
+        h = rotate_left::<K, V, M>(h);
     }
     if is_red(&h.left) && is_red(&h.left.as_ref().unwrap().left) {
-        h = rotate_right(h);
+        h = rotate_right::<K, V, M>(h);
     }
     if is_red(&h.left) && is_red(&h.right) {
-        flip_colors(&mut h)
+        flip_colors::<K, V, M>(&mut h)
     }
     h
 }
 
 /// Make a left-leaning link lean to the right.
-fn rotate_right<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
-    mut h: Box<Node<K, V>>,
-) -> Box<Node<K, V>> {
+fn rotate_right<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static, M: NodeMut<K, V>>(
+    mut h: Arc<Node<K, V>>,
+) -> Arc<Node<K, V>> {
     debug_assert!(is_red(&h.left));
 
-    let mut x = h.left.take().unwrap();
-    h.left = x.right.take();
-    h.update_subtree_hash();
+    let n = M::make_mut(&mut h);
+    let mut x = n.left.take().unwrap();
+    let m = M::make_mut(&mut x);
+    n.left = m.right.take();
+    n.mark_dirty();
 
-    x.right = Some(h);
-    x.color = x.right.as_ref().unwrap().color;
-    x.right.as_mut().unwrap().color = Color::Red;
-    x.update_subtree_hash();
+    m.color = n.color;
+    n.color = Color::Red;
+    m.right = Some(h);
+    m.mark_dirty();
 
     x
 }
 
-fn rotate_left<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static>(
-    mut h: Box<Node<K, V>>,
-) -> Box<Node<K, V>> {
+fn rotate_left<K: 'static + AsRef<[u8]>, V: AsHashTree + 'static, M: NodeMut<K, V>>(
+    mut h: Arc<Node<K, V>>,
+) -> Arc<Node<K, V>> {
     debug_assert!(is_red(&h.right));
 
-    let mut x = h.right.take().unwrap();
-    h.right = x.left.take();
-    h.update_subtree_hash();
+    let n = M::make_mut(&mut h);
+    let mut x = n.right.take().unwrap();
+    let m = M::make_mut(&mut x);
+    n.right = m.left.take();
+    n.mark_dirty();
 
-    x.left = Some(h);
-    x.color = x.left.as_ref().unwrap().color;
-    x.left.as_mut().unwrap().color = Color::Red;
-    x.update_subtree_hash();
+    m.color = n.color;
+    n.color = Color::Red;
+    m.left = Some(h);
+    m.mark_dirty();
 
     x
 }
 
-fn flip_colors<K, V>(h: &mut Box<Node<K, V>>) {
+fn flip_colors<K, V, M: NodeMut<K, V>>(h: &mut Arc<Node<K, V>>) {
+    let h = M::make_mut(h);
     h.color.flip_assign();
-    h.left.as_mut().unwrap().color.flip_assign();
-    h.right.as_mut().unwrap().color.flip_assign();
+    M::make_mut(h.left.as_mut().unwrap()).color.flip_assign();
+    M::make_mut(h.right.as_mut().unwrap()).color.flip_assign();
 }
 
 #[cfg(test)]
@@ -1019,5 +1782,36 @@ impl<'a, K: AsRef<[u8]>, V> fmt::Debug for DebugView<'a, K, V> {
     }
 }
 
+/// An [RbTree] is serialized as a sequence of `(key, value, level)` entries, see
+/// [`entries_with_levels`](RbTree::entries_with_levels), so a deserialized tree has the same root
+/// hash as the serialized one, and is built in linear time.
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::{AsHashTree, RbTree};
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    impl<K, V> Serialize for RbTree<K, V>
+    where
+        K: 'static + AsRef<[u8]> + Serialize,
+        V: AsHashTree + 'static + Serialize,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.entries_with_levels())
+        }
+    }
+
+    impl<'de, K, V> Deserialize<'de> for RbTree<K, V>
+    where
+        K: 'static + AsRef<[u8]> + Deserialize<'de>,
+        V: AsHashTree + 'static + Deserialize<'de>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let entries = Vec::<(K, V, u8)>::deserialize(deserializer)?;
+
+            RbTree::from_entries_with_levels(entries).map_err(de::Error::custom)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests;
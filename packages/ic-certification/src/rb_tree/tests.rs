@@ -1,4 +1,5 @@
 use super::*;
+use crate::{AbsenceResult, RangeLookupResult};
 use std::convert::AsRef;
 
 type TreeOfBytes = RbTree<Vec<u8>, Vec<u8>>;
@@ -389,3 +390,535 @@ fn test_ordering() {
     assert_eq!(t1.cmp(&t3), Greater);
     assert_eq!(t1.cmp(&t4), Less);
 }
+
+#[test]
+fn test_from_sorted() {
+    for n in (0..64u64).chain([127, 128, 255, 256, 1000]) {
+        let entries: Vec<_> = (0..n)
+            .map(|k| (k.to_be_bytes().to_vec(), (k + 10).to_be_bytes().to_vec()))
+            .collect();
+
+        let mut expected = TreeOfBytes::new();
+        for (k, v) in entries.iter() {
+            insert(&mut expected, k, v);
+        }
+        let t = TreeOfBytes::from_sorted(entries);
+
+        assert_eq!(t.root_hash(), expected.root_hash(), "size: {}", n);
+        assert_eq!(
+            format!("{:?}", DebugView(&t.root)),
+            format!("{:?}", DebugView(&expected.root)),
+            "size: {}",
+            n
+        );
+        assert!(is_balanced(&t.root));
+        assert_eq!(t, expected);
+    }
+}
+
+#[test]
+fn test_from_sorted_with_unsorted_entries() {
+    let entries: Vec<_> = [3u64, 1, 4, 1, 5, 9, 2, 6]
+        .iter()
+        .enumerate()
+        .map(|(i, k)| (k.to_be_bytes().to_vec(), i.to_be_bytes().to_vec()))
+        .collect();
+
+    let mut expected = TreeOfBytes::new();
+    for (k, v) in entries.iter() {
+        insert(&mut expected, k, v);
+    }
+    let t = TreeOfBytes::from_sorted(entries);
+
+    assert_eq!(t.root_hash(), expected.root_hash());
+    assert!(is_balanced(&t.root));
+    assert_eq!(t, expected);
+}
+
+#[test]
+fn test_insert_many() {
+    let mut t = TreeOfBytes::new();
+    let mut expected = TreeOfBytes::new();
+    for k in (0..50u64).rev() {
+        insert(&mut t, k.to_be_bytes(), k.to_be_bytes());
+        insert(&mut expected, k.to_be_bytes(), k.to_be_bytes());
+    }
+
+    let entries: Vec<_> = (25..100u64)
+        .rev()
+        .map(|k| (k.to_be_bytes().to_vec(), (k + 10).to_be_bytes().to_vec()))
+        .collect();
+    for (k, v) in entries.iter() {
+        insert(&mut expected, k, v);
+    }
+    t.insert_many(entries);
+
+    assert_eq!(t.root_hash(), expected.root_hash());
+    assert_eq!(t, expected);
+    assert!(is_balanced(&t.root));
+
+    for k in 0..100u64 {
+        let key = k.to_be_bytes();
+        assert_eq!(t.witness(&key[..]).digest(), t.root_hash());
+    }
+}
+
+#[test]
+fn test_clone_is_a_snapshot() {
+    fn update(t: &mut TreeOfBytes) {
+        for k in (0..150u64).step_by(7) {
+            insert(t, k.to_be_bytes(), [1]);
+        }
+        for k in (0..100u64).step_by(3) {
+            t.delete(&k.to_be_bytes());
+        }
+        t.modify(&1u64.to_be_bytes(), |v| v.push(1));
+    }
+
+    let mut t = TreeOfBytes::new();
+    for k in 0..100u64 {
+        insert(&mut t, k.to_be_bytes(), k.to_be_bytes());
+    }
+    let mut expected = t.clone();
+    let snapshot = t.clone();
+    let snapshot_hash = snapshot.root_hash();
+    let snapshot_entries: Vec<_> = snapshot
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+
+    update(&mut t);
+
+    assert_ne!(t.root_hash(), snapshot_hash);
+    assert_eq!(snapshot.root_hash(), snapshot_hash);
+    assert!(is_balanced(&snapshot.root));
+    let entries: Vec<_> = snapshot
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    assert_eq!(entries, snapshot_entries);
+    assert_eq!(snapshot, TreeOfBytes::from_sorted(snapshot_entries));
+
+    drop(snapshot);
+    update(&mut expected);
+    assert_eq!(t.root_hash(), expected.root_hash());
+    assert_eq!(t, expected);
+}
+
+#[test]
+fn test_persistent_clone_is_a_snapshot() {
+    let mut t = PersistentRbTree::<Vec<u8>, Vec<u8>>::new();
+    let mut expected = TreeOfBytes::new();
+    for k in 0..100u64 {
+        t.insert(k.to_be_bytes().to_vec(), k.to_be_bytes().to_vec());
+        insert(&mut expected, k.to_be_bytes(), k.to_be_bytes());
+    }
+    let snapshot = t.clone();
+    let snapshot_hash = snapshot.root_hash();
+    assert_eq!(snapshot_hash, expected.root_hash());
+
+    t.insert_many(
+        (0..150u64)
+            .step_by(7)
+            .map(|k| (k.to_be_bytes().to_vec(), vec![1])),
+    );
+    t.delete_many((0..100u64).step_by(3).map(|k| k.to_be_bytes()));
+    t.modify(&1u64.to_be_bytes(), |v| v.push(1));
+    for k in (0..150u64).step_by(7) {
+        insert(&mut expected, k.to_be_bytes(), [1]);
+    }
+    for k in (0..100u64).step_by(3) {
+        expected.delete(&k.to_be_bytes());
+    }
+    expected.modify(&1u64.to_be_bytes(), |v| v.push(1));
+
+    assert_eq!(snapshot.root_hash(), snapshot_hash);
+    assert!(is_balanced(&snapshot.root));
+    assert!(snapshot
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .eq((0..100u64).map(|k| (k.to_be_bytes().to_vec(), k.to_be_bytes().to_vec()))));
+
+    assert!(is_balanced(&t.root));
+    assert_eq!(t.root_hash(), expected.root_hash());
+    assert_eq!(*t, expected);
+}
+
+#[test]
+fn test_persistent_updates_share_unchanged_nodes() {
+    fn nodes<K, V>(n: &NodeRef<K, V>, out: &mut Vec<*const Node<K, V>>) {
+        if let Some(n) = n {
+            out.push(Arc::as_ptr(n));
+            nodes(&n.left, out);
+            nodes(&n.right, out);
+        }
+    }
+
+    let mut t = PersistentRbTree::<Vec<u8>, Vec<u8>>::new();
+    for k in 0..1000u64 {
+        t.insert(k.to_be_bytes().to_vec(), k.to_be_bytes().to_vec());
+    }
+    let snapshot = t.clone();
+    t.insert(500u64.to_be_bytes().to_vec(), vec![1]);
+    t.delete(&250u64.to_be_bytes());
+
+    let mut before = vec![];
+    nodes(&snapshot.root, &mut before);
+    let mut after = vec![];
+    nodes(&t.root, &mut after);
+    let copied = after.iter().filter(|n| !before.contains(n)).count();
+
+    assert_eq!(after.len(), 999);
+    assert!(
+        copied <= 60,
+        "{} of {} nodes were copied",
+        copied,
+        after.len()
+    );
+}
+
+#[test]
+fn test_diff() {
+    let mut old = TreeOfBytes::new();
+    for k in 0..100u64 {
+        insert(&mut old, k.to_be_bytes(), k.to_be_bytes());
+    }
+    let mut new = old.clone();
+    for k in (0..100u64).step_by(10) {
+        new.delete(&k.to_be_bytes());
+    }
+    for k in (5..100u64).step_by(30) {
+        insert(&mut new, k.to_be_bytes(), [1]);
+    }
+    insert(&mut new, 7u64.to_be_bytes(), 7u64.to_be_bytes());
+    for k in 100..105u64 {
+        insert(&mut new, k.to_be_bytes(), k.to_be_bytes());
+    }
+
+    let mut expected = vec![];
+    for k in 0..105u64 {
+        let key = k.to_be_bytes().to_vec();
+        let change = match (old.get(&key), new.get(&key)) {
+            (Some(o), None) => RbTreeChange::Removed(&key, o),
+            (None, Some(n)) => RbTreeChange::Added(&key, n),
+            (Some(o), Some(n)) if o != n => RbTreeChange::Changed(&key, o, n),
+            _ => continue,
+        };
+        expected.push(format!("{:?}", change));
+    }
+
+    let rebuilt = TreeOfBytes::from_sorted(new.iter().map(|(k, v)| (k.clone(), v.clone())));
+    for new in [&new, &rebuilt] {
+        let changes: Vec<_> = old
+            .diff(new)
+            .into_iter()
+            .map(|change| format!("{:?}", change))
+            .collect();
+        assert_eq!(changes, expected);
+    }
+
+    assert_eq!(old.diff(&old), vec![]);
+    assert_eq!(old.diff(&old.clone()), vec![]);
+    assert_eq!(TreeOfBytes::new().diff(&TreeOfBytes::new()), vec![]);
+    assert_eq!(old.diff(&TreeOfBytes::new()).len(), 100);
+}
+
+#[test]
+fn test_delete_many() {
+    let mut t = TreeOfBytes::new();
+    let mut expected = TreeOfBytes::new();
+    for k in 0..100u64 {
+        insert(&mut t, k.to_be_bytes(), k.to_be_bytes());
+        insert(&mut expected, k.to_be_bytes(), k.to_be_bytes());
+    }
+
+    let keys: Vec<_> = (0..150u64).step_by(3).map(|k| k.to_be_bytes()).collect();
+    for k in keys.iter() {
+        expected.delete(k);
+    }
+    t.delete_many(keys.iter());
+
+    assert_eq!(t.root_hash(), expected.root_hash());
+    assert_eq!(t, expected);
+    assert!(is_balanced(&t.root));
+
+    for k in 0..100u64 {
+        let key = k.to_be_bytes();
+        assert_eq!(t.witness(&key[..]).digest(), t.root_hash());
+    }
+}
+
+#[test]
+fn test_absence_proof() {
+    let mut t = TreeOfBytes::new();
+    let ht = t.absence_proof(b"key").unwrap();
+    assert_eq!(ht.digest(), t.root_hash());
+    assert_eq!(ht.verify_absent([b"key"]), AbsenceResult::Absent);
+
+    for i in 0u64..10 {
+        let key = (1 + 2 * i).to_be_bytes();
+        insert(&mut t, key, key);
+    }
+
+    for i in 0u64..21 {
+        let key = i.to_be_bytes();
+        let absence_proof = t.absence_proof(&key[..]);
+        if i % 2 == 1 {
+            assert_eq!(absence_proof, None, "key: {}", hex::encode(key));
+            assert_eq!(
+                t.witness(&key[..]).verify_absent([key]),
+                AbsenceResult::Present,
+                "key: {}",
+                hex::encode(key)
+            );
+            continue;
+        }
+
+        let ht = absence_proof.unwrap();
+        assert_eq!(
+            ht.digest(),
+            t.root_hash(),
+            "key: {}, witness {:?}",
+            hex::encode(key),
+            ht
+        );
+        assert_eq!(
+            ht.verify_absent([key]),
+            AbsenceResult::Absent,
+            "key: {}, witness {:?}",
+            hex::encode(key),
+            ht
+        );
+    }
+
+    // a proof for one key does not prove the absence of keys that are far away from it
+    let ht = t.absence_proof(&0u64.to_be_bytes()).unwrap();
+    assert_eq!(
+        ht.verify_absent([20u64.to_be_bytes()]),
+        AbsenceResult::Unknown
+    );
+}
+
+#[test]
+fn test_witness_values_with_prefix() {
+    let mut t = TreeOfBytes::new();
+    insert(&mut t, b"a", b"u");
+    insert(&mut t, b"ba", b"v");
+    insert(&mut t, b"bb", b"w");
+    insert(&mut t, b"c", b"x");
+
+    let ht = t.values_with_prefix(b"b");
+    assert_eq!(ht.digest(), t.root_hash());
+    assert_eq!(
+        get_labels(&ht.root),
+        vec![b"a" as &[u8], b"ba", b"bb", b"c"]
+    );
+    assert_eq!(get_leaf_values(&ht.root), vec![b"v", b"w"]);
+
+    let ht = t.values_with_prefix(b"d");
+    assert_eq!(ht.digest(), t.root_hash());
+    assert_eq!(get_labels(&ht.root), vec![b"c"]);
+    assert!(get_leaf_values(&ht.root).is_empty());
+}
+
+#[test]
+fn test_value_page() {
+    use std::ops::Bound;
+
+    let mut t = TreeOfBytes::new();
+    for i in 0u64..10 {
+        let key = (2 * i).to_be_bytes();
+        insert(&mut t, key, key);
+    }
+
+    for limit in 0..12 {
+        let mut keys = vec![];
+        let mut cursor = None;
+        loop {
+            let (ht, next) = t.value_page(cursor.as_deref(), limit);
+            assert_eq!(
+                ht.digest(),
+                t.root_hash(),
+                "limit: {}, witness {:?}",
+                limit,
+                ht
+            );
+
+            let start = cursor.as_deref().map_or(Bound::Unbounded, Bound::Included);
+            let end = next.map_or(Bound::Unbounded, |next| Bound::Excluded(next.as_slice()));
+            let RangeLookupResult::Found(page) =
+                ht.lookup_range(std::iter::empty::<&[u8]>(), (start, end))
+            else {
+                panic!("incomplete page, limit: {}, witness {:?}", limit, ht);
+            };
+            assert_eq!(page.len(), limit.clamp(1, 10 - keys.len()));
+            keys.extend(page.into_iter().map(|(key, _)| key.as_bytes().to_vec()));
+
+            match next {
+                Some(next) => cursor = Some(next.clone()),
+                None => break,
+            }
+        }
+
+        assert_eq!(
+            keys,
+            t.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>()
+        );
+    }
+
+    let (ht, next) = t.value_page(Some(&5u64.to_be_bytes()[..]), 2);
+    assert_eq!(next, Some(&10u64.to_be_bytes().to_vec()));
+    assert_eq!(
+        get_leaf_values(&ht.root),
+        vec![6u64.to_be_bytes(), 8u64.to_be_bytes()]
+    );
+}
+
+#[test]
+fn test_iter_from() {
+    let mut t = TreeOfBytes::new();
+    assert_eq!(t.iter_from(None).count(), 0);
+    assert_eq!(t.iter_from(Some(&[1u8][..])).count(), 0);
+
+    for i in 0u8..50 {
+        insert(&mut t, [2 * i], [i]);
+
+        assert_eq!(
+            t.iter_from(None).collect::<Vec<_>>(),
+            t.iter().collect::<Vec<_>>()
+        );
+        for cursor in 0u8..=(2 * i + 2) {
+            assert_eq!(
+                t.iter_from(Some(&[cursor][..])).collect::<Vec<_>>(),
+                t.iter()
+                    .filter(|(key, _)| key.as_slice() >= &[cursor][..])
+                    .collect::<Vec<_>>(),
+                "cursor: {}",
+                cursor
+            );
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_witness_size() {
+    let mut t = TreeOfBytes::new();
+    for i in 0u64..100 {
+        let key = (2 * i).to_be_bytes();
+        insert(&mut t, key, vec![1; i as usize * 3]);
+    }
+
+    for i in 0u64..200 {
+        let key = i.to_be_bytes();
+        assert_eq!(
+            t.witness_size(&key[..]),
+            serde_cbor::to_vec(&t.witness(&key[..])).unwrap().len(),
+            "key: {}",
+            hex::encode(key)
+        );
+    }
+}
+
+#[test]
+fn test_entries_with_levels() {
+    let mut t = TreeOfBytes::new();
+    for k in (0..100u64).rev() {
+        insert(&mut t, (k * 37 % 100).to_be_bytes(), k.to_be_bytes());
+    }
+    for k in (0..100u64).step_by(4) {
+        t.delete(&k.to_be_bytes());
+    }
+
+    let entries: Vec<_> = t
+        .entries_with_levels()
+        .into_iter()
+        .map(|(k, v, level)| (k.clone(), v.clone(), level))
+        .collect();
+    let restored = TreeOfBytes::from_entries_with_levels(entries).unwrap();
+
+    assert_eq!(restored.root_hash(), t.root_hash());
+    assert_eq!(
+        format!("{:?}", DebugView(&restored.root)),
+        format!("{:?}", DebugView(&t.root))
+    );
+    assert!(TreeOfBytes::from_entries_with_levels(vec![])
+        .unwrap()
+        .is_empty());
+
+    let entries = |entries: &[(&[u8], u8)]| -> Vec<(Vec<u8>, Vec<u8>, u8)> {
+        entries
+            .iter()
+            .map(|(k, level)| (k.to_vec(), k.to_vec(), *level))
+            .collect()
+    };
+    assert_eq!(
+        TreeOfBytes::from_entries_with_levels(entries(&[(b"b", 0), (b"a", 1)])),
+        Err(RbTreeLevelsError::UnsortedKeys)
+    );
+    assert_eq!(
+        TreeOfBytes::from_entries_with_levels(entries(&[(b"a", 1), (b"b", 0)])),
+        Err(RbTreeLevelsError::InvalidLevels)
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip() {
+    let mut t = TreeOfBytes::new();
+    let round_trip = |t: &TreeOfBytes| -> TreeOfBytes {
+        serde_cbor::from_slice(&serde_cbor::to_vec(t).unwrap()).unwrap()
+    };
+    assert_eq!(round_trip(&t).root_hash(), t.root_hash());
+
+    for k in (0..200u64).rev() {
+        insert(&mut t, (k * 7919 % 200).to_be_bytes(), k.to_be_bytes());
+    }
+    for k in (0..200u64).step_by(3) {
+        t.delete(&k.to_be_bytes());
+    }
+    // the shape of the tree depends on the order of the updates
+    let sorted = TreeOfBytes::from_sorted(t.iter().map(|(k, v)| (k.clone(), v.clone())));
+    assert_ne!(sorted.root_hash(), t.root_hash());
+
+    let mut deserialized = round_trip(&t);
+    assert_eq!(deserialized.root_hash(), t.root_hash());
+    assert_eq!(deserialized, t);
+    assert!(is_balanced(&deserialized.root));
+
+    for k in 150..250u64 {
+        insert(&mut t, k.to_be_bytes(), [1]);
+        insert(&mut deserialized, k.to_be_bytes(), [1]);
+    }
+    for k in (0..250u64).step_by(5) {
+        t.delete(&k.to_be_bytes());
+        deserialized.delete(&k.to_be_bytes());
+    }
+    assert_eq!(deserialized.root_hash(), t.root_hash());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_rejects_invalid_trees() {
+    let deserialize = |entries: &[(&[u8], &[u8], u8)]| {
+        serde_cbor::from_slice::<TreeOfBytes>(&serde_cbor::to_vec(&entries).unwrap())
+    };
+
+    let valid: &[(&[u8], &[u8], u8)] = &[(b"a", b"1", 0), (b"b", b"2", 1), (b"c", b"3", 0)];
+    assert!(deserialize(valid).is_ok());
+
+    let unsorted: &[(&[u8], &[u8], u8)] = &[(b"b", b"1", 0), (b"a", b"2", 1), (b"c", b"3", 0)];
+    assert!(deserialize(unsorted).is_err());
+
+    let duplicate: &[(&[u8], &[u8], u8)] = &[(b"a", b"1", 0), (b"a", b"2", 1), (b"c", b"3", 0)];
+    assert!(deserialize(duplicate).is_err());
+
+    let unbalanced: &[(&[u8], &[u8], u8)] = &[(b"a", b"1", 0), (b"b", b"2", 1), (b"c", b"3", 1)];
+    assert!(deserialize(unbalanced).is_err());
+
+    let three_node: &[(&[u8], &[u8], u8)] = &[(b"a", b"1", 0), (b"b", b"2", 0)];
+    assert!(deserialize(three_node).is_ok());
+
+    let missing_leaf: &[(&[u8], &[u8], u8)] = &[(b"a", b"1", 1), (b"b", b"2", 0)];
+    assert!(deserialize(missing_leaf).is_err());
+}
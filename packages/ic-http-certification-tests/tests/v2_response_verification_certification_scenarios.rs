@@ -198,17 +198,17 @@ mod tests {
     #[case::etag_match(
         etag_caching_match_request(),
         etag_caching_match_response(),
-        etag_caching_match_certification(&HttpCertificationPath::Exact("/app"))
+        etag_caching_match_certification(&HttpCertificationPath::exact("/app"))
     )]
     #[case::etag_match_mismatch_response(
         etag_caching_match_request(),
         etag_caching_mismatch_response(),
-        etag_caching_mismatch_certification(&HttpCertificationPath::Exact("/app"))
+        etag_caching_mismatch_certification(&HttpCertificationPath::exact("/app"))
     )]
     #[case::etag_match_mismatch_response(
         etag_caching_mismatch_request(),
         etag_caching_mismatch_response(),
-        etag_caching_mismatch_certification(&HttpCertificationPath::Exact("/app"))
+        etag_caching_mismatch_certification(&HttpCertificationPath::exact("/app"))
     )]
     fn etag_scenarios_pass_verification(
         #[from(etag_certificate_tree)] certification_tree: HttpCertificationTree,
@@ -274,7 +274,7 @@ mod tests {
     ) {
         let req_path = "/app";
         let http_certification_tree_entry =
-            etag_caching_match_certification(&HttpCertificationPath::Exact("/app"));
+            etag_caching_match_certification(&HttpCertificationPath::exact("/app"));
         let current_time = get_current_timestamp();
 
         let V2CertificateFixture {
@@ -347,12 +347,11 @@ mod fixtures {
     #[fixture]
     pub fn index_html_certification() -> HttpCertificationTreeEntry<'static> {
         HttpCertificationTreeEntry {
-            path: Cow::Borrowed(&HttpCertificationPath::Wildcard("")),
-            certification: Cow::Owned(HttpCertification::response_only(
-                &asset_cel(),
-                &index_html_response(),
-                None,
-            )),
+            path: Cow::Borrowed(&HttpCertificationPath::wildcard("")),
+            certification: Cow::Owned(
+                HttpCertification::response_only(&asset_cel(), &index_html_response(), None)
+                    .unwrap(),
+            ),
         }
     }
 
@@ -376,12 +375,10 @@ mod fixtures {
     #[fixture]
     pub fn index_js_certification() -> HttpCertificationTreeEntry<'static> {
         HttpCertificationTreeEntry {
-            path: Cow::Borrowed(&HttpCertificationPath::Exact("/js/index.js")),
-            certification: Cow::Owned(HttpCertification::response_only(
-                &asset_cel(),
-                &index_js_response(),
-                None,
-            )),
+            path: Cow::Borrowed(&HttpCertificationPath::exact("/js/index.js")),
+            certification: Cow::Owned(
+                HttpCertification::response_only(&asset_cel(), &index_js_response(), None).unwrap(),
+            ),
         }
     }
 
@@ -405,12 +402,11 @@ mod fixtures {
     #[fixture]
     pub fn not_found_certification() -> HttpCertificationTreeEntry<'static> {
         HttpCertificationTreeEntry {
-            path: Cow::Borrowed(&HttpCertificationPath::Wildcard("/js")),
-            certification: Cow::Owned(HttpCertification::response_only(
-                &asset_cel(),
-                &not_found_response(),
-                None,
-            )),
+            path: Cow::Borrowed(&HttpCertificationPath::wildcard("/js")),
+            certification: Cow::Owned(
+                HttpCertification::response_only(&asset_cel(), &not_found_response(), None)
+                    .unwrap(),
+            ),
         }
     }
 
@@ -433,12 +429,11 @@ mod fixtures {
     #[fixture]
     pub fn redirect_certification() -> HttpCertificationTreeEntry<'static> {
         HttpCertificationTreeEntry {
-            path: Cow::Borrowed(&HttpCertificationPath::Exact("/old-path")),
-            certification: Cow::Owned(HttpCertification::response_only(
-                &redirect_cel(),
-                &redirect_response(),
-                None,
-            )),
+            path: Cow::Borrowed(&HttpCertificationPath::exact("/old-path")),
+            certification: Cow::Owned(
+                HttpCertification::response_only(&redirect_cel(), &redirect_response(), None)
+                    .unwrap(),
+            ),
         }
     }
 
@@ -461,12 +456,15 @@ mod fixtures {
     #[fixture]
     pub fn content_encoding_identity_certification() -> HttpCertificationTreeEntry<'static> {
         HttpCertificationTreeEntry {
-            path: Cow::Borrowed(&HttpCertificationPath::Exact("/multi-encoded-path")),
-            certification: Cow::Owned(HttpCertification::response_only(
-                &asset_cel(),
-                &content_encoding_identity_response(),
-                None,
-            )),
+            path: Cow::Borrowed(&HttpCertificationPath::exact("/multi-encoded-path")),
+            certification: Cow::Owned(
+                HttpCertification::response_only(
+                    &asset_cel(),
+                    &content_encoding_identity_response(),
+                    None,
+                )
+                .unwrap(),
+            ),
         }
     }
 
@@ -489,12 +487,15 @@ mod fixtures {
     #[fixture]
     pub fn content_encoding_gzip_certification() -> HttpCertificationTreeEntry<'static> {
         HttpCertificationTreeEntry {
-            path: Cow::Borrowed(&HttpCertificationPath::Exact("/multi-encoded-path")),
-            certification: Cow::Owned(HttpCertification::response_only(
-                &asset_cel(),
-                &content_encoding_gzip_response(),
-                None,
-            )),
+            path: Cow::Borrowed(&HttpCertificationPath::exact("/multi-encoded-path")),
+            certification: Cow::Owned(
+                HttpCertification::response_only(
+                    &asset_cel(),
+                    &content_encoding_gzip_response(),
+                    None,
+                )
+                .unwrap(),
+            ),
         }
     }
 
@@ -517,12 +518,15 @@ mod fixtures {
     #[fixture]
     pub fn content_encoding_deflate_certification() -> HttpCertificationTreeEntry<'static> {
         HttpCertificationTreeEntry {
-            path: Cow::Borrowed(&HttpCertificationPath::Exact("/multi-encoded-path")),
-            certification: Cow::Owned(HttpCertification::response_only(
-                &asset_cel(),
-                &content_encoding_deflate_response(),
-                None,
-            )),
+            path: Cow::Borrowed(&HttpCertificationPath::exact("/multi-encoded-path")),
+            certification: Cow::Owned(
+                HttpCertification::response_only(
+                    &asset_cel(),
+                    &content_encoding_deflate_response(),
+                    None,
+                )
+                .unwrap(),
+            ),
         }
     }
 
@@ -561,7 +565,7 @@ mod fixtures {
 
     #[fixture]
     pub fn etag_caching_match_certification(
-        #[default(&HttpCertificationPath::Exact(""))] path: &'static HttpCertificationPath,
+        #[default(&HttpCertificationPath::exact(""))] path: &'static HttpCertificationPath,
     ) -> HttpCertificationTreeEntry<'static> {
         HttpCertificationTreeEntry {
             path: Cow::Borrowed(path),
@@ -614,15 +618,18 @@ mod fixtures {
 
     #[fixture]
     pub fn etag_caching_mismatch_certification(
-        #[default(&HttpCertificationPath::Exact(""))] path: &'static HttpCertificationPath,
+        #[default(&HttpCertificationPath::exact(""))] path: &'static HttpCertificationPath,
     ) -> HttpCertificationTreeEntry<'static> {
         HttpCertificationTreeEntry {
             path: Cow::Borrowed(path),
-            certification: Cow::Owned(HttpCertification::response_only(
-                &etag_caching_mismatch_cel(),
-                &etag_caching_mismatch_response(),
-                None,
-            )),
+            certification: Cow::Owned(
+                HttpCertification::response_only(
+                    &etag_caching_mismatch_cel(),
+                    &etag_caching_mismatch_response(),
+                    None,
+                )
+                .unwrap(),
+            ),
         }
     }
 
@@ -683,16 +690,16 @@ mod fixtures {
         let mut http_certification_tree = HttpCertificationTree::default();
 
         http_certification_tree.insert(&etag_caching_match_certification(
-            &HttpCertificationPath::Exact("/app"),
+            &HttpCertificationPath::exact("/app"),
         ));
         http_certification_tree.insert(&etag_caching_match_certification(
-            &HttpCertificationPath::Exact("/app/"),
+            &HttpCertificationPath::exact("/app/"),
         ));
         http_certification_tree.insert(&etag_caching_mismatch_certification(
-            &HttpCertificationPath::Exact("/app"),
+            &HttpCertificationPath::exact("/app"),
         ));
         http_certification_tree.insert(&etag_caching_mismatch_certification(
-            &HttpCertificationPath::Exact("/app/"),
+            &HttpCertificationPath::exact("/app/"),
         ));
 
         http_certification_tree
@@ -1,15 +1,24 @@
 mod tests {
     use ic_http_certification::{
-        DefaultCelBuilder, DefaultResponseCertification, HttpCertification, HttpCertificationPath,
+        cel::{
+            CelAttribute, CelBinaryOperator, CelCondition, ConditionalCelExpression,
+            DefaultCelExpression, DuplicateResponseHeaderMode, QueryParameterCertificationMode,
+            RequestBodyCertificationMode, ResponseHeaderCertificationOptions,
+            ResponseHeaderValueMode,
+        },
+        debug_self_verify, CelExpression, DefaultCelBuilder, DefaultResponseCertification,
+        HttpCertification, HttpCertificationPath, HttpCertificationTree,
         HttpCertificationTreeEntry, HttpRequest, HttpResponse,
     };
     use ic_response_verification::{
         types::{VerificationInfo, VerifiedResponse},
-        verify_request_response_pair,
+        verify_request_response_pair, verify_request_response_pairs,
     };
     use ic_response_verification_test_utils::{
-        create_v2_fixture, get_current_timestamp, V2Fixture,
+        cbor_encode, create_v2_certificate_fixture, create_v2_fixture, create_v2_header,
+        get_current_timestamp, V2CertificateFixture, V2Fixture,
     };
+    use rstest::*;
 
     const MAX_CERT_TIME_OFFSET_NS: u128 = 300_000_000_000;
     const MIN_REQUESTED_VERIFICATION_VERSION: u8 = 2;
@@ -19,7 +28,7 @@ mod tests {
         let req_path = "/";
         let body = "Hello World!";
         let current_time = get_current_timestamp();
-        let certification_path = HttpCertificationPath::Exact("/");
+        let certification_path = HttpCertificationPath::exact("/");
         let cel_expr = DefaultCelBuilder::skip_certification();
 
         let request = HttpRequest {
@@ -77,7 +86,7 @@ mod tests {
         let req_path = "/";
         let body = "Hello World!";
         let current_time = get_current_timestamp();
-        let certification_path = HttpCertificationPath::Exact("/");
+        let certification_path = HttpCertificationPath::exact("/");
 
         let cel_expr = DefaultCelBuilder::response_only_certification()
             .with_response_certification(DefaultResponseCertification::certified_response_headers(
@@ -101,7 +110,7 @@ mod tests {
             upgrade: None,
         };
 
-        let certification = HttpCertification::response_only(&cel_expr, &response, None);
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
         let certification_tree_entry =
             HttpCertificationTreeEntry::new(&certification_path, &certification);
 
@@ -141,12 +150,101 @@ mod tests {
         ));
     }
 
+    #[rstest]
+    #[case::success(200, vec![("cache-control".into(), "max-age=604800".into())])]
+    #[case::not_found(404, vec![])]
+    fn conditional_certification_passes_verification(
+        #[case] status_code: u16,
+        #[case] expected_headers: Vec<(String, String)>,
+    ) {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = CelExpression::Conditional(ConditionalCelExpression {
+            condition: CelCondition::binary(
+                CelBinaryOperator::Eq,
+                CelCondition::Attribute(CelAttribute::ResponseStatusCode),
+                CelCondition::Int(200),
+            ),
+            if_true: Box::new(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+                DefaultCelBuilder::response_only_certification()
+                    .with_response_certification(
+                        DefaultResponseCertification::certified_response_headers(&[
+                            "Cache-Control",
+                        ]),
+                    )
+                    .build(),
+            ))),
+            if_false: Box::new(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+                DefaultCelBuilder::response_only_certification().build(),
+            ))),
+        });
+
+        let request = HttpRequest {
+            url: req_path.into(),
+            method: "GET".into(),
+            headers: vec![],
+            body: vec![],
+        };
+        let mut response = HttpResponse {
+            status_code,
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("IC-CertificateExpression".into(), cel_expr.to_string()),
+                ("Cache-Control".into(), "max-age=604800".into()),
+            ],
+            upgrade: None,
+        };
+
+        let certification =
+            HttpCertification::from_cel_expr(&cel_expr, &request, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, &certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response
+            .headers
+            .push(("IC-Certificate".into(), certificate_header));
+
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        let expected_response = VerifiedResponse {
+            status_code: Some(status_code),
+            body: body.as_bytes().to_vec(),
+            headers: expected_headers,
+        };
+
+        assert!(matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+            } if verification_version == 2 && response == Some(expected_response)
+        ));
+    }
+
     #[test]
     fn full_certification_passes_verification() {
         let req_path = "/?q=greeting";
         let body = "Hello World!";
         let current_time = get_current_timestamp();
-        let certification_path = HttpCertificationPath::Exact("/");
+        let certification_path = HttpCertificationPath::exact("/");
 
         let cel_expr = DefaultCelBuilder::full_certification()
             .with_request_headers(&["Cache-Control"])
@@ -215,17 +313,166 @@ mod tests {
         ));
     }
 
+    #[rstest]
+    #[case::same_query("/?q=greeting&lang=en")]
+    #[case::reordered_query("/?lang=en&q=greeting")]
+    #[case::encoded_query("/?LANG=en&q=gr%65eting&page=2")]
+    fn canonical_query_certification_passes_verification(#[case] req_url: &str) {
+        let certified_req_url = "/?q=greeting&lang=en";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_query_parameters(&["q", "lang"])
+            .with_request_query_parameter_mode(QueryParameterCertificationMode::Canonical)
+            .build();
+
+        let certified_request = HttpRequest {
+            url: certified_req_url.into(),
+            method: "GET".into(),
+            headers: vec![],
+            body: vec![],
+        };
+        let mut response = HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![("IC-CertificateExpression".into(), cel_expr.to_string())],
+            upgrade: None,
+        };
+
+        let certification =
+            HttpCertification::full(&cel_expr, &certified_request, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, &certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(certified_req_url, &certification_tree_entry, &current_time);
+
+        response
+            .headers
+            .push(("IC-Certificate".into(), certificate_header));
+
+        let request = HttpRequest {
+            url: req_url.into(),
+            ..certified_request
+        };
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        let expected_response = VerifiedResponse {
+            status_code: Some(200),
+            body: body.as_bytes().to_vec(),
+            headers: vec![],
+        };
+
+        assert!(matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+            } if verification_version == 2 && response == Some(expected_response)
+        ));
+    }
+
+    #[rstest]
+    #[case::same_body(r#"{"name":"greeting","lang":"en"}"#)]
+    #[case::reformatted_body("{\n  \"lang\": \"en\",\n  \"name\": \"greeting\"\n}")]
+    fn canonical_json_body_certification_passes_verification(#[case] req_body: &str) {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_body_mode(RequestBodyCertificationMode::CanonicalJson)
+            .build();
+
+        let certified_request = HttpRequest {
+            url: req_path.into(),
+            method: "POST".into(),
+            headers: vec![],
+            body: r#"{"name":"greeting","lang":"en"}"#.as_bytes().to_vec(),
+        };
+        let mut response = HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![("IC-CertificateExpression".into(), cel_expr.to_string())],
+            upgrade: None,
+        };
+
+        let certification =
+            HttpCertification::full(&cel_expr, &certified_request, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, &certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response
+            .headers
+            .push(("IC-Certificate".into(), certificate_header));
+
+        let request = HttpRequest {
+            body: req_body.as_bytes().to_vec(),
+            ..certified_request
+        };
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        let expected_response = VerifiedResponse {
+            status_code: Some(200),
+            body: body.as_bytes().to_vec(),
+            headers: vec![],
+        };
+
+        assert!(matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+            } if verification_version == 2 && response == Some(expected_response)
+        ));
+    }
+
     #[test]
-    fn response_certification_with_header_exclusions_passes_verification() {
+    fn response_header_options_pass_verification() {
         let req_path = "/";
         let body = "Hello World!";
         let current_time = get_current_timestamp();
-        let expr_path = HttpCertificationPath::Exact("/");
+        let certification_path = HttpCertificationPath::exact("/");
 
         let cel_expr = DefaultCelBuilder::response_only_certification()
-            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
-                &["Content-Language", "Content-Encoding"],
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                &["Cache-Control", "Set-Cookie"],
             ))
+            .with_response_header_options(
+                ResponseHeaderCertificationOptions::default()
+                    .with_duplicate_header_mode(DuplicateResponseHeaderMode::Array)
+                    .with_header_value_mode(ResponseHeaderValueMode::Trim),
+            )
             .build();
 
         let request = HttpRequest {
@@ -234,21 +481,112 @@ mod tests {
             headers: vec![],
             body: vec![],
         };
+        let certified_response = HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("IC-CertificateExpression".into(), cel_expr.to_string()),
+                ("Set-Cookie".into(), "session=abc".into()),
+                ("Cache-Control".into(), "max-age=604800".into()),
+                ("Set-Cookie".into(), "theme=dark".into()),
+            ],
+            upgrade: None,
+        };
+
+        let certification =
+            HttpCertification::response_only(&cel_expr, &certified_response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, &certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        // the same response, served with padded header values and a different header order
+        let response = HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("IC-CertificateExpression".into(), cel_expr.to_string()),
+                ("Cache-Control".into(), " max-age=604800".into()),
+                ("Set-Cookie".into(), "session=abc\t".into()),
+                ("set-cookie".into(), "theme=dark ".into()),
+                ("IC-Certificate".into(), certificate_header),
+            ],
+            upgrade: None,
+        };
+
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        let expected_response = VerifiedResponse {
+            status_code: Some(200),
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("cache-control".into(), " max-age=604800".into()),
+                ("set-cookie".into(), "session=abc\t".into()),
+                ("set-cookie".into(), "theme=dark ".into()),
+            ],
+        };
+
+        assert!(matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+            } if verification_version == 2 && response == Some(expected_response)
+        ));
+    }
+
+    #[test]
+    fn header_name_patterns_pass_verification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(&["X-App-*"])
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                &["X-Amz-*"],
+            ))
+            .build();
+
+        let request = HttpRequest {
+            url: req_path.into(),
+            method: "GET".into(),
+            headers: vec![
+                ("X-App-Version".into(), "1.0.0".into()),
+                ("x-app-locale".into(), "en-US".into()),
+                ("User-Agent".into(), "curl/8.4.0".into()),
+            ],
+            body: vec![],
+        };
         let mut response = HttpResponse {
             status_code: 200,
             body: body.as_bytes().to_vec(),
             headers: vec![
                 ("IC-CertificateExpression".into(), cel_expr.to_string()),
                 ("Cache-Control".into(), "max-age=604800".into()),
-                ("Content-Encoding".into(), "gzip".into()),
-                ("Content-Language".into(), "en-US".into()),
-                ("Server".into(), "Apache/2.4.1 (Unix)".into()),
+                ("X-Amz-Request-Id".into(), "4442587FB7D0A2F9".into()),
+                ("x-amz-id-2".into(), "vlR7PnpV2Ce81puvV+QTnAz".into()),
             ],
             upgrade: None,
         };
 
-        let certification = HttpCertification::response_only(&cel_expr, &response, None);
-        let certification_tree_entry = HttpCertificationTreeEntry::new(&expr_path, &certification);
+        let certification = HttpCertification::full(&cel_expr, &request, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, &certification);
 
         let V2Fixture {
             root_key,
@@ -259,6 +597,8 @@ mod tests {
         response
             .headers
             .push(("IC-Certificate".into(), certificate_header));
+        // headers matching an excluded pattern can change without breaking verification
+        response.headers[2].1 = "A1B2C3D4E5F6A7B8".into();
 
         let result = verify_request_response_pair(
             request,
@@ -274,10 +614,7 @@ mod tests {
         let expected_response = VerifiedResponse {
             status_code: Some(200),
             body: body.as_bytes().to_vec(),
-            headers: vec![
-                ("cache-control".into(), "max-age=604800".into()),
-                ("server".into(), "Apache/2.4.1 (Unix)".into()),
-            ],
+            headers: vec![("cache-control".into(), "max-age=604800".into())],
         };
 
         assert!(matches!(
@@ -288,4 +625,528 @@ mod tests {
             } if verification_version == 2 && response == Some(expected_response)
         ));
     }
+
+    #[rstest]
+    #[case::get("GET", "Hello World!")]
+    #[case::head("HEAD", "")]
+    fn head_certification_passes_verification(#[case] method: &str, #[case] served_body: &str) {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_methods(&["GET", "HEAD"])
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                &["Content-Length"],
+            ))
+            .build();
+
+        let get_request = HttpRequest {
+            url: req_path.into(),
+            method: "GET".into(),
+            headers: vec![],
+            body: vec![],
+        };
+        let get_response = HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("IC-CertificateExpression".into(), cel_expr.to_string()),
+                ("Content-Length".into(), body.len().to_string()),
+            ],
+            upgrade: None,
+        };
+
+        let get_certification =
+            HttpCertification::full(&cel_expr, &get_request, &get_response, None).unwrap();
+        let head_certification =
+            HttpCertification::full_head(&cel_expr, &get_request, &get_response).unwrap();
+        let get_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, &get_certification);
+        let head_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, &head_certification);
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert(&get_tree_entry);
+        tree.insert(&head_tree_entry);
+
+        let tree_entry = if method == "HEAD" {
+            &head_tree_entry
+        } else {
+            &get_tree_entry
+        };
+        let tree_cbor = cbor_encode(&tree.witness(tree_entry, req_path));
+
+        let V2CertificateFixture {
+            root_key,
+            certificate_cbor,
+            canister_id,
+        } = create_v2_certificate_fixture(&tree.root_hash(), &current_time);
+
+        let request = HttpRequest {
+            method: method.into(),
+            ..get_request
+        };
+        let mut response = HttpResponse {
+            body: served_body.as_bytes().to_vec(),
+            ..get_response
+        };
+        response.headers.push((
+            "IC-Certificate".into(),
+            create_v2_header(tree_entry, &certificate_cbor, &tree_cbor),
+        ));
+
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        let expected_response = VerifiedResponse {
+            status_code: Some(200),
+            body: served_body.as_bytes().to_vec(),
+            headers: vec![("content-length".into(), body.len().to_string())],
+        };
+
+        assert!(matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+            } if verification_version == 2 && response == Some(expected_response)
+        ));
+    }
+
+    #[test]
+    fn response_only_head_certification_passes_verification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                &["Content-Length"],
+            ))
+            .build();
+
+        let get_response = HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("IC-CertificateExpression".into(), cel_expr.to_string()),
+                ("Content-Length".into(), body.len().to_string()),
+            ],
+            upgrade: None,
+        };
+
+        let head_certification =
+            HttpCertification::response_only_head(&cel_expr, &get_response).unwrap();
+        let head_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, &head_certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &head_tree_entry, &current_time);
+
+        let request = HttpRequest {
+            url: req_path.into(),
+            method: "HEAD".into(),
+            headers: vec![],
+            body: vec![],
+        };
+        let mut response = HttpResponse {
+            body: vec![],
+            ..get_response
+        };
+        response
+            .headers
+            .push(("IC-Certificate".into(), certificate_header));
+
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        let expected_response = VerifiedResponse {
+            status_code: Some(200),
+            body: vec![],
+            headers: vec![("content-length".into(), body.len().to_string())],
+        };
+
+        assert!(matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+            } if verification_version == 2 && response == Some(expected_response)
+        ));
+    }
+
+    // the same expected hashes are checked on the certification side by the
+    // `response_hash_with_header_options` test of the ic-http-certification package
+    #[rstest]
+    #[case::separate_raw(
+        DuplicateResponseHeaderMode::Separate,
+        ResponseHeaderValueMode::Raw,
+        "b269295fe6dd9de2509451e93ce0032e9b1848881b61cf294ec5e1a8498e26c4"
+    )]
+    #[case::separate_trim(
+        DuplicateResponseHeaderMode::Separate,
+        ResponseHeaderValueMode::Trim,
+        "ec4b3867295e8ef8ded8c5889ab0737fff231486d85950a6b3308003e447b352"
+    )]
+    #[case::fold_raw(
+        DuplicateResponseHeaderMode::Fold,
+        ResponseHeaderValueMode::Raw,
+        "d5aab020fc8bd9fd22e7927b3fd244109beb08c5dd7b1d32fc81d7a35bfd3ef5"
+    )]
+    #[case::fold_trim(
+        DuplicateResponseHeaderMode::Fold,
+        ResponseHeaderValueMode::Trim,
+        "bd90336f1320275689f045d10e17b99766dfe7f54291eae08a7923dc318aeece"
+    )]
+    #[case::array_raw(
+        DuplicateResponseHeaderMode::Array,
+        ResponseHeaderValueMode::Raw,
+        "ed5b3e1be7e3215981864380078b55f1eaced168999b55d22e5ca4cdd5401511"
+    )]
+    #[case::array_trim(
+        DuplicateResponseHeaderMode::Array,
+        ResponseHeaderValueMode::Trim,
+        "1f5f71d700f503da76573484b7fa4fb16ea3d1ec92d7a7399ce6b7660f92861b"
+    )]
+    fn response_header_options_pass_verification_with_expected_hash(
+        #[case] duplicate_header_mode: DuplicateResponseHeaderMode,
+        #[case] header_value_mode: ResponseHeaderValueMode,
+        #[case] expected_response_hash: &str,
+    ) {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                &["Cache-Control"],
+            ))
+            .with_response_header_options(
+                ResponseHeaderCertificationOptions::default()
+                    .with_duplicate_header_mode(duplicate_header_mode)
+                    .with_header_value_mode(header_value_mode),
+            )
+            .build();
+
+        let request = HttpRequest {
+            url: req_path.into(),
+            method: "GET".into(),
+            headers: vec![],
+            body: vec![],
+        };
+        let mut response = HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("IC-CertificateExpression".into(), cel_expr.to_string()),
+                ("Cache-Control".into(), " no-cache".into()),
+                ("Accept-Encoding".into(), "gzip".into()),
+                ("Cache-Control".into(), "no-store\t".into()),
+            ],
+            upgrade: None,
+        };
+
+        // certify the expected hash directly, so that the verifier is checked against it
+        // rather than against the hash that the certification side calculates
+        let certification = HttpCertification::ResponseOnly {
+            cel_expr_hash: HttpCertification::response_only(&cel_expr, &response, None)
+                .unwrap()
+                .cel_expr_hash(),
+            response_hash: hex::decode(expected_response_hash)
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        };
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, &certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response
+            .headers
+            .push(("IC-Certificate".into(), certificate_header));
+
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        assert_eq!(result.verification_version, 2);
+        assert!(result.response.is_some());
+    }
+
+    #[test]
+    fn response_certification_with_header_exclusions_passes_verification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let expr_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                &["Content-Language", "Content-Encoding"],
+            ))
+            .build();
+
+        let request = HttpRequest {
+            url: req_path.into(),
+            method: "GET".into(),
+            headers: vec![],
+            body: vec![],
+        };
+        let mut response = HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("IC-CertificateExpression".into(), cel_expr.to_string()),
+                ("Cache-Control".into(), "max-age=604800".into()),
+                ("Content-Encoding".into(), "gzip".into()),
+                ("Content-Language".into(), "en-US".into()),
+                ("Server".into(), "Apache/2.4.1 (Unix)".into()),
+            ],
+            upgrade: None,
+        };
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry = HttpCertificationTreeEntry::new(&expr_path, &certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response
+            .headers
+            .push(("IC-Certificate".into(), certificate_header));
+
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        let expected_response = VerifiedResponse {
+            status_code: Some(200),
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("cache-control".into(), "max-age=604800".into()),
+                ("server".into(), "Apache/2.4.1 (Unix)".into()),
+            ],
+        };
+
+        assert!(matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+            } if verification_version == 2 && response == Some(expected_response)
+        ));
+    }
+
+    #[test]
+    fn multiple_responses_with_shared_witness_pass_verification() {
+        let current_time = get_current_timestamp();
+        let req_paths = ["/index.html", "/app.js"];
+        let bodies = ["<html></html>", "console.log('Hello World!');"];
+        let certification_paths = req_paths.map(HttpCertificationPath::exact);
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                &["Cache-Control"],
+            ))
+            .build();
+
+        let requests = req_paths.map(|req_path| HttpRequest {
+            url: req_path.into(),
+            method: "GET".into(),
+            headers: vec![],
+            body: vec![],
+        });
+        let mut responses = bodies.map(|body| HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("IC-CertificateExpression".into(), cel_expr.to_string()),
+                ("Cache-Control".into(), "max-age=604800".into()),
+            ],
+            upgrade: None,
+        });
+
+        let certifications: Vec<_> = responses
+            .iter()
+            .map(|response| HttpCertification::response_only(&cel_expr, response, None).unwrap())
+            .collect();
+        let certification_tree_entries: Vec<_> = certification_paths
+            .iter()
+            .zip(certifications.iter())
+            .map(|(path, certification)| HttpCertificationTreeEntry::new(path, certification))
+            .collect();
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert_many(&certification_tree_entries);
+        tree.insert(&HttpCertificationTreeEntry::new(
+            &HttpCertificationPath::wildcard("/"),
+            &HttpCertification::skip(),
+        ));
+
+        let witness = tree.witness_many(&[
+            (&certification_tree_entries[0], req_paths[0]),
+            (&certification_tree_entries[1], req_paths[1]),
+        ]);
+        let tree_cbor = cbor_encode(&witness);
+
+        let V2CertificateFixture {
+            root_key,
+            certificate_cbor,
+            canister_id,
+        } = create_v2_certificate_fixture(&tree.root_hash(), &current_time);
+
+        for (response, certification_tree_entry) in
+            responses.iter_mut().zip(certification_tree_entries.iter())
+        {
+            let certificate_header =
+                create_v2_header(certification_tree_entry, &certificate_cbor, &tree_cbor);
+
+            response
+                .headers
+                .push(("IC-Certificate".into(), certificate_header));
+        }
+
+        let result = verify_request_response_pairs(
+            requests.into_iter().zip(responses).collect(),
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        assert_eq!(result.len(), 2);
+        for (result, body) in result.into_iter().zip(bodies) {
+            let expected_response = VerifiedResponse {
+                status_code: Some(200),
+                body: body.as_bytes().to_vec(),
+                headers: vec![("cache-control".into(), "max-age=604800".into())],
+            };
+
+            assert!(matches!(
+                result,
+                VerificationInfo {
+                    verification_version,
+                    response,
+                } if verification_version == 2 && response == Some(expected_response)
+            ));
+        }
+    }
+
+    #[rstest]
+    #[case::most_specific_path(HttpCertificationPath::wildcard("/js"), true)]
+    #[case::shadowed_path(HttpCertificationPath::wildcard("/"), false)]
+    fn debug_self_verify_matches_verification(
+        #[case] certification_path: HttpCertificationPath,
+        #[case] is_valid: bool,
+    ) {
+        let req_path = "/js/app.js";
+        let body = "console.log('Hello World!');";
+        let current_time = get_current_timestamp();
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                &["Cache-Control"],
+            ))
+            .build();
+
+        let request = HttpRequest {
+            url: req_path.into(),
+            method: "GET".into(),
+            headers: vec![],
+            body: vec![],
+        };
+        let mut response = HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("IC-CertificateExpression".into(), cel_expr.to_string()),
+                ("Cache-Control".into(), "max-age=604800".into()),
+            ],
+            upgrade: None,
+        };
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let tree_entry = HttpCertificationTreeEntry::new(&certification_path, &certification);
+        let skip_path = HttpCertificationPath::wildcard("/js");
+        let skip_certification = HttpCertification::skip();
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert(&tree_entry);
+        tree.insert(&HttpCertificationTreeEntry::new(
+            &skip_path,
+            &skip_certification,
+        ));
+
+        let self_verification_result = debug_self_verify(&request, &response, &tree);
+        assert_eq!(self_verification_result.is_ok(), is_valid);
+
+        let tree_cbor = cbor_encode(&tree.witness(&tree_entry, req_path));
+        let V2CertificateFixture {
+            root_key,
+            certificate_cbor,
+            canister_id,
+        } = create_v2_certificate_fixture(&tree.root_hash(), &current_time);
+        response.headers.push((
+            "IC-Certificate".into(),
+            create_v2_header(&tree_entry, &certificate_cbor, &tree_cbor),
+        ));
+
+        let verification_result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+        assert_eq!(verification_result.is_ok(), is_valid);
+    }
 }
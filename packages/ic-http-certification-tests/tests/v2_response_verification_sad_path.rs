@@ -7,8 +7,9 @@ mod tests {
     use candid::Principal;
     use ic_certificate_verification::CertificateVerificationError;
     use ic_http_certification::{
-        CelExpression, DefaultFullCelExpression, HttpCertification, HttpCertificationPath,
-        HttpCertificationTree, HttpCertificationTreeEntry, HttpRequest, HttpResponse,
+        CelExpression, DefaultCelBuilder, DefaultFullCelExpression, HttpCertification,
+        HttpCertificationError, HttpCertificationPath, HttpCertificationTree,
+        HttpCertificationTreeEntry, HttpRequest, HttpResponse,
     };
     use ic_response_verification::{verify_request_response_pair, ResponseVerificationError};
     use ic_response_verification_test_utils::{
@@ -25,7 +26,7 @@ mod tests {
         let req_path = "/?q=greeting";
         let body = "Hello World!";
         let current_time = get_current_timestamp();
-        let certification_path = HttpCertificationPath::Exact("/");
+        let certification_path = HttpCertificationPath::exact("/");
 
         let request = HttpRequest {
             url: req_path.into(),
@@ -85,6 +86,134 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn head_request_with_uncertified_method_fails_verification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_methods(&["GET"])
+            .build();
+
+        let get_request = HttpRequest {
+            url: req_path.into(),
+            method: "GET".into(),
+            headers: vec![],
+            body: vec![],
+        };
+        let get_response = HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![("IC-CertificateExpression".into(), cel_expr.to_string())],
+            upgrade: None,
+        };
+
+        let certification =
+            HttpCertification::full(&cel_expr, &get_request, &get_response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, &certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        let request = HttpRequest {
+            method: "HEAD".into(),
+            ..get_request
+        };
+        let mut response = HttpResponse {
+            body: vec![],
+            ..get_response
+        };
+        response
+            .headers
+            .push(("IC-Certificate".into(), certificate_header));
+
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            fixtures::MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            fixtures::MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        assert!(matches!(
+            result,
+            Err(ResponseVerificationError::HttpCertificationError(
+                HttpCertificationError::UncertifiedRequestMethod { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn head_response_with_body_fails_verification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_methods(&["GET", "HEAD"])
+            .build();
+
+        let get_request = HttpRequest {
+            url: req_path.into(),
+            method: "GET".into(),
+            headers: vec![],
+            body: vec![],
+        };
+        let get_response = HttpResponse {
+            status_code: 200,
+            body: body.as_bytes().to_vec(),
+            headers: vec![("IC-CertificateExpression".into(), cel_expr.to_string())],
+            upgrade: None,
+        };
+
+        let certification =
+            HttpCertification::full_head(&cel_expr, &get_request, &get_response).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, &certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        let request = HttpRequest {
+            method: "HEAD".into(),
+            ..get_request
+        };
+        let mut response = HttpResponse {
+            body: body.as_bytes().to_vec(),
+            ..get_response
+        };
+        response
+            .headers
+            .push(("IC-Certificate".into(), certificate_header));
+
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            fixtures::MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            fixtures::MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        assert!(matches!(
+            result,
+            Err(ResponseVerificationError::InvalidResponseHashes)
+        ));
+    }
+
     #[rstest]
     pub fn response_hash_mismatch_fails_verification(
         #[from(full_certification_cel)] cel_expr: DefaultFullCelExpression<'static>,
@@ -92,7 +221,7 @@ mod tests {
         let req_path = "/?q=greeting";
         let body = "Hello World!";
         let current_time = get_current_timestamp();
-        let certification_path = HttpCertificationPath::Exact("/");
+        let certification_path = HttpCertificationPath::exact("/");
 
         let request = HttpRequest {
             url: req_path.into(),
@@ -161,7 +290,7 @@ mod tests {
         let req_path = "/?q=greeting";
         let body = "Hello World!";
         let current_time = get_current_timestamp();
-        let certification_path = HttpCertificationPath::Exact("");
+        let certification_path = HttpCertificationPath::exact("");
 
         let request = HttpRequest {
             url: req_path.into(),
@@ -222,9 +351,9 @@ mod tests {
     }
 
     #[rstest]
-    #[case::does_not_exist_in_tree(HttpCertificationPath::Wildcard("/assets/css"))]
-    #[case::more_specific_path_exists_in_tree(HttpCertificationPath::Wildcard("/assets"))]
-    #[case::does_not_match_request_url(HttpCertificationPath::Exact("/assets/js/dashboard.js"))]
+    #[case::does_not_exist_in_tree(HttpCertificationPath::wildcard("/assets/css"))]
+    #[case::more_specific_path_exists_in_tree(HttpCertificationPath::wildcard("/assets"))]
+    #[case::does_not_match_request_url(HttpCertificationPath::exact("/assets/js/dashboard.js"))]
     fn invalid_expr_path_fails_verification(
         #[from(skip_certification_cel)] cel_expr: CelExpression<'static>,
         #[case] certification_path: HttpCertificationPath,
@@ -248,15 +377,15 @@ mod tests {
         let certification = HttpCertification::skip();
         let mut certification_tree = HttpCertificationTree::default();
         certification_tree.insert(&HttpCertificationTreeEntry::new(
-            &HttpCertificationPath::Wildcard("/assets"),
+            &HttpCertificationPath::wildcard("/assets"),
             &certification,
         ));
         certification_tree.insert(&HttpCertificationTreeEntry::new(
-            &HttpCertificationPath::Wildcard("/assets/js"),
+            &HttpCertificationPath::wildcard("/assets/js"),
             &certification,
         ));
         certification_tree.insert(&HttpCertificationTreeEntry::new(
-            &HttpCertificationPath::Exact("/assets/js/dashboard.js"),
+            &HttpCertificationPath::exact("/assets/js/dashboard.js"),
             &certification,
         ));
 
@@ -408,7 +537,7 @@ mod fixtures {
     pub fn invalid_root_key_certificate() -> (V2Fixture, u128, String) {
         let cel_expr = skip_certification_cel().to_string();
         let req_path = "/";
-        let certification_path = HttpCertificationPath::Exact("/");
+        let certification_path = HttpCertificationPath::exact("/");
         let current_time = get_current_timestamp();
         let certification = HttpCertification::skip();
         let certification_tree_entry =
@@ -431,7 +560,7 @@ mod fixtures {
     pub fn expired_certificate() -> (V2Fixture, u128, String) {
         let cel_expr = skip_certification_cel().to_string();
         let req_path = "/";
-        let certification_path = HttpCertificationPath::Exact("/");
+        let certification_path = HttpCertificationPath::exact("/");
         let current_time = get_current_timestamp();
         let certification = HttpCertification::skip();
         let certification_tree_entry =
@@ -451,7 +580,7 @@ mod fixtures {
     pub fn future_certificate() -> (V2Fixture, u128, String) {
         let cel_expr = skip_certification_cel().to_string();
         let req_path = "/";
-        let certification_path = HttpCertificationPath::Exact("/");
+        let certification_path = HttpCertificationPath::exact("/");
         let current_time = get_current_timestamp();
         let certification = HttpCertification::skip();
         let certification_tree_entry =
@@ -471,7 +600,7 @@ mod fixtures {
     pub fn wrong_canister_certificate() -> (V2Fixture, u128, String) {
         let cel_expr = skip_certification_cel().to_string();
         let req_path = "/";
-        let certification_path = HttpCertificationPath::Exact("/");
+        let certification_path = HttpCertificationPath::exact("/");
         let other_canister_id = CanisterId::from_u64(15);
         let current_time = get_current_timestamp();
         let certification = HttpCertification::skip();
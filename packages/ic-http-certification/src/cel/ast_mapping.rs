@@ -1,9 +1,12 @@
 use crate::cel::error::{CelParserError, CelParserResult};
+use crate::cel::header_name_pattern::is_valid_header_name_pattern;
 use crate::cel::parser::CelValue;
-use ic_http_certification::{
+use crate::{
     cel::{
-        CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification,
-        DefaultResponseOnlyCelExpression,
+        CelExpression, ConditionalCelExpression, DefaultCelExpression, DefaultFullCelExpression,
+        DefaultRequestCertification, DefaultResponseOnlyCelExpression, DuplicateResponseHeaderMode,
+        QueryParameterCertificationMode, RequestBodyCertificationMode,
+        ResponseHeaderCertificationOptions, ResponseHeaderValueMode,
     },
     DefaultResponseCertification,
 };
@@ -59,7 +62,7 @@ fn validate_function<'a>(
 fn validate_string_array<'a>(
     cel: &'a CelValue<'a>,
     name: &'a str,
-) -> CelParserResult<Vec<&'a str>> {
+) -> CelParserResult<Vec<Cow<'a, str>>> {
     let CelValue::Array(array) = cel else {
         return Err(CelParserError::UnexpectedNodeType {
             node_name: name.into(),
@@ -79,13 +82,60 @@ fn validate_string_array<'a>(
                 });
             };
 
-            Ok(*e)
+            Ok(e.clone())
         })
         .collect::<Result<_, _>>()?;
 
     Ok(elements)
 }
 
+fn validate_header_name_patterns<'a>(
+    cel: &'a CelValue<'a>,
+    name: &'a str,
+) -> CelParserResult<Vec<Cow<'a, str>>> {
+    let header_name_patterns = validate_string_array(cel, name)?;
+
+    if let Some(invalid_pattern) = header_name_patterns
+        .iter()
+        .find(|pattern| !is_valid_header_name_pattern(pattern))
+    {
+        return Err(CelParserError::InvalidHeaderNamePattern(
+            invalid_pattern.to_string(),
+        ));
+    }
+
+    Ok(header_name_patterns)
+}
+
+fn validate_string<'a>(cel: &'a CelValue<'a>, name: &str) -> CelParserResult<&'a str> {
+    let CelValue::String(value) = cel else {
+        return Err(CelParserError::UnexpectedNodeType {
+            node_name: name.into(),
+            expected_type: "String".into(),
+            found_type: cel.to_string(),
+        });
+    };
+
+    Ok(value)
+}
+
+fn validate_query_parameter_mode(
+    cel: &CelValue,
+) -> CelParserResult<QueryParameterCertificationMode> {
+    let query_parameter_mode = validate_string(cel, "query_parameter_mode")?;
+
+    QueryParameterCertificationMode::from_name(query_parameter_mode).ok_or_else(|| {
+        CelParserError::UnrecognizedQueryParameterMode(query_parameter_mode.to_string())
+    })
+}
+
+fn validate_request_body_mode(cel: &CelValue) -> CelParserResult<RequestBodyCertificationMode> {
+    let body_mode = validate_string(cel, "body_mode")?;
+
+    RequestBodyCertificationMode::from_name(body_mode)
+        .ok_or_else(|| CelParserError::UnrecognizedRequestBodyMode(body_mode.to_string()))
+}
+
 fn validate_request_certification<'a>(
     certification: &'a HashMap<&'a str, CelValue<'a>>,
 ) -> CelParserResult<Option<DefaultRequestCertification<'a>>> {
@@ -108,8 +158,10 @@ fn validate_request_certification<'a>(
                     expected_property_name: "certified_request_headers".into(),
                 });
             };
-            let certified_request_headers =
-                validate_string_array(certified_request_headers, "certified_request_headers")?;
+            let certified_request_headers = validate_header_name_patterns(
+                certified_request_headers,
+                "certified_request_headers",
+            )?;
 
             let Some(certified_query_parameters) =
                 request_certification.get("certified_query_parameters")
@@ -122,9 +174,32 @@ fn validate_request_certification<'a>(
             let certified_query_parameters =
                 validate_string_array(certified_query_parameters, "certified_query_parameters")?;
 
+            let query_parameter_mode = request_certification
+                .get("query_parameter_mode")
+                .map(validate_query_parameter_mode)
+                .transpose()?
+                .unwrap_or_default();
+
+            let body_mode = request_certification
+                .get("body_mode")
+                .map(validate_request_body_mode)
+                .transpose()?
+                .unwrap_or_default();
+
+            let certified_methods = request_certification
+                .get("certified_methods")
+                .map(|certified_methods| {
+                    validate_string_array(certified_methods, "certified_methods")
+                })
+                .transpose()?
+                .unwrap_or_default();
+
             Ok(Some(DefaultRequestCertification {
-                headers: Cow::Owned(certified_request_headers),
-                query_parameters: Cow::Owned(certified_query_parameters),
+                headers: certified_request_headers,
+                query_parameters: certified_query_parameters,
+                query_parameter_mode,
+                body_mode,
+                methods: certified_methods,
             }))
         }
     };
@@ -132,7 +207,10 @@ fn validate_request_certification<'a>(
 
 fn validate_response_certification<'a>(
     certification: &'a HashMap<&'a str, CelValue<'a>>,
-) -> CelParserResult<DefaultResponseCertification<'a>> {
+) -> CelParserResult<(
+    DefaultResponseCertification<'a>,
+    ResponseHeaderCertificationOptions,
+)> {
     let Some(response_certification) = certification.get("response_certification") else {
         return Err(CelParserError::MissingObjectProperty {
             object_name: "RequestCertification".into(),
@@ -142,7 +220,7 @@ fn validate_response_certification<'a>(
     let response_certification = validate_object(response_certification, "ResponseCertification")?;
 
     let get_response_certification_headers =
-        |property_name| -> CelParserResult<Option<Vec<&'a str>>> {
+        |property_name| -> CelParserResult<Option<Vec<Cow<'a, str>>>> {
             response_certification
                 .get(property_name)
                 .map(|certified_response_headers| {
@@ -150,7 +228,7 @@ fn validate_response_certification<'a>(
                 })
                 .transpose()?
                 .and_then(|certified_response_headers| certified_response_headers.get("headers"))
-                .map(|headers| validate_string_array(headers, property_name))
+                .map(|headers| validate_header_name_patterns(headers, property_name))
                 .transpose()
         };
 
@@ -160,19 +238,58 @@ fn validate_response_certification<'a>(
     let response_header_exclusions =
         get_response_certification_headers("response_header_exclusions")?;
 
-    match (certified_response_headers, response_header_exclusions) {
+    let duplicate_header_mode = response_certification
+        .get("duplicate_header_mode")
+        .map(|duplicate_header_mode| {
+            let duplicate_header_mode =
+                validate_string(duplicate_header_mode, "duplicate_header_mode")?;
+
+            DuplicateResponseHeaderMode::from_name(duplicate_header_mode).ok_or_else(|| {
+                CelParserError::UnrecognizedDuplicateHeaderMode(duplicate_header_mode.to_string())
+            })
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    let header_value_mode = response_certification
+        .get("header_value_mode")
+        .map(|header_value_mode| {
+            let header_value_mode = validate_string(header_value_mode, "header_value_mode")?;
+
+            ResponseHeaderValueMode::from_name(header_value_mode).ok_or_else(|| {
+                CelParserError::UnrecognizedHeaderValueMode(header_value_mode.to_string())
+            })
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    let options = ResponseHeaderCertificationOptions::default()
+        .with_duplicate_header_mode(duplicate_header_mode)
+        .with_header_value_mode(header_value_mode);
+
+    let response_certification = match (certified_response_headers, response_header_exclusions) {
         (Some(_), Some(_)) => Err(CelParserError::ExtraneousResponseCertificationProperty),
         (None, None) => Err(CelParserError::MissingResponseCertificationProperty),
         (Some(headers), None) => Ok(DefaultResponseCertification::CertifiedResponseHeaders(
-            Cow::Owned(headers),
+            headers,
         )),
         (None, Some(headers)) => Ok(DefaultResponseCertification::ResponseHeaderExclusions(
-            Cow::Owned(headers),
+            headers,
         )),
-    }
+    }?;
+
+    Ok((response_certification, options))
 }
 
 pub(crate) fn map_cel_ast<'a>(cel: &'a CelValue<'a>) -> CelParserResult<CelExpression<'a>> {
+    if let CelValue::Conditional(condition, if_true, if_false) = cel {
+        return Ok(CelExpression::Conditional(ConditionalCelExpression {
+            condition: condition.clone(),
+            if_true: Box::new(map_cel_ast(if_true)?),
+            if_false: Box::new(map_cel_ast(if_false)?),
+        }));
+    }
+
     let default_certification = validate_function(cel, "default_certification")?;
 
     let Some(validation_args) = default_certification.first() else {
@@ -198,12 +315,14 @@ pub(crate) fn map_cel_ast<'a>(cel: &'a CelValue<'a>) -> CelParserResult<CelExpre
 
             let request_certification = validate_request_certification(certification)?;
 
-            let response_certification = validate_response_certification(certification)?;
+            let (response_certification, response_header_options) =
+                validate_response_certification(certification)?;
 
             let Some(request_certification) = request_certification else {
                 return Ok(CelExpression::Default(DefaultCelExpression::ResponseOnly(
                     DefaultResponseOnlyCelExpression {
                         response: response_certification,
+                        response_header_options,
                     },
                 )));
             };
@@ -212,6 +331,7 @@ pub(crate) fn map_cel_ast<'a>(cel: &'a CelValue<'a>) -> CelParserResult<CelExpre
                 DefaultFullCelExpression {
                     request: request_certification,
                     response: response_certification,
+                    response_header_options,
                 },
             )))
         }
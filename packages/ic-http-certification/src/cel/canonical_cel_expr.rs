@@ -0,0 +1,338 @@
+use super::{
+    parse_cel_expr, CelExpression, CelParserResult, ConditionalCelExpression, DefaultCelExpression,
+    DefaultFullCelExpression, DefaultRequestCertification, DefaultResponseCertification,
+    DefaultResponseOnlyCelExpression,
+};
+use std::borrow::Cow;
+
+/// Converts a [CelExpression] into its canonical form.
+///
+/// Two expressions that certify the same parts of a request and response have the same canonical form,
+/// regardless of how they were written. The canonical form is created by:
+///
+/// - Converting request header, query parameter and response header names to lowercase, since these names
+///   are matched case-insensitively when calculating request and response hashes.
+/// - Sorting and removing duplicates from lists of request headers, query parameters and response headers,
+///   since the order of these lists does not affect certification.
+/// - Sorting and removing duplicates from the list of certified request methods. Methods are case-sensitive,
+///   so their case is kept.
+///
+/// Conditions of [conditional expressions](CelExpression::Conditional) are kept as they are, but the
+/// expressions that they select between are converted into their canonical form.
+/// The [String] representation of an expression never contains whitespace between tokens,
+/// so the canonical form does not depend on the whitespace of a parsed expression either.
+///
+/// [CelExpression::canonicalize](CelExpression::canonicalize()) is an alias of this function and can be used
+/// for ergonomics.
+pub fn canonicalize_cel_expr(cel_expr: &CelExpression) -> CelExpression<'static> {
+    match cel_expr {
+        CelExpression::Default(expr) => CelExpression::Default(canonicalize_default_cel_expr(expr)),
+        CelExpression::Conditional(expr) => CelExpression::Conditional(ConditionalCelExpression {
+            condition: expr.condition.clone().into_owned(),
+            if_true: Box::new(canonicalize_cel_expr(&expr.if_true)),
+            if_false: Box::new(canonicalize_cel_expr(&expr.if_false)),
+        }),
+    }
+}
+
+/// Parses a CEL expression from it's [String] representation and converts it into the [String]
+/// representation of its canonical form.
+///
+/// See [canonicalize_cel_expr](canonicalize_cel_expr()) for details on the canonical form.
+pub fn canonical_cel_expr(cel_expr: &str) -> CelParserResult<String> {
+    parse_cel_expr(cel_expr).map(|cel_expr| canonicalize_cel_expr(&cel_expr).to_string())
+}
+
+fn canonicalize_default_cel_expr(cel_expr: &DefaultCelExpression) -> DefaultCelExpression<'static> {
+    match cel_expr {
+        DefaultCelExpression::Full(expr) => DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification {
+                headers: canonical_names(&expr.request.headers),
+                query_parameters: canonical_names(&expr.request.query_parameters),
+                query_parameter_mode: expr.request.query_parameter_mode,
+                body_mode: expr.request.body_mode,
+                methods: canonical_methods(&expr.request.methods),
+            },
+            response: canonicalize_response_certification(&expr.response),
+            response_header_options: expr.response_header_options,
+        }),
+        DefaultCelExpression::ResponseOnly(expr) => {
+            DefaultCelExpression::ResponseOnly(DefaultResponseOnlyCelExpression {
+                response: canonicalize_response_certification(&expr.response),
+                response_header_options: expr.response_header_options,
+            })
+        }
+        DefaultCelExpression::Skip => DefaultCelExpression::Skip,
+    }
+}
+
+fn canonicalize_response_certification(
+    response: &DefaultResponseCertification,
+) -> DefaultResponseCertification<'static> {
+    match response {
+        DefaultResponseCertification::CertifiedResponseHeaders(headers) => {
+            DefaultResponseCertification::CertifiedResponseHeaders(canonical_names(headers))
+        }
+        DefaultResponseCertification::ResponseHeaderExclusions(headers) => {
+            DefaultResponseCertification::ResponseHeaderExclusions(canonical_names(headers))
+        }
+    }
+}
+
+fn canonical_methods(methods: &[Cow<'_, str>]) -> Vec<Cow<'static, str>> {
+    let mut methods: Vec<String> = methods.iter().map(|method| method.to_string()).collect();
+    methods.sort_unstable();
+    methods.dedup();
+
+    methods.into_iter().map(Cow::Owned).collect()
+}
+
+fn canonical_names(names: &[Cow<'_, str>]) -> Vec<Cow<'static, str>> {
+    let mut names: Vec<String> = names.iter().map(|name| name.to_ascii_lowercase()).collect();
+    names.sort_unstable();
+    names.dedup();
+
+    names.into_iter().map(Cow::Owned).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cel::{
+        CelAttribute, CelBinaryOperator, CelCondition, ResponseHeaderCertificationOptions,
+    };
+    use rstest::*;
+
+    #[rstest]
+    fn canonicalize_full_cel_expr() {
+        let cel_expr =
+            CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+                request: DefaultRequestCertification::new(
+                    ["Cache-Control", "accept", "Accept"],
+                    ["foo", "bar", "foo"],
+                ),
+                response: DefaultResponseCertification::certified_response_headers(&[
+                    "ETag",
+                    "Content-Type",
+                    "etag",
+                ]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            }));
+        let expected =
+            CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+                request: DefaultRequestCertification::new(
+                    ["accept", "cache-control"],
+                    ["bar", "foo"],
+                ),
+                response: DefaultResponseCertification::certified_response_headers(&[
+                    "content-type",
+                    "etag",
+                ]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            }));
+
+        let result = canonicalize_cel_expr(&cel_expr);
+
+        assert_eq!(result, expected);
+        assert_eq!(cel_expr.canonicalize(), expected);
+    }
+
+    #[rstest]
+    fn canonicalize_certified_methods() {
+        let cel_expr =
+            CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+                request: DefaultRequestCertification::new(Vec::<&str>::new(), Vec::<&str>::new())
+                    .with_methods(["HEAD", "GET", "HEAD"]),
+                response: DefaultResponseCertification::default(),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            }));
+        let expected =
+            CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+                request: DefaultRequestCertification::new(Vec::<&str>::new(), Vec::<&str>::new())
+                    .with_methods(["GET", "HEAD"]),
+                response: DefaultResponseCertification::default(),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            }));
+
+        let result = canonicalize_cel_expr(&cel_expr);
+
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    fn canonicalize_response_only_cel_expr() {
+        let cel_expr = CelExpression::Default(DefaultCelExpression::ResponseOnly(
+            DefaultResponseOnlyCelExpression {
+                response: DefaultResponseCertification::response_header_exclusions(&[
+                    "Date", "Cookie", "date",
+                ]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            },
+        ));
+        let expected = CelExpression::Default(DefaultCelExpression::ResponseOnly(
+            DefaultResponseOnlyCelExpression {
+                response: DefaultResponseCertification::response_header_exclusions(&[
+                    "cookie", "date",
+                ]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            },
+        ));
+
+        let result = canonicalize_cel_expr(&cel_expr);
+
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    fn canonicalize_skip_cel_expr() {
+        let cel_expr = CelExpression::Default(DefaultCelExpression::Skip);
+
+        let result = canonicalize_cel_expr(&cel_expr);
+
+        assert_eq!(result, cel_expr);
+    }
+
+    #[rstest]
+    fn canonicalize_conditional_cel_expr() {
+        let condition = CelCondition::binary(
+            CelBinaryOperator::Eq,
+            CelCondition::Index(
+                Box::new(CelCondition::Attribute(CelAttribute::RequestHeaders)),
+                Box::new(CelCondition::string("Accept")),
+            ),
+            CelCondition::string("Text/HTML"),
+        );
+        let cel_expr = CelExpression::Conditional(ConditionalCelExpression {
+            condition: condition.clone(),
+            if_true: Box::new(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+                DefaultResponseOnlyCelExpression {
+                    response: DefaultResponseCertification::certified_response_headers(&[
+                        "ETag",
+                        "Cache-Control",
+                    ]),
+                    response_header_options: ResponseHeaderCertificationOptions::default(),
+                },
+            ))),
+            if_false: Box::new(CelExpression::Default(DefaultCelExpression::Skip)),
+        });
+        let expected = CelExpression::Conditional(ConditionalCelExpression {
+            condition,
+            if_true: Box::new(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+                DefaultResponseOnlyCelExpression {
+                    response: DefaultResponseCertification::certified_response_headers(&[
+                        "cache-control",
+                        "etag",
+                    ]),
+                    response_header_options: ResponseHeaderCertificationOptions::default(),
+                },
+            ))),
+            if_false: Box::new(CelExpression::Default(DefaultCelExpression::Skip)),
+        });
+
+        let result = canonicalize_cel_expr(&cel_expr);
+
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    fn canonicalize_is_idempotent() {
+        let cel_expr =
+            CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+                request: DefaultRequestCertification::new(["B", "a", "A"], ["z", "y"]),
+                response: DefaultResponseCertification::certified_response_headers(&["X", "x"]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            }));
+
+        let canonical = canonicalize_cel_expr(&cel_expr);
+
+        assert_eq!(canonicalize_cel_expr(&canonical), canonical);
+    }
+
+    #[rstest]
+    fn canonical_cel_expr_normalizes_whitespace_and_lists() {
+        let cel_expr = r#"
+            default_certification (
+                ValidationArgs {
+                    certification: Certification {
+                        no_request_certification: Empty { },
+                        response_certification: ResponseCertification {
+                            certified_response_headers: ResponseHeaderList {
+                                headers: [ "ETag", "Cache-Control", "etag" ]
+                            }
+                        }
+                    }
+                }
+            )
+        "#;
+
+        let result = canonical_cel_expr(cel_expr).unwrap();
+
+        assert_eq!(
+            result,
+            r#"default_certification(ValidationArgs{certification:Certification{no_request_certification:Empty{},response_certification:ResponseCertification{certified_response_headers:ResponseHeaderList{headers:["cache-control","etag"]}}}})"#
+        );
+    }
+
+    #[rstest]
+    fn canonical_cel_expr_rejects_invalid_expression() {
+        let result = canonical_cel_expr("default_certification(");
+
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::header_case_and_order(
+        CelExpression::Default(DefaultCelExpression::ResponseOnly(DefaultResponseOnlyCelExpression {
+            response: DefaultResponseCertification::certified_response_headers(&["ETag", "Cache-Control"]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+        CelExpression::Default(DefaultCelExpression::ResponseOnly(DefaultResponseOnlyCelExpression {
+            response: DefaultResponseCertification::certified_response_headers(&["cache-control", "etag", "ETAG"]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+        true
+    )]
+    #[case::different_headers(
+        CelExpression::Default(DefaultCelExpression::ResponseOnly(DefaultResponseOnlyCelExpression {
+            response: DefaultResponseCertification::certified_response_headers(&["ETag"]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+        CelExpression::Default(DefaultCelExpression::ResponseOnly(DefaultResponseOnlyCelExpression {
+            response: DefaultResponseCertification::certified_response_headers(&["Cache-Control"]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+        false
+    )]
+    #[case::inclusions_and_exclusions(
+        CelExpression::Default(DefaultCelExpression::ResponseOnly(DefaultResponseOnlyCelExpression {
+            response: DefaultResponseCertification::certified_response_headers(&["ETag"]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+        CelExpression::Default(DefaultCelExpression::ResponseOnly(DefaultResponseOnlyCelExpression {
+            response: DefaultResponseCertification::response_header_exclusions(&["ETag"]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+        false
+    )]
+    #[case::query_parameter_case(
+        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(Vec::<&str>::new(), ["foo"]),
+            response: DefaultResponseCertification::default(),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(Vec::<&str>::new(), ["FOO", "foo"]),
+            response: DefaultResponseCertification::default(),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+        true
+    )]
+    fn is_equivalent(
+        #[case] cel_expr: CelExpression,
+        #[case] other: CelExpression,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(cel_expr.is_equivalent(&other), expected);
+        assert_eq!(other.is_equivalent(&cel_expr), expected);
+    }
+}
@@ -1,6 +1,8 @@
 use super::{
-    CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification,
-    DefaultResponseCertification, DefaultResponseOnlyCelExpression,
+    cel_types::borrow_strs, CelExpression, DefaultCelExpression, DefaultFullCelExpression,
+    DefaultRequestCertification, DefaultResponseCertification, DefaultResponseOnlyCelExpression,
+    QueryParameterCertificationMode, RequestBodyCertificationMode,
+    ResponseHeaderCertificationOptions,
 };
 use std::borrow::Cow;
 
@@ -35,6 +37,7 @@ impl DefaultCelBuilder {
 #[derive(Debug, Clone, Default)]
 pub struct DefaultResponseOnlyCelBuilder<'a> {
     response_certification: DefaultResponseCertification<'a>,
+    response_header_options: ResponseHeaderCertificationOptions,
 }
 
 impl<'a> DefaultResponseOnlyCelBuilder<'a> {
@@ -51,10 +54,25 @@ impl<'a> DefaultResponseOnlyCelBuilder<'a> {
         self
     }
 
+    /// Configure how the values of the certified response headers will be included in certification.
+    ///
+    /// See [ResponseHeaderCertificationOptions] for details on the available options.
+    /// Not calling this method will result in the [default](ResponseHeaderCertificationOptions::default())
+    /// options being used.
+    pub fn with_response_header_options(
+        mut self,
+        response_header_options: ResponseHeaderCertificationOptions,
+    ) -> Self {
+        self.response_header_options = response_header_options;
+
+        self
+    }
+
     /// Build the CEL expression, consuming the builder.
     pub fn build(self) -> DefaultResponseOnlyCelExpression<'a> {
         DefaultResponseOnlyCelExpression {
             response: self.response_certification,
+            response_header_options: self.response_header_options,
         }
     }
 }
@@ -63,9 +81,13 @@ impl<'a> DefaultResponseOnlyCelBuilder<'a> {
 /// To create an expression that only certifies the response, see [DefaultResponseOnlyCelBuilder].
 #[derive(Debug, Clone, Default)]
 pub struct DefaultFullCelExpressionBuilder<'a> {
-    request_headers: &'a [&'a str],
-    request_query_parameters: &'a [&'a str],
+    request_headers: Vec<Cow<'a, str>>,
+    request_query_parameters: Vec<Cow<'a, str>>,
+    request_query_parameter_mode: QueryParameterCertificationMode,
+    request_body_mode: RequestBodyCertificationMode,
+    request_methods: Vec<Cow<'a, str>>,
     response_certification: DefaultResponseCertification<'a>,
+    response_header_options: ResponseHeaderCertificationOptions,
 }
 
 impl<'a> DefaultFullCelExpressionBuilder<'a> {
@@ -74,7 +96,7 @@ impl<'a> DefaultFullCelExpressionBuilder<'a> {
     /// As many or as little headers can be provided as desired.
     /// Providing an empty list, or not calling this method, will result in no request query parameters being certified.
     pub fn with_request_headers(mut self, headers: &'a [&'a str]) -> Self {
-        self.request_headers = headers;
+        self.request_headers = borrow_strs(headers);
 
         self
     }
@@ -84,7 +106,40 @@ impl<'a> DefaultFullCelExpressionBuilder<'a> {
     /// As many or as little query parameters can be provided as desired.
     /// Providing an empty list, or not calling this method, will result in no request query parameters being certified.
     pub fn with_request_query_parameters(mut self, query_params: &'a [&'a str]) -> Self {
-        self.request_query_parameters = query_params;
+        self.request_query_parameters = borrow_strs(query_params);
+
+        self
+    }
+
+    /// Configure how the request query parameters will be included in certification.
+    ///
+    /// See [QueryParameterCertificationMode] for details on the available modes.
+    /// Not calling this method will result in the [Raw](QueryParameterCertificationMode::Raw) mode being used.
+    pub fn with_request_query_parameter_mode(
+        mut self,
+        query_parameter_mode: QueryParameterCertificationMode,
+    ) -> Self {
+        self.request_query_parameter_mode = query_parameter_mode;
+
+        self
+    }
+
+    /// Configure how the request body will be included in certification.
+    ///
+    /// See [RequestBodyCertificationMode] for details on the available modes.
+    /// Not calling this method will result in the [Raw](RequestBodyCertificationMode::Raw) mode being used.
+    pub fn with_request_body_mode(mut self, body_mode: RequestBodyCertificationMode) -> Self {
+        self.request_body_mode = body_mode;
+
+        self
+    }
+
+    /// Configure the request methods that will share the same certification.
+    ///
+    /// See [methods](DefaultRequestCertification::methods) for details on how the methods are certified.
+    /// Providing an empty list, or not calling this method, will result in the exact request method being certified.
+    pub fn with_request_methods(mut self, methods: &'a [&'a str]) -> Self {
+        self.request_methods = borrow_strs(methods);
 
         self
     }
@@ -102,16 +157,34 @@ impl<'a> DefaultFullCelExpressionBuilder<'a> {
         self
     }
 
+    /// Configure how the values of the certified response headers will be included in certification.
+    ///
+    /// See [ResponseHeaderCertificationOptions] for details on the available options.
+    /// Not calling this method will result in the [default](ResponseHeaderCertificationOptions::default())
+    /// options being used.
+    pub fn with_response_header_options(
+        mut self,
+        response_header_options: ResponseHeaderCertificationOptions,
+    ) -> Self {
+        self.response_header_options = response_header_options;
+
+        self
+    }
+
     /// Build the CEL expression, consuming the builder.
     pub fn build(self) -> DefaultFullCelExpression<'a> {
         let request_certification = DefaultRequestCertification {
-            headers: Cow::Borrowed(self.request_headers),
-            query_parameters: Cow::Borrowed(self.request_query_parameters),
+            headers: self.request_headers,
+            query_parameters: self.request_query_parameters,
+            query_parameter_mode: self.request_query_parameter_mode,
+            body_mode: self.request_body_mode,
+            methods: self.request_methods,
         };
 
         DefaultFullCelExpression {
             request: request_certification,
             response: self.response_certification,
+            response_header_options: self.response_header_options,
         }
     }
 }
@@ -119,7 +192,7 @@ impl<'a> DefaultFullCelExpressionBuilder<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cel::fixtures::*;
+    use crate::cel::{fixtures::*, DuplicateResponseHeaderMode, ResponseHeaderValueMode};
     use rstest::*;
 
     #[rstest]
@@ -230,6 +303,64 @@ mod tests {
         assert_eq!(cel_expr, include_request_response_header_exclusions_cel);
     }
 
+    #[rstest]
+    fn include_request_canonical_query_response_inclusions(
+        include_request_canonical_query_response_inclusions_cel: String,
+    ) {
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(&["Accept"])
+            .with_request_query_parameters(&["foo", "bar"])
+            .with_request_query_parameter_mode(QueryParameterCertificationMode::Canonical)
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                &["ETag"],
+            ))
+            .build()
+            .to_string();
+
+        assert_eq!(
+            cel_expr,
+            include_request_canonical_query_response_inclusions_cel
+        );
+    }
+
+    #[rstest]
+    fn include_request_canonical_json_body_response_inclusions(
+        include_request_canonical_json_body_response_inclusions_cel: String,
+    ) {
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(&["Content-Type"])
+            .with_request_body_mode(RequestBodyCertificationMode::CanonicalJson)
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                &["ETag"],
+            ))
+            .build()
+            .to_string();
+
+        assert_eq!(
+            cel_expr,
+            include_request_canonical_json_body_response_inclusions_cel
+        );
+    }
+
+    #[rstest]
+    fn skip_request_response_inclusions_with_options(
+        skip_request_response_inclusions_with_options_cel: String,
+    ) {
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                &["Cache-Control", "Set-Cookie"],
+            ))
+            .with_response_header_options(
+                ResponseHeaderCertificationOptions::default()
+                    .with_duplicate_header_mode(DuplicateResponseHeaderMode::Array)
+                    .with_header_value_mode(ResponseHeaderValueMode::Trim),
+            )
+            .build()
+            .to_string();
+
+        assert_eq!(cel_expr, skip_request_response_inclusions_with_options_cel);
+    }
+
     #[rstest]
     fn include_request_empty_response_inclusions(
         include_request_empty_response_inclusions_cel: String,
@@ -327,4 +458,50 @@ mod tests {
         assert_eq!(implicit_cel_expr, empty_request_response_exclusions_cel);
         assert_eq!(explicit_cel_expr, empty_request_response_exclusions_cel);
     }
+
+    #[rstest]
+    fn owned_request_response_header_inclusions(
+        include_request_response_header_inclusions_cel: String,
+    ) {
+        let request_headers: Vec<String> =
+            vec!["Accept".into(), "Accept-Encoding".into(), "If-Match".into()];
+        let response_headers: Vec<String> = vec![
+            "Cache-Control".into(),
+            "ETag".into(),
+            "Content-Length".into(),
+            "Content-Type".into(),
+            "Content-Encoding".into(),
+        ];
+
+        let cel_expr = DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(request_headers, ["foo", "bar", "baz"]),
+            response: DefaultResponseCertification::CertifiedResponseHeaders(
+                response_headers.into_iter().map(Cow::Owned).collect(),
+            ),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        };
+        let owned_cel_expr: DefaultFullCelExpression<'static> = cel_expr.clone().into_owned();
+
+        assert_eq!(owned_cel_expr, cel_expr);
+        assert_eq!(
+            owned_cel_expr.to_string(),
+            include_request_response_header_inclusions_cel
+        );
+    }
+
+    #[rstest]
+    fn owned_response_header_exclusions(no_request_response_exclusions_cel: String) {
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                &["Date", "Cookie", "Set-Cookie"],
+            ))
+            .build();
+        let owned_cel_expr: CelExpression<'static> =
+            CelExpression::Default(DefaultCelExpression::ResponseOnly(cel_expr)).into_owned();
+
+        assert_eq!(
+            owned_cel_expr.to_string(),
+            no_request_response_exclusions_cel
+        );
+    }
 }
@@ -0,0 +1,1035 @@
+use super::{create_cel_condition_expr, CelExpression, DefaultCelExpression};
+use crate::{
+    hash::decode_query_component, HttpCertificationError, HttpCertificationResult, HttpRequest,
+    HttpResponse,
+};
+use std::borrow::Cow;
+
+/// A certification CEL expression definition that selects between two other
+/// [CEL expressions](CelExpression) based on a [condition](CelCondition).
+///
+/// The condition is evaluated against the [HTTP request](crate::HttpRequest) and the
+/// [HTTP response](crate::HttpResponse). If it evaluates to `true`, then the
+/// [if_true](ConditionalCelExpression::if_true) expression is used for certification, otherwise the
+/// [if_false](ConditionalCelExpression::if_false) expression is used. Both branches can be another
+/// [ConditionalCelExpression], so conditions can be chained.
+///
+/// The hash of the whole expression is certified, but the request and response hashes are calculated
+/// according to the [DefaultCelExpression] that the condition selects, exactly as if that expression
+/// had been certified by itself. This is done in the same way by the canister, using
+/// [HttpCertification::from_cel_expr](crate::HttpCertification::from_cel_expr()), and by the verifier.
+///
+/// The [String] representation of this expression uses the ternary operator:
+///
+/// ```text
+/// <condition>?<if_true>:<if_false>
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionalCelExpression<'a> {
+    /// The condition that selects which expression is used for certification.
+    /// It must evaluate to a boolean.
+    pub condition: CelCondition<'a>,
+
+    /// The expression that is used for certification when the condition evaluates to `true`.
+    pub if_true: Box<CelExpression<'a>>,
+
+    /// The expression that is used for certification when the condition evaluates to `false`.
+    pub if_false: Box<CelExpression<'a>>,
+}
+
+impl ConditionalCelExpression<'_> {
+    /// Converts this [ConditionalCelExpression] into an owned version that does not borrow any data,
+    /// for example to store it in a canister's state.
+    pub fn into_owned(self) -> ConditionalCelExpression<'static> {
+        ConditionalCelExpression {
+            condition: self.condition.into_owned(),
+            if_true: Box::new(self.if_true.into_owned()),
+            if_false: Box::new(self.if_false.into_owned()),
+        }
+    }
+}
+
+/// An attribute of an [HTTP request](crate::HttpRequest) or an [HTTP response](crate::HttpResponse)
+/// that can be accessed from a [CelCondition].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CelAttribute {
+    /// The request method, such as `GET` or `POST`. Accessed with `request.method`.
+    RequestMethod,
+
+    /// The full request URL, including the query string. Accessed with `request.url`.
+    RequestUrl,
+
+    /// The decoded path of the request URL, without the query string. Accessed with `request.path`.
+    RequestPath,
+
+    /// A map of the request's query parameters. Accessed with `request.query`.
+    ///
+    /// Names and values are percent-decoded, with `+` decoded as a space, in the same way as the
+    /// [Canonical](super::QueryParameterCertificationMode::Canonical) query parameter mode does, so
+    /// `request.query["q"] == "hello world"` matches both `?q=hello+world` and `?q=hello%20world`.
+    /// A parameter without a value has an empty value. Parameters that appear multiple times keep all
+    /// of their values in the map, in their original order, and indexing into the map returns the first
+    /// one. Requests with a query string that does not decode to valid UTF-8 fail to evaluate.
+    RequestQuery,
+
+    /// A map of the request's headers. Header names are converted to lowercase.
+    /// Accessed with `request.headers`.
+    RequestHeaders,
+
+    /// The response status code. Accessed with `response.status_code`.
+    ResponseStatusCode,
+
+    /// A map of the response's headers. Header names are converted to lowercase.
+    /// Accessed with `response.headers`.
+    ResponseHeaders,
+}
+
+impl CelAttribute {
+    /// All of the attributes that can be accessed from a [CelCondition].
+    pub const ALL: [CelAttribute; 7] = [
+        CelAttribute::RequestMethod,
+        CelAttribute::RequestUrl,
+        CelAttribute::RequestPath,
+        CelAttribute::RequestQuery,
+        CelAttribute::RequestHeaders,
+        CelAttribute::ResponseStatusCode,
+        CelAttribute::ResponseHeaders,
+    ];
+
+    /// The name of the attribute as it appears in a CEL expression, such as `request.method`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CelAttribute::RequestMethod => "request.method",
+            CelAttribute::RequestUrl => "request.url",
+            CelAttribute::RequestPath => "request.path",
+            CelAttribute::RequestQuery => "request.query",
+            CelAttribute::RequestHeaders => "request.headers",
+            CelAttribute::ResponseStatusCode => "response.status_code",
+            CelAttribute::ResponseHeaders => "response.headers",
+        }
+    }
+
+    /// Returns the attribute with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<CelAttribute> {
+        CelAttribute::ALL
+            .into_iter()
+            .find(|attribute| attribute.name() == name)
+    }
+
+    /// Whether this attribute is an attribute of the [HTTP response](crate::HttpResponse).
+    pub fn is_response_attribute(&self) -> bool {
+        matches!(
+            self,
+            CelAttribute::ResponseStatusCode | CelAttribute::ResponseHeaders
+        )
+    }
+
+    fn evaluate(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+    ) -> HttpCertificationResult<CelConditionValue> {
+        let headers_map = |headers: &[(String, String)]| {
+            CelConditionValue::Map(
+                headers
+                    .iter()
+                    .map(|(name, value)| {
+                        (
+                            name.to_ascii_lowercase(),
+                            CelConditionValue::String(value.into()),
+                        )
+                    })
+                    .collect(),
+            )
+        };
+
+        let value = match self {
+            CelAttribute::RequestMethod => CelConditionValue::String(request.method.clone()),
+            CelAttribute::RequestUrl => CelConditionValue::String(request.url.clone()),
+            CelAttribute::RequestPath => CelConditionValue::String(request.get_path()?),
+            CelAttribute::RequestQuery => CelConditionValue::Map(
+                request
+                    .get_query()?
+                    .map(|query| {
+                        query
+                            .split('&')
+                            .filter(|fragment| !fragment.is_empty())
+                            .map(|fragment| {
+                                let (name, value) =
+                                    fragment.split_once('=').unwrap_or((fragment, ""));
+
+                                Ok((
+                                    decode_query_component(name)?,
+                                    CelConditionValue::String(decode_query_component(value)?),
+                                ))
+                            })
+                            .collect::<HttpCertificationResult<_>>()
+                    })
+                    .transpose()?
+                    .unwrap_or_default(),
+            ),
+            CelAttribute::RequestHeaders => headers_map(&request.headers),
+            CelAttribute::ResponseStatusCode => CelConditionValue::Int(response.status_code.into()),
+            CelAttribute::ResponseHeaders => headers_map(&response.headers),
+        };
+
+        Ok(value)
+    }
+}
+
+/// A function that can be called from a [CelCondition].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CelFunction {
+    /// Returns the length of a string, list or map. Called with `size(x)`.
+    Size,
+
+    /// Returns whether a string contains another string. Called with `x.contains(y)`.
+    Contains,
+
+    /// Returns whether a string starts with another string. Called with `x.startsWith(y)`.
+    StartsWith,
+
+    /// Returns whether a string ends with another string. Called with `x.endsWith(y)`.
+    EndsWith,
+
+    /// Converts the ASCII characters of a string to lowercase. Called with `x.lowerAscii()`.
+    LowerAscii,
+}
+
+impl CelFunction {
+    /// All of the functions that can be called from a [CelCondition].
+    pub const ALL: [CelFunction; 5] = [
+        CelFunction::Size,
+        CelFunction::Contains,
+        CelFunction::StartsWith,
+        CelFunction::EndsWith,
+        CelFunction::LowerAscii,
+    ];
+
+    /// The name of the function as it appears in a CEL expression, such as `startsWith`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CelFunction::Size => "size",
+            CelFunction::Contains => "contains",
+            CelFunction::StartsWith => "startsWith",
+            CelFunction::EndsWith => "endsWith",
+            CelFunction::LowerAscii => "lowerAscii",
+        }
+    }
+
+    /// Returns the function with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<CelFunction> {
+        CelFunction::ALL
+            .into_iter()
+            .find(|function| function.name() == name)
+    }
+
+    /// Whether the function is called as a method on its first argument, such as `x.startsWith(y)`,
+    /// rather than as a global function, such as `size(x)`.
+    pub fn is_method(&self) -> bool {
+        !matches!(self, CelFunction::Size)
+    }
+
+    /// The number of arguments that the function takes, including the target of a method call.
+    pub fn arity(&self) -> usize {
+        match self {
+            CelFunction::Size | CelFunction::LowerAscii => 1,
+            CelFunction::Contains | CelFunction::StartsWith | CelFunction::EndsWith => 2,
+        }
+    }
+
+    fn evaluate(&self, args: Vec<CelConditionValue>) -> HttpCertificationResult<CelConditionValue> {
+        let value = match (self, args.as_slice()) {
+            (CelFunction::Size, [CelConditionValue::String(value)]) => {
+                CelConditionValue::Int(value.chars().count() as i64)
+            }
+            (CelFunction::Size, [CelConditionValue::List(values)]) => {
+                CelConditionValue::Int(values.len() as i64)
+            }
+            (CelFunction::Size, [CelConditionValue::Map(entries)]) => {
+                CelConditionValue::Int(entries.len() as i64)
+            }
+            (
+                CelFunction::Contains,
+                [CelConditionValue::String(value), CelConditionValue::String(other)],
+            ) => CelConditionValue::Bool(value.contains(other.as_str())),
+            (
+                CelFunction::StartsWith,
+                [CelConditionValue::String(value), CelConditionValue::String(other)],
+            ) => CelConditionValue::Bool(value.starts_with(other.as_str())),
+            (
+                CelFunction::EndsWith,
+                [CelConditionValue::String(value), CelConditionValue::String(other)],
+            ) => CelConditionValue::Bool(value.ends_with(other.as_str())),
+            (CelFunction::LowerAscii, [CelConditionValue::String(value)]) => {
+                CelConditionValue::String(value.to_ascii_lowercase())
+            }
+            _ => {
+                return Err(HttpCertificationError::CelTypeMismatch {
+                    operation: self.name().to_string(),
+                    found: args
+                        .iter()
+                        .map(CelConditionValue::type_name)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                })
+            }
+        };
+
+        Ok(value)
+    }
+}
+
+/// A binary operator that can be used in a [CelCondition].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CelBinaryOperator {
+    /// Logical and of two booleans, `x && y`.
+    And,
+
+    /// Logical or of two booleans, `x || y`.
+    Or,
+
+    /// Equality of two values, `x == y`. Values of different types are never equal.
+    Eq,
+
+    /// Inequality of two values, `x != y`. Values of different types are never equal.
+    Ne,
+
+    /// Less than comparison of two integers or two strings, `x < y`.
+    Lt,
+
+    /// Less than or equal comparison of two integers or two strings, `x <= y`.
+    Le,
+
+    /// Greater than comparison of two integers or two strings, `x > y`.
+    Gt,
+
+    /// Greater than or equal comparison of two integers or two strings, `x >= y`.
+    Ge,
+
+    /// Membership of a value in a list, or of a key in a map, `x in y`.
+    In,
+}
+
+impl CelBinaryOperator {
+    /// The operator as it appears in a CEL expression, such as `&&`.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CelBinaryOperator::And => "&&",
+            CelBinaryOperator::Or => "||",
+            CelBinaryOperator::Eq => "==",
+            CelBinaryOperator::Ne => "!=",
+            CelBinaryOperator::Lt => "<",
+            CelBinaryOperator::Le => "<=",
+            CelBinaryOperator::Gt => ">",
+            CelBinaryOperator::Ge => ">=",
+            CelBinaryOperator::In => " in ",
+        }
+    }
+
+    fn evaluate(
+        &self,
+        lhs: CelConditionValue,
+        rhs: CelConditionValue,
+    ) -> HttpCertificationResult<CelConditionValue> {
+        let type_mismatch = HttpCertificationError::CelTypeMismatch {
+            operation: self.symbol().trim().to_string(),
+            found: format!("{}, {}", lhs.type_name(), rhs.type_name()),
+        };
+
+        let value = match (self, &lhs, &rhs) {
+            (CelBinaryOperator::Eq, _, _) => lhs == rhs,
+            (CelBinaryOperator::Ne, _, _) => lhs != rhs,
+            (CelBinaryOperator::In, value, CelConditionValue::List(values)) => {
+                values.contains(value)
+            }
+            (
+                CelBinaryOperator::In,
+                CelConditionValue::String(key),
+                CelConditionValue::Map(entries),
+            ) => entries.iter().any(|(name, _)| name == key),
+            (operator, CelConditionValue::Int(a), CelConditionValue::Int(b)) => match operator {
+                CelBinaryOperator::Lt => a < b,
+                CelBinaryOperator::Le => a <= b,
+                CelBinaryOperator::Gt => a > b,
+                CelBinaryOperator::Ge => a >= b,
+                _ => return Err(type_mismatch),
+            },
+            (operator, CelConditionValue::String(a), CelConditionValue::String(b)) => {
+                match operator {
+                    CelBinaryOperator::Lt => a < b,
+                    CelBinaryOperator::Le => a <= b,
+                    CelBinaryOperator::Gt => a > b,
+                    CelBinaryOperator::Ge => a >= b,
+                    _ => return Err(type_mismatch),
+                }
+            }
+            (operator, CelConditionValue::Bool(a), CelConditionValue::Bool(b)) => match operator {
+                CelBinaryOperator::And => *a && *b,
+                CelBinaryOperator::Or => *a || *b,
+                _ => return Err(type_mismatch),
+            },
+            _ => return Err(type_mismatch),
+        };
+
+        Ok(CelConditionValue::Bool(value))
+    }
+}
+
+/// A condition that is evaluated against an [HTTP request](crate::HttpRequest) and an
+/// [HTTP response](crate::HttpResponse) to select which expression of a [ConditionalCelExpression]
+/// is used for certification.
+///
+/// Conditions support a subset of CEL, consisting of literals, lists, [request and response attributes](CelAttribute),
+/// [boolean logic and comparisons](CelBinaryOperator), indexing into lists and maps, and a small set of
+/// [string and list functions](CelFunction). For example:
+///
+/// ```text
+/// request.method == "GET" && response.status_code == 200
+/// "accept-language" in request.headers && request.path.startsWith("/docs/")
+/// ```
+///
+/// Accessing a key that does not exist in a map, or an index that does not exist in a list, evaluates
+/// to `null`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CelCondition<'a> {
+    /// The `null` literal.
+    Null,
+
+    /// A boolean literal, `true` or `false`.
+    Bool(bool),
+
+    /// An integer literal, such as `200`.
+    Int(i64),
+
+    /// A string literal, such as `"GET"`.
+    String(Cow<'a, str>),
+
+    /// A list of values, such as `[200, 204]`.
+    List(Vec<CelCondition<'a>>),
+
+    /// An attribute of the request or response, such as `request.method`.
+    Attribute(CelAttribute),
+
+    /// Logical negation of a boolean, `!x`.
+    Not(Box<CelCondition<'a>>),
+
+    /// A binary operation, such as `x == y`.
+    Binary(
+        CelBinaryOperator,
+        Box<CelCondition<'a>>,
+        Box<CelCondition<'a>>,
+    ),
+
+    /// Indexing into a list or map, such as `request.headers["accept"]`.
+    Index(Box<CelCondition<'a>>, Box<CelCondition<'a>>),
+
+    /// A function call, such as `size(x)` or `x.startsWith(y)`. The target of a method call is the first argument.
+    Call(CelFunction, Vec<CelCondition<'a>>),
+}
+
+impl<'a> CelCondition<'a> {
+    /// Creates a string literal from borrowed or owned data.
+    pub fn string(value: impl Into<Cow<'a, str>>) -> Self {
+        CelCondition::String(value.into())
+    }
+
+    /// Creates a binary operation from two operands.
+    pub fn binary(operator: CelBinaryOperator, lhs: Self, rhs: Self) -> Self {
+        CelCondition::Binary(operator, Box::new(lhs), Box::new(rhs))
+    }
+
+    /// Creates a condition that checks if the request method is one of the given methods,
+    /// such as `request.method in ["GET","HEAD"]`.
+    pub fn request_method_in(methods: impl IntoIterator<Item = impl Into<Cow<'a, str>>>) -> Self {
+        CelCondition::binary(
+            CelBinaryOperator::In,
+            CelCondition::Attribute(CelAttribute::RequestMethod),
+            CelCondition::List(methods.into_iter().map(CelCondition::string).collect()),
+        )
+    }
+
+    /// Creates a condition that checks if the response status code belongs to a class of status codes,
+    /// identified by its first digit. For example, the class `5` creates the condition
+    /// `response.status_code >= 500 && response.status_code < 600`, which matches all server errors.
+    pub fn response_status_code_class(class: u8) -> Self {
+        let min_status_code = i64::from(class) * 100;
+
+        CelCondition::binary(
+            CelBinaryOperator::And,
+            CelCondition::binary(
+                CelBinaryOperator::Ge,
+                CelCondition::Attribute(CelAttribute::ResponseStatusCode),
+                CelCondition::Int(min_status_code),
+            ),
+            CelCondition::binary(
+                CelBinaryOperator::Lt,
+                CelCondition::Attribute(CelAttribute::ResponseStatusCode),
+                CelCondition::Int(min_status_code + 100),
+            ),
+        )
+    }
+
+    /// Evaluates the condition against an [HTTP request](crate::HttpRequest) and an
+    /// [HTTP response](crate::HttpResponse).
+    ///
+    /// The `&&` and `||` operators are short-circuiting, so the right hand side is not evaluated
+    /// if the left hand side already determines the result.
+    pub fn evaluate(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+    ) -> HttpCertificationResult<CelConditionValue> {
+        let value = match self {
+            CelCondition::Null => CelConditionValue::Null,
+            CelCondition::Bool(value) => CelConditionValue::Bool(*value),
+            CelCondition::Int(value) => CelConditionValue::Int(*value),
+            CelCondition::String(value) => CelConditionValue::String(value.to_string()),
+            CelCondition::List(values) => CelConditionValue::List(
+                values
+                    .iter()
+                    .map(|value| value.evaluate(request, response))
+                    .collect::<HttpCertificationResult<_>>()?,
+            ),
+            CelCondition::Attribute(attribute) => attribute.evaluate(request, response)?,
+            CelCondition::Not(value) => match value.evaluate(request, response)? {
+                CelConditionValue::Bool(value) => CelConditionValue::Bool(!value),
+                value => {
+                    return Err(HttpCertificationError::CelTypeMismatch {
+                        operation: "!".to_string(),
+                        found: value.type_name().to_string(),
+                    })
+                }
+            },
+            CelCondition::Binary(operator, lhs, rhs) => {
+                let lhs = lhs.evaluate(request, response)?;
+
+                match (operator, &lhs) {
+                    (CelBinaryOperator::And, CelConditionValue::Bool(false)) => {
+                        CelConditionValue::Bool(false)
+                    }
+                    (CelBinaryOperator::Or, CelConditionValue::Bool(true)) => {
+                        CelConditionValue::Bool(true)
+                    }
+                    _ => operator.evaluate(lhs, rhs.evaluate(request, response)?)?,
+                }
+            }
+            CelCondition::Index(value, index) => {
+                match (
+                    value.evaluate(request, response)?,
+                    index.evaluate(request, response)?,
+                ) {
+                    (CelConditionValue::List(values), CelConditionValue::Int(index)) => {
+                        usize::try_from(index)
+                            .ok()
+                            .and_then(|index| values.into_iter().nth(index))
+                            .unwrap_or(CelConditionValue::Null)
+                    }
+                    (CelConditionValue::Map(entries), CelConditionValue::String(key)) => entries
+                        .into_iter()
+                        .find(|(name, _)| *name == key)
+                        .map(|(_, value)| value)
+                        .unwrap_or(CelConditionValue::Null),
+                    (value, index) => {
+                        return Err(HttpCertificationError::CelTypeMismatch {
+                            operation: "[]".to_string(),
+                            found: format!("{}, {}", value.type_name(), index.type_name()),
+                        })
+                    }
+                }
+            }
+            CelCondition::Call(function, args) => {
+                if args.len() != function.arity() {
+                    return Err(HttpCertificationError::CelArgumentCountMismatch {
+                        function: function.name().to_string(),
+                        expected: function.arity(),
+                        found: args.len(),
+                    });
+                }
+
+                function.evaluate(
+                    args.iter()
+                        .map(|arg| arg.evaluate(request, response))
+                        .collect::<HttpCertificationResult<_>>()?,
+                )?
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Whether the condition accesses any attributes of the [HTTP response](crate::HttpResponse).
+    pub fn references_response(&self) -> bool {
+        match self {
+            CelCondition::Null
+            | CelCondition::Bool(_)
+            | CelCondition::Int(_)
+            | CelCondition::String(_) => false,
+            CelCondition::Attribute(attribute) => attribute.is_response_attribute(),
+            CelCondition::Not(value) => value.references_response(),
+            CelCondition::Binary(_, lhs, rhs) | CelCondition::Index(lhs, rhs) => {
+                lhs.references_response() || rhs.references_response()
+            }
+            CelCondition::List(values) | CelCondition::Call(_, values) => {
+                values.iter().any(CelCondition::references_response)
+            }
+        }
+    }
+
+    /// Converts this [CelCondition] into an owned version that does not borrow any data,
+    /// for example to store it in a canister's state.
+    pub fn into_owned(self) -> CelCondition<'static> {
+        match self {
+            CelCondition::Null => CelCondition::Null,
+            CelCondition::Bool(value) => CelCondition::Bool(value),
+            CelCondition::Int(value) => CelCondition::Int(value),
+            CelCondition::String(value) => CelCondition::String(Cow::Owned(value.into_owned())),
+            CelCondition::List(values) => {
+                CelCondition::List(values.into_iter().map(CelCondition::into_owned).collect())
+            }
+            CelCondition::Attribute(attribute) => CelCondition::Attribute(attribute),
+            CelCondition::Not(value) => CelCondition::Not(Box::new(value.into_owned())),
+            CelCondition::Binary(operator, lhs, rhs) => CelCondition::Binary(
+                operator,
+                Box::new(lhs.into_owned()),
+                Box::new(rhs.into_owned()),
+            ),
+            CelCondition::Index(value, index) => {
+                CelCondition::Index(Box::new(value.into_owned()), Box::new(index.into_owned()))
+            }
+            CelCondition::Call(function, args) => CelCondition::Call(
+                function,
+                args.into_iter().map(CelCondition::into_owned).collect(),
+            ),
+        }
+    }
+}
+
+impl ToString for CelCondition<'_> {
+    /// Converts a [CelCondition] object into it's [String] representation.
+    /// Alias of [create_cel_condition_expr](create_cel_condition_expr()).
+    fn to_string(&self) -> String {
+        create_cel_condition_expr(self)
+    }
+}
+
+/// A value produced by evaluating a [CelCondition].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CelConditionValue {
+    /// The `null` value.
+    Null,
+
+    /// A boolean value.
+    Bool(bool),
+
+    /// An integer value.
+    Int(i64),
+
+    /// A string value.
+    String(String),
+
+    /// A list of values.
+    List(Vec<CelConditionValue>),
+
+    /// A map from strings to values. Duplicate keys are preserved in their original order,
+    /// and indexing into the map returns the first matching value.
+    Map(Vec<(String, CelConditionValue)>),
+}
+
+impl CelConditionValue {
+    /// The name of the value's type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            CelConditionValue::Null => "null",
+            CelConditionValue::Bool(_) => "bool",
+            CelConditionValue::Int(_) => "int",
+            CelConditionValue::String(_) => "string",
+            CelConditionValue::List(_) => "list",
+            CelConditionValue::Map(_) => "map",
+        }
+    }
+}
+
+impl<'a> CelExpression<'a> {
+    /// Evaluates any [conditions](ConditionalCelExpression) in the expression against an
+    /// [HTTP request](crate::HttpRequest) and an [HTTP response](crate::HttpResponse), and returns the
+    /// [DefaultCelExpression] that is used to calculate the request and response hashes.
+    ///
+    /// Conditions that access attributes of the response are rejected if any branch of the expression
+    /// skips certification, since the response is not certified in that case and could be modified to
+    /// select a different branch.
+    pub fn evaluate(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+    ) -> HttpCertificationResult<&DefaultCelExpression<'a>> {
+        if self.references_response() && self.may_skip_certification() {
+            return Err(HttpCertificationError::UncertifiedCelConditionResponse);
+        }
+
+        let mut cel_expr = self;
+        loop {
+            match cel_expr {
+                CelExpression::Default(default_cel_expr) => return Ok(default_cel_expr),
+                CelExpression::Conditional(conditional) => {
+                    cel_expr = match conditional.condition.evaluate(request, response)? {
+                        CelConditionValue::Bool(true) => &conditional.if_true,
+                        CelConditionValue::Bool(false) => &conditional.if_false,
+                        value => {
+                            return Err(HttpCertificationError::CelTypeMismatch {
+                                operation: "?:".to_string(),
+                                found: value.type_name().to_string(),
+                            })
+                        }
+                    };
+                }
+            }
+        }
+    }
+
+    fn references_response(&self) -> bool {
+        match self {
+            CelExpression::Default(_) => false,
+            CelExpression::Conditional(conditional) => {
+                conditional.condition.references_response()
+                    || conditional.if_true.references_response()
+                    || conditional.if_false.references_response()
+            }
+        }
+    }
+
+    fn may_skip_certification(&self) -> bool {
+        match self {
+            CelExpression::Default(default_cel_expr) => {
+                matches!(default_cel_expr, DefaultCelExpression::Skip)
+            }
+            CelExpression::Conditional(conditional) => {
+                conditional.if_true.may_skip_certification()
+                    || conditional.if_false.may_skip_certification()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultCelBuilder;
+    use rstest::*;
+
+    #[fixture]
+    fn request() -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            url: "/docs/index.html?page=2&lang=en".to_string(),
+            headers: vec![
+                ("Accept-Language".to_string(), "en-US".to_string()),
+                ("Accept".to_string(), "text/html".to_string()),
+            ],
+            body: vec![],
+        }
+    }
+
+    #[fixture]
+    fn response() -> HttpResponse {
+        HttpResponse {
+            status_code: 200,
+            headers: vec![("Cache-Control".to_string(), "no-cache".to_string())],
+            body: vec![],
+            upgrade: None,
+        }
+    }
+
+    fn attribute(attribute: CelAttribute) -> CelCondition<'static> {
+        CelCondition::Attribute(attribute)
+    }
+
+    fn index(value: CelCondition<'static>, key: &'static str) -> CelCondition<'static> {
+        CelCondition::Index(Box::new(value), Box::new(CelCondition::string(key)))
+    }
+
+    #[rstest]
+    #[case::method(attribute(CelAttribute::RequestMethod), CelConditionValue::String("GET".to_string()))]
+    #[case::path(attribute(CelAttribute::RequestPath), CelConditionValue::String("/docs/index.html".to_string()))]
+    #[case::status_code(
+        attribute(CelAttribute::ResponseStatusCode),
+        CelConditionValue::Int(200)
+    )]
+    #[case::query_param(index(attribute(CelAttribute::RequestQuery), "page"), CelConditionValue::String("2".to_string()))]
+    #[case::lowercase_header(index(attribute(CelAttribute::RequestHeaders), "accept-language"), CelConditionValue::String("en-US".to_string()))]
+    #[case::missing_header(
+        index(attribute(CelAttribute::ResponseHeaders), "etag"),
+        CelConditionValue::Null
+    )]
+    #[case::header_size(CelCondition::Call(CelFunction::Size, vec![attribute(CelAttribute::RequestHeaders)]), CelConditionValue::Int(2))]
+    #[case::header_in(
+        CelCondition::binary(
+            CelBinaryOperator::In,
+            CelCondition::string("cache-control"),
+            attribute(CelAttribute::ResponseHeaders)
+        ),
+        CelConditionValue::Bool(true)
+    )]
+    #[case::method_in_list(
+        CelCondition::binary(
+            CelBinaryOperator::In,
+            attribute(CelAttribute::RequestMethod),
+            CelCondition::List(vec![CelCondition::string("GET"), CelCondition::string("HEAD")]),
+        ),
+        CelConditionValue::Bool(true)
+    )]
+    #[case::starts_with(
+        CelCondition::Call(CelFunction::StartsWith, vec![attribute(CelAttribute::RequestPath), CelCondition::string("/docs/")]),
+        CelConditionValue::Bool(true)
+    )]
+    #[case::lower_ascii(
+        CelCondition::Call(CelFunction::LowerAscii, vec![index(attribute(CelAttribute::RequestHeaders), "accept-language")]),
+        CelConditionValue::String("en-us".to_string())
+    )]
+    #[case::status_code_range(
+        CelCondition::binary(
+            CelBinaryOperator::And,
+            CelCondition::binary(
+                CelBinaryOperator::Ge,
+                attribute(CelAttribute::ResponseStatusCode),
+                CelCondition::Int(200)
+            ),
+            CelCondition::binary(
+                CelBinaryOperator::Lt,
+                attribute(CelAttribute::ResponseStatusCode),
+                CelCondition::Int(300)
+            ),
+        ),
+        CelConditionValue::Bool(true)
+    )]
+    #[case::request_method_in(CelCondition::request_method_in(["HEAD", "GET"]), CelConditionValue::Bool(true))]
+    #[case::request_method_not_in(CelCondition::request_method_in(["POST"]), CelConditionValue::Bool(false))]
+    #[case::response_status_code_class(
+        CelCondition::response_status_code_class(2),
+        CelConditionValue::Bool(true)
+    )]
+    #[case::other_response_status_code_class(
+        CelCondition::response_status_code_class(5),
+        CelConditionValue::Bool(false)
+    )]
+    #[case::not(
+        CelCondition::Not(Box::new(CelCondition::binary(
+            CelBinaryOperator::Eq,
+            attribute(CelAttribute::RequestMethod),
+            CelCondition::string("GET")
+        ))),
+        CelConditionValue::Bool(false)
+    )]
+    #[case::different_types_not_equal(
+        CelCondition::binary(
+            CelBinaryOperator::Eq,
+            attribute(CelAttribute::ResponseStatusCode),
+            CelCondition::string("200")
+        ),
+        CelConditionValue::Bool(false)
+    )]
+    #[case::short_circuit(
+        CelCondition::binary(
+            CelBinaryOperator::Or,
+            CelCondition::Bool(true),
+            CelCondition::Int(1)
+        ),
+        CelConditionValue::Bool(true)
+    )]
+    fn evaluate_condition(
+        request: HttpRequest,
+        response: HttpResponse,
+        #[case] condition: CelCondition,
+        #[case] expected_value: CelConditionValue,
+    ) {
+        let value = condition.evaluate(&request, &response).unwrap();
+
+        assert_eq!(value, expected_value);
+    }
+
+    #[rstest]
+    #[case::not_int(CelCondition::Not(Box::new(CelCondition::Int(1))))]
+    #[case::and_int(CelCondition::binary(
+        CelBinaryOperator::And,
+        CelCondition::Bool(true),
+        CelCondition::Int(1)
+    ))]
+    #[case::compare_mixed(CelCondition::binary(
+        CelBinaryOperator::Lt,
+        CelCondition::Int(1),
+        CelCondition::string("2")
+    ))]
+    #[case::starts_with_int(CelCondition::Call(CelFunction::StartsWith, vec![CelCondition::Int(1), CelCondition::string("1")]))]
+    #[case::wrong_argument_count(CelCondition::Call(CelFunction::Size, vec![]))]
+    fn evaluate_invalid_condition(
+        request: HttpRequest,
+        response: HttpResponse,
+        #[case] condition: CelCondition,
+    ) {
+        let result = condition.evaluate(&request, &response);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::CelTypeMismatch { .. })
+                | Err(HttpCertificationError::CelArgumentCountMismatch { .. })
+        ));
+    }
+
+    #[rstest]
+    #[case::plus_as_space(index(attribute(CelAttribute::RequestQuery), "q"), CelConditionValue::String("hello world".to_string()))]
+    #[case::percent_encoded_name(index(attribute(CelAttribute::RequestQuery), "café"), CelConditionValue::String("crème brûlée".to_string()))]
+    #[case::empty_value(
+        index(attribute(CelAttribute::RequestQuery), "flag"),
+        CelConditionValue::String(String::new())
+    )]
+    #[case::duplicates_counted(CelCondition::Call(CelFunction::Size, vec![attribute(CelAttribute::RequestQuery)]), CelConditionValue::Int(4))]
+    fn evaluate_decoded_query(
+        mut request: HttpRequest,
+        response: HttpResponse,
+        #[case] condition: CelCondition,
+        #[case] expected_value: CelConditionValue,
+    ) {
+        request.url =
+            "/?q=hello+world&caf%C3%A9=cr%C3%A8me%20br%C3%BBl%C3%A9e&q=second&flag".to_string();
+
+        let value = condition.evaluate(&request, &response).unwrap();
+
+        assert_eq!(value, expected_value);
+    }
+
+    #[rstest]
+    fn evaluate_invalid_utf8_query(mut request: HttpRequest, response: HttpResponse) {
+        request.url = "/?q=%FF".to_string();
+
+        let result =
+            index(attribute(CelAttribute::RequestQuery), "q").evaluate(&request, &response);
+
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    fn evaluate_conditional_expression(request: HttpRequest, response: HttpResponse) {
+        let full_cel_expr =
+            DefaultCelExpression::Full(DefaultCelBuilder::full_certification().build());
+        let response_only_cel_expr = DefaultCelExpression::ResponseOnly(
+            DefaultCelBuilder::response_only_certification().build(),
+        );
+        let cel_expr = CelExpression::Conditional(ConditionalCelExpression {
+            condition: CelCondition::binary(
+                CelBinaryOperator::Ne,
+                attribute(CelAttribute::ResponseStatusCode),
+                CelCondition::Int(200),
+            ),
+            if_true: Box::new(CelExpression::Default(response_only_cel_expr)),
+            if_false: Box::new(CelExpression::Conditional(ConditionalCelExpression {
+                condition: CelCondition::string("page").into_owned(),
+                if_true: Box::new(CelExpression::Default(DefaultCelExpression::Skip)),
+                if_false: Box::new(CelExpression::Default(full_cel_expr.clone())),
+            })),
+        });
+
+        let result = cel_expr.evaluate(&request, &response);
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::UncertifiedCelConditionResponse)
+        ));
+
+        let cel_expr = CelExpression::Conditional(ConditionalCelExpression {
+            condition: CelCondition::binary(
+                CelBinaryOperator::In,
+                CelCondition::string("page"),
+                attribute(CelAttribute::RequestQuery),
+            ),
+            if_true: Box::new(CelExpression::Default(full_cel_expr.clone())),
+            if_false: Box::new(CelExpression::Default(DefaultCelExpression::Skip)),
+        });
+
+        let result = cel_expr.evaluate(&request, &response).unwrap();
+        assert_eq!(result, &full_cel_expr);
+    }
+
+    #[rstest]
+    fn evaluate_response_status_code_class_expression(
+        request: HttpRequest,
+        mut response: HttpResponse,
+    ) {
+        let full_cel_expr =
+            DefaultCelExpression::Full(DefaultCelBuilder::full_certification().build());
+        let response_only_cel_expr = DefaultCelExpression::ResponseOnly(
+            DefaultCelBuilder::response_only_certification().build(),
+        );
+
+        let cel_expr = CelExpression::Conditional(ConditionalCelExpression {
+            condition: CelCondition::response_status_code_class(5),
+            if_true: Box::new(CelExpression::Default(DefaultCelExpression::Skip)),
+            if_false: Box::new(CelExpression::Default(full_cel_expr.clone())),
+        });
+        let result = cel_expr.evaluate(&request, &response);
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::UncertifiedCelConditionResponse)
+        ));
+
+        let cel_expr = CelExpression::Conditional(ConditionalCelExpression {
+            condition: CelCondition::response_status_code_class(5),
+            if_true: Box::new(CelExpression::Default(response_only_cel_expr.clone())),
+            if_false: Box::new(CelExpression::Default(full_cel_expr.clone())),
+        });
+        assert_eq!(
+            cel_expr.evaluate(&request, &response).unwrap(),
+            &full_cel_expr
+        );
+
+        response.status_code = 503;
+        assert_eq!(
+            cel_expr.evaluate(&request, &response).unwrap(),
+            &response_only_cel_expr
+        );
+    }
+
+    #[rstest]
+    fn condition_helpers_to_string() {
+        assert_eq!(
+            CelCondition::request_method_in(["GET", "HEAD"]).to_string(),
+            r#"request.method in ["GET","HEAD"]"#
+        );
+        assert_eq!(
+            CelCondition::response_status_code_class(5).to_string(),
+            "(response.status_code>=500)&&(response.status_code<600)"
+        );
+    }
+
+    #[rstest]
+    fn conditional_expression_to_string() {
+        let cel_expr = CelExpression::Conditional(ConditionalCelExpression {
+            condition: CelCondition::binary(
+                CelBinaryOperator::Or,
+                CelCondition::binary(
+                    CelBinaryOperator::In,
+                    attribute(CelAttribute::RequestMethod),
+                    CelCondition::List(vec![
+                        CelCondition::string("GET"),
+                        CelCondition::string("HEAD"),
+                    ]),
+                ),
+                CelCondition::Call(
+                    CelFunction::EndsWith,
+                    vec![
+                        attribute(CelAttribute::RequestPath),
+                        CelCondition::string("\"\\"),
+                    ],
+                ),
+            ),
+            if_true: Box::new(CelExpression::Default(DefaultCelExpression::Skip)),
+            if_false: Box::new(CelExpression::Default(DefaultCelExpression::Skip)),
+        });
+
+        assert_eq!(
+            cel_expr.to_string(),
+            format!(
+                r#"(request.method in ["GET","HEAD"])||request.path.endsWith("\"\\")?{skip}:{skip}"#,
+                skip = DefaultCelExpression::Skip.to_string()
+            )
+        );
+    }
+}
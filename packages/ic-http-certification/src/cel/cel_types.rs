@@ -1,21 +1,70 @@
 use super::{
-    create_cel_expr, create_default_cel_expr, create_default_full_cel_expr,
-    create_default_response_only_cel_expr,
+    canonicalize_cel_expr, create_cel_expr, create_default_cel_expr, create_default_full_cel_expr,
+    create_default_response_only_cel_expr, parse_cel_expr, CelParserError, CelParserResult,
+    ConditionalCelExpression,
 };
-use std::borrow::Cow;
+use std::{borrow::Cow, str::FromStr};
 
 /// A certification CEL expression defintion.
-/// Contains an enum variant for each CEL function supported for certification.
-/// Currently only one variant is supported: [CelExpression::Default].
+/// Contains an enum variant for each CEL function supported for certification,
+/// and a [Conditional](CelExpression::Conditional) variant for selecting between them.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CelExpression<'a> {
     /// A certification CEL expression definition that uses the `default_certification` function.
-    /// This is currently the only supported function.
     ///
-    /// The enum's inner value is an [Option] to allow for opting in, or out of certification.
-    /// Providing [None] will opt out of certification, while providing [Some] will opt in to certification.
+    /// The inner [DefaultCelExpression] selects whether the request and response are both certified
+    /// ([Full](DefaultCelExpression::Full)), only the response is certified
+    /// ([ResponseOnly](DefaultCelExpression::ResponseOnly)), or certification is opted out of entirely
+    /// ([Skip](DefaultCelExpression::Skip)).
     /// See [DefaultCelExpression] for more details on its available parameters.
     Default(DefaultCelExpression<'a>),
+
+    /// A certification CEL expression that selects between two other expressions based on a condition
+    /// that is evaluated against the request and response.
+    /// See [ConditionalCelExpression] for more details.
+    Conditional(ConditionalCelExpression<'a>),
+}
+
+impl CelExpression<'_> {
+    /// Converts this [CelExpression] into an owned version that does not borrow any data,
+    /// for example to store it in a canister's state.
+    pub fn into_owned(self) -> CelExpression<'static> {
+        match self {
+            CelExpression::Default(expr) => CelExpression::Default(expr.into_owned()),
+            CelExpression::Conditional(expr) => CelExpression::Conditional(expr.into_owned()),
+        }
+    }
+
+    /// Converts this [CelExpression] into its canonical form.
+    /// Alias of [canonicalize_cel_expr](canonicalize_cel_expr()).
+    pub fn canonicalize(&self) -> CelExpression<'static> {
+        canonicalize_cel_expr(self)
+    }
+
+    /// Checks if this [CelExpression] is semantically equivalent to another [CelExpression],
+    /// meaning that both expressions have the same canonical form.
+    /// See [canonicalize_cel_expr](canonicalize_cel_expr()) for details on the canonical form.
+    pub fn is_equivalent(&self, other: &CelExpression) -> bool {
+        self.canonicalize() == other.canonicalize()
+    }
+}
+
+impl CelExpression<'static> {
+    /// Parses a CEL expression from it's [String] representation.
+    /// Alias of [parse_cel_expr](parse_cel_expr()).
+    pub fn parse(cel_expr: &str) -> CelParserResult<Self> {
+        parse_cel_expr(cel_expr)
+    }
+}
+
+impl FromStr for CelExpression<'static> {
+    type Err = CelParserError;
+
+    /// Parses a CEL expression from it's [String] representation.
+    /// Alias of [parse_cel_expr](parse_cel_expr()).
+    fn from_str(cel_expr: &str) -> CelParserResult<Self> {
+        parse_cel_expr(cel_expr)
+    }
 }
 
 impl ToString for CelExpression<'_> {
@@ -57,6 +106,20 @@ pub enum DefaultCelExpression<'a> {
     Skip,
 }
 
+impl DefaultCelExpression<'_> {
+    /// Converts this [DefaultCelExpression] into an owned version that does not borrow any data,
+    /// for example to store it in a canister's state.
+    pub fn into_owned(self) -> DefaultCelExpression<'static> {
+        match self {
+            DefaultCelExpression::Full(expr) => DefaultCelExpression::Full(expr.into_owned()),
+            DefaultCelExpression::ResponseOnly(expr) => {
+                DefaultCelExpression::ResponseOnly(expr.into_owned())
+            }
+            DefaultCelExpression::Skip => DefaultCelExpression::Skip,
+        }
+    }
+}
+
 impl ToString for DefaultCelExpression<'_> {
     /// Converts a [DefaultCelExpression] object into it's [String] representation.
     ///
@@ -73,6 +136,21 @@ pub struct DefaultResponseOnlyCelExpression<'a> {
     /// Options for configuring response certification for this CEL expression.
     /// See [DefaultResponseCertification] for details on how to configure response certification.
     pub response: DefaultResponseCertification<'a>,
+
+    /// Options for configuring how the values of the certified response headers are included in certification.
+    /// See [ResponseHeaderCertificationOptions] for details on the available options.
+    pub response_header_options: ResponseHeaderCertificationOptions,
+}
+
+impl DefaultResponseOnlyCelExpression<'_> {
+    /// Converts this [DefaultResponseOnlyCelExpression] into an owned version that does not borrow any data,
+    /// for example to store it in a canister's state.
+    pub fn into_owned(self) -> DefaultResponseOnlyCelExpression<'static> {
+        DefaultResponseOnlyCelExpression {
+            response: self.response.into_owned(),
+            response_header_options: self.response_header_options,
+        }
+    }
 }
 
 impl ToString for DefaultResponseOnlyCelExpression<'_> {
@@ -95,6 +173,22 @@ pub struct DefaultFullCelExpression<'a> {
     /// Options for configuring response certification for this CEL expression.
     /// See [DefaultResponseCertification] for details on how to configure response certification.
     pub response: DefaultResponseCertification<'a>,
+
+    /// Options for configuring how the values of the certified response headers are included in certification.
+    /// See [ResponseHeaderCertificationOptions] for details on the available options.
+    pub response_header_options: ResponseHeaderCertificationOptions,
+}
+
+impl DefaultFullCelExpression<'_> {
+    /// Converts this [DefaultFullCelExpression] into an owned version that does not borrow any data,
+    /// for example to store it in a canister's state.
+    pub fn into_owned(self) -> DefaultFullCelExpression<'static> {
+        DefaultFullCelExpression {
+            request: self.request.into_owned(),
+            response: self.response.into_owned(),
+            response_header_options: self.response_header_options,
+        }
+    }
 }
 
 impl ToString for DefaultFullCelExpression<'_> {
@@ -107,22 +201,188 @@ impl ToString for DefaultFullCelExpression<'_> {
 
 /// Options for configuring certification of a request.
 ///
-/// The request method and body are always certified, but this struct allows configuring the
-/// certification of request [headers](DefaultRequestCertification::headers) and
-/// [query parameters](DefaultRequestCertification::query_parameters).
+/// The request method is always certified, but this struct allows configuring the
+/// [methods](DefaultRequestCertification::methods) that share a certification, and the
+/// certification of request [headers](DefaultRequestCertification::headers),
+/// [query parameters](DefaultRequestCertification::query_parameters) and
+/// the [request body](DefaultRequestCertification::body_mode).
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DefaultRequestCertification<'a> {
     /// A list of request headers to include in certification.
     ///
     /// As many or as little headers can be provided as desired.
     /// Providing an empty list will result in no request headers being certified.
-    pub headers: Cow<'a, [&'a str]>,
+    pub headers: Vec<Cow<'a, str>>,
 
     /// A list of request query parameters to include in certification.
     ///
     /// As many or as little query parameters can be provided as desired.
     /// Providing an empty list will result in no request query parameters being certified.
-    pub query_parameters: Cow<'a, [&'a str]>,
+    pub query_parameters: Vec<Cow<'a, str>>,
+
+    /// How the request query parameters listed in [query_parameters](DefaultRequestCertification::query_parameters)
+    /// are included in certification.
+    /// See [QueryParameterCertificationMode] for details on the available modes.
+    pub query_parameter_mode: QueryParameterCertificationMode,
+
+    /// How the request body is included in certification.
+    /// See [RequestBodyCertificationMode] for details on the available modes.
+    pub body_mode: RequestBodyCertificationMode,
+
+    /// A list of request methods that share the same certification.
+    ///
+    /// Providing an empty list will result in the exact method of the request being certified.
+    /// Otherwise, the list of methods is certified instead of the method of the request, so any of the listed
+    /// methods result in the same request hash, and requests with any other method fail to be certified.
+    /// For example, `["GET", "HEAD"]` allows a `HEAD` request to share the certification of a `GET` request.
+    pub methods: Vec<Cow<'a, str>>,
+}
+
+impl<'a> DefaultRequestCertification<'a> {
+    /// Creates a new [DefaultRequestCertification] from a list of request headers and a list of
+    /// request query parameters to include in certification.
+    ///
+    /// Both lists accept borrowed or owned strings, so they can also be built dynamically from runtime data.
+    pub fn new(
+        headers: impl IntoIterator<Item = impl Into<Cow<'a, str>>>,
+        query_parameters: impl IntoIterator<Item = impl Into<Cow<'a, str>>>,
+    ) -> Self {
+        Self {
+            headers: headers.into_iter().map(Into::into).collect(),
+            query_parameters: query_parameters.into_iter().map(Into::into).collect(),
+            query_parameter_mode: QueryParameterCertificationMode::default(),
+            body_mode: RequestBodyCertificationMode::default(),
+            methods: vec![],
+        }
+    }
+
+    /// Sets the [QueryParameterCertificationMode] that is used to include the request query parameters
+    /// in certification.
+    pub fn with_query_parameter_mode(
+        mut self,
+        query_parameter_mode: QueryParameterCertificationMode,
+    ) -> Self {
+        self.query_parameter_mode = query_parameter_mode;
+
+        self
+    }
+
+    /// Sets the [RequestBodyCertificationMode] that is used to include the request body in certification.
+    pub fn with_body_mode(mut self, body_mode: RequestBodyCertificationMode) -> Self {
+        self.body_mode = body_mode;
+
+        self
+    }
+
+    /// Sets the list of request [methods](DefaultRequestCertification::methods) that share the same certification.
+    pub fn with_methods(
+        mut self,
+        methods: impl IntoIterator<Item = impl Into<Cow<'a, str>>>,
+    ) -> Self {
+        self.methods = methods.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    /// Converts this [DefaultRequestCertification] into an owned version that does not borrow any data,
+    /// for example to store it in a canister's state.
+    pub fn into_owned(self) -> DefaultRequestCertification<'static> {
+        DefaultRequestCertification {
+            headers: into_owned_strs(self.headers),
+            query_parameters: into_owned_strs(self.query_parameters),
+            query_parameter_mode: self.query_parameter_mode,
+            body_mode: self.body_mode,
+            methods: into_owned_strs(self.methods),
+        }
+    }
+}
+
+/// Options for configuring how the certified query parameters of a request are included in certification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueryParameterCertificationMode {
+    /// The certified query parameters are included exactly as they appear in the request URL.
+    ///
+    /// Query parameters keep their original order and their values are not decoded, so `?a=1&b=2` and
+    /// `?b=2&a=1` result in different request hashes. This is the default mode.
+    #[default]
+    Raw,
+
+    /// The certified query parameters are included in a canonical form, so that requests with equivalent
+    /// query strings result in the same request hash.
+    ///
+    /// Query parameter names and values are percent-decoded, with `+` decoded as a space, and names are
+    /// converted to lowercase. The parameters are then sorted by name. Parameters that appear multiple times
+    /// keep all of their values, in their original order, so `?a=1&b=2&a=3` and `?b=2&a=1&a=3` result in the
+    /// same request hash, while `?a=3&a=1` results in a different one. A parameter without a value is treated
+    /// the same as a parameter with an empty value. Finally, names and values are percent-encoded again and
+    /// joined into a query string.
+    Canonical,
+}
+
+impl QueryParameterCertificationMode {
+    /// Returns the name of this mode, as it appears in the `query_parameter_mode` property of a CEL expression.
+    pub fn name(&self) -> &'static str {
+        match self {
+            QueryParameterCertificationMode::Raw => "raw",
+            QueryParameterCertificationMode::Canonical => "canonical",
+        }
+    }
+
+    /// Returns the mode with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "raw" => Some(QueryParameterCertificationMode::Raw),
+            "canonical" => Some(QueryParameterCertificationMode::Canonical),
+            _ => None,
+        }
+    }
+}
+
+/// Options for configuring how the body of a request is included in certification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RequestBodyCertificationMode {
+    /// The hash of the request body is certified exactly as it was received. This is the default mode.
+    #[default]
+    Raw,
+
+    /// The request body is excluded from certification, and is hashed as if it were empty.
+    /// Any request body will be accepted by verifiers.
+    Skip,
+
+    /// The request body is parsed as JSON and the hash of its canonical form is certified, so that
+    /// semantically equal JSON bodies result in the same request hash.
+    ///
+    /// The canonical form is defined by the [JSON Canonicalization Scheme](https://www.rfc-editor.org/rfc/rfc8785)
+    /// (JCS): it does not contain any insignificant whitespace, object keys are sorted by their UTF-16 code units,
+    /// strings are escaped like ECMAScript's `JSON.stringify` does and numbers are converted to IEEE 754 doubles
+    /// and serialized like ECMAScript's `Number.prototype.toString` does, so `1`, `1.0` and `1e0` are
+    /// considered to be equal.
+    ///
+    /// Requests with a body that is not valid JSON cannot be certified with this mode. Neither can requests
+    /// with a body that contains duplicate object keys, or integers that cannot be represented exactly as an
+    /// IEEE 754 double, since implementations would disagree on the canonical form of such a body.
+    CanonicalJson,
+}
+
+impl RequestBodyCertificationMode {
+    /// Returns the name of this mode, as it appears in the `body_mode` property of a CEL expression.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RequestBodyCertificationMode::Raw => "raw",
+            RequestBodyCertificationMode::Skip => "skip",
+            RequestBodyCertificationMode::CanonicalJson => "canonical_json",
+        }
+    }
+
+    /// Returns the mode with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "raw" => Some(RequestBodyCertificationMode::Raw),
+            "skip" => Some(RequestBodyCertificationMode::Skip),
+            "canonical_json" => Some(RequestBodyCertificationMode::CanonicalJson),
+            _ => None,
+        }
+    }
 }
 
 /// Options for configuring certification of a response.
@@ -132,6 +392,9 @@ pub struct DefaultRequestCertification<'a> {
 /// [CertifiedResponseHeaders](DefaultResponseCertification::CertifiedResponseHeaders) variant,
 /// and response headers may be excluded using the
 /// [ResponseHeaderExclusions](DefaultResponseCertification::ResponseHeaderExclusions) variant.
+///
+/// How the values of the certified response headers are included in certification is configured
+/// separately with [ResponseHeaderCertificationOptions].
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DefaultResponseCertification<'a> {
     /// A list of response headers to include in certification.
@@ -141,7 +404,7 @@ pub enum DefaultResponseCertification<'a> {
     ///
     /// See [certified_response_headers](DefaultResponseCertification::certified_response_headers())
     /// for a more ergonomic way of doing this.
-    CertifiedResponseHeaders(Cow<'a, [&'a str]>),
+    CertifiedResponseHeaders(Vec<Cow<'a, str>>),
 
     /// A list of response headers to exclude from certification.
     ///
@@ -150,7 +413,7 @@ pub enum DefaultResponseCertification<'a> {
     ///
     /// See [response_header_exclusions](DefaultResponseCertification::response_header_exclusions())
     /// for a more ergonomic way of doing this.
-    ResponseHeaderExclusions(Cow<'a, [&'a str]>),
+    ResponseHeaderExclusions(Vec<Cow<'a, str>>),
 }
 
 impl<'a> DefaultResponseCertification<'a> {
@@ -159,7 +422,7 @@ impl<'a> DefaultResponseCertification<'a> {
     /// As many or as little headers can be provided as desired.
     /// Providing an empty list will result in no response headers being certified.
     pub fn certified_response_headers(headers: &'a [&'a str]) -> Self {
-        Self::CertifiedResponseHeaders(Cow::Borrowed(headers))
+        Self::CertifiedResponseHeaders(borrow_strs(headers))
     }
 
     /// A list of response headers to exclude from certification.
@@ -167,12 +430,169 @@ impl<'a> DefaultResponseCertification<'a> {
     /// As many or as little headers can be provided as desired.
     /// Providing an empty list will result in all response headers being certified.
     pub fn response_header_exclusions(headers: &'a [&'a str]) -> Self {
-        Self::ResponseHeaderExclusions(Cow::Borrowed(headers))
+        Self::ResponseHeaderExclusions(borrow_strs(headers))
+    }
+
+    /// Returns the list of response headers that are either included in, or excluded from, certification.
+    pub fn headers(&self) -> &[Cow<'a, str>] {
+        match self {
+            Self::CertifiedResponseHeaders(headers) | Self::ResponseHeaderExclusions(headers) => {
+                headers
+            }
+        }
+    }
+
+    /// Returns true if the [headers](DefaultResponseCertification::headers()) are included in
+    /// certification, or false if they are excluded from certification.
+    pub fn is_inclusion(&self) -> bool {
+        matches!(self, Self::CertifiedResponseHeaders(_))
+    }
+
+    /// Converts this [DefaultResponseCertification] into an owned version that does not borrow any data,
+    /// for example to store it in a canister's state.
+    pub fn into_owned(self) -> DefaultResponseCertification<'static> {
+        match self {
+            Self::CertifiedResponseHeaders(headers) => {
+                DefaultResponseCertification::CertifiedResponseHeaders(into_owned_strs(headers))
+            }
+            Self::ResponseHeaderExclusions(headers) => {
+                DefaultResponseCertification::ResponseHeaderExclusions(into_owned_strs(headers))
+            }
+        }
     }
 }
 
 impl Default for DefaultResponseCertification<'_> {
     fn default() -> Self {
-        DefaultResponseCertification::CertifiedResponseHeaders(Cow::Borrowed(&[]))
+        DefaultResponseCertification::CertifiedResponseHeaders(vec![])
+    }
+}
+
+/// Options for configuring how the values of certified response headers are included in certification.
+///
+/// By default, every occurrence of a certified response header is certified separately and header
+/// values are certified exactly as they appear in the response.
+///
+/// New options may be added in the future, so this struct is constructed from its
+/// [default](ResponseHeaderCertificationOptions::default()) using the `with_*` builder methods.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ResponseHeaderCertificationOptions {
+    /// How response headers that appear multiple times in a response are included in certification.
+    /// See [DuplicateResponseHeaderMode] for details on the available modes.
+    pub duplicate_header_mode: DuplicateResponseHeaderMode,
+
+    /// How the values of response headers are normalized before they are included in certification.
+    /// See [ResponseHeaderValueMode] for details on the available modes.
+    pub header_value_mode: ResponseHeaderValueMode,
+}
+
+impl ResponseHeaderCertificationOptions {
+    /// Sets the [DuplicateResponseHeaderMode] that configures how response headers that appear multiple
+    /// times in a response are included in certification.
+    pub fn with_duplicate_header_mode(
+        mut self,
+        duplicate_header_mode: DuplicateResponseHeaderMode,
+    ) -> Self {
+        self.duplicate_header_mode = duplicate_header_mode;
+
+        self
+    }
+
+    /// Sets the [ResponseHeaderValueMode] that configures how the values of response headers are
+    /// normalized before they are included in certification.
+    pub fn with_header_value_mode(mut self, header_value_mode: ResponseHeaderValueMode) -> Self {
+        self.header_value_mode = header_value_mode;
+
+        self
+    }
+}
+
+/// Options for configuring how response headers that appear multiple times in a response are included
+/// in certification.
+///
+/// Header names are case-insensitive, so headers whose names only differ in case are considered to be
+/// the same header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DuplicateResponseHeaderMode {
+    /// Every occurrence of a response header is certified separately, and the order of occurrences of
+    /// the same header does not affect certification. This is the default mode.
+    #[default]
+    Separate,
+
+    /// All values of a response header are joined with `", "` in the order that they appear in the
+    /// response, and certified as a single value, as described in
+    /// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-5.3).
+    ///
+    /// This mode should not be used for headers such as `Set-Cookie`, whose values may contain commas.
+    Fold,
+
+    /// All values of a response header are certified as a single array, in the order that they appear in
+    /// the response. Unlike [Fold](DuplicateResponseHeaderMode::Fold), this mode is unambiguous for any
+    /// header value, including `Set-Cookie` values.
+    Array,
+}
+
+impl DuplicateResponseHeaderMode {
+    /// Returns the name of this mode, as it appears in the `duplicate_header_mode` property of a CEL expression.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DuplicateResponseHeaderMode::Separate => "separate",
+            DuplicateResponseHeaderMode::Fold => "fold",
+            DuplicateResponseHeaderMode::Array => "array",
+        }
     }
+
+    /// Returns the mode with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "separate" => Some(DuplicateResponseHeaderMode::Separate),
+            "fold" => Some(DuplicateResponseHeaderMode::Fold),
+            "array" => Some(DuplicateResponseHeaderMode::Array),
+            _ => None,
+        }
+    }
+}
+
+/// Options for configuring how the values of response headers are normalized before they are included
+/// in certification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResponseHeaderValueMode {
+    /// Response header values are certified exactly as they appear in the response. This is the default mode.
+    #[default]
+    Raw,
+
+    /// Leading and trailing spaces and tabs are removed from response header values before they are certified.
+    Trim,
+}
+
+impl ResponseHeaderValueMode {
+    /// Returns the name of this mode, as it appears in the `header_value_mode` property of a CEL expression.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResponseHeaderValueMode::Raw => "raw",
+            ResponseHeaderValueMode::Trim => "trim",
+        }
+    }
+
+    /// Returns the mode with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "raw" => Some(ResponseHeaderValueMode::Raw),
+            "trim" => Some(ResponseHeaderValueMode::Trim),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn borrow_strs<'a>(strs: &'a [&'a str]) -> Vec<Cow<'a, str>> {
+    strs.iter().map(|s| Cow::Borrowed(*s)).collect()
+}
+
+fn into_owned_strs(strs: Vec<Cow<'_, str>>) -> Vec<Cow<'static, str>> {
+    strs.into_iter()
+        .map(|s| Cow::Owned(s.into_owned()))
+        .collect()
 }
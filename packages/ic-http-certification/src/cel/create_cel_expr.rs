@@ -1,6 +1,8 @@
 use super::{
-    CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification,
-    DefaultResponseCertification, DefaultResponseOnlyCelExpression,
+    CelCondition, CelExpression, ConditionalCelExpression, DefaultCelExpression,
+    DefaultFullCelExpression, DefaultRequestCertification, DefaultResponseCertification,
+    DefaultResponseOnlyCelExpression, DuplicateResponseHeaderMode, QueryParameterCertificationMode,
+    RequestBodyCertificationMode, ResponseHeaderCertificationOptions, ResponseHeaderValueMode,
 };
 
 /// Converts a CEL expression from a [CelExpression] struct into it's [String] representation.
@@ -10,9 +12,114 @@ use super::{
 pub fn create_cel_expr(certification: &CelExpression) -> String {
     match certification {
         CelExpression::Default(certification) => create_default_cel_expr(certification),
+        CelExpression::Conditional(certification) => create_conditional_cel_expr(certification),
     }
 }
 
+/// Converts a CEL expression from a [ConditionalCelExpression] struct into it's [String] representation.
+pub fn create_conditional_cel_expr(certification: &ConditionalCelExpression) -> String {
+    let mut cel_expr = create_cel_condition_expr(&certification.condition);
+
+    cel_expr.push('?');
+    cel_expr.push_str(&create_cel_expr(&certification.if_true));
+    cel_expr.push(':');
+    cel_expr.push_str(&create_cel_expr(&certification.if_false));
+
+    cel_expr
+}
+
+/// Converts a condition from a [CelCondition] enum into it's [String] representation.
+///
+/// Operands of unary and binary operators are wrapped in parentheses if they are binary operations themselves,
+/// so the representation does not depend on operator precedence.
+///
+/// [CelCondition::to_string](CelCondition::to_string()) is an alias of this function and can be used
+/// for ergonomics.
+pub fn create_cel_condition_expr(condition: &CelCondition) -> String {
+    let mut cel_expr = String::new();
+    push_cel_condition(&mut cel_expr, condition);
+    cel_expr
+}
+
+fn push_cel_condition(cel_expr: &mut String, condition: &CelCondition) {
+    match condition {
+        CelCondition::Null => cel_expr.push_str("null"),
+        CelCondition::Bool(value) => cel_expr.push_str(&value.to_string()),
+        CelCondition::Int(value) => cel_expr.push_str(&value.to_string()),
+        CelCondition::String(value) => push_cel_string(cel_expr, value),
+        CelCondition::List(values) => {
+            cel_expr.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    cel_expr.push(',');
+                }
+                push_cel_condition(cel_expr, value);
+            }
+            cel_expr.push(']');
+        }
+        CelCondition::Attribute(attribute) => cel_expr.push_str(attribute.name()),
+        CelCondition::Not(value) => {
+            cel_expr.push('!');
+            push_cel_operand(cel_expr, value);
+        }
+        CelCondition::Binary(operator, lhs, rhs) => {
+            push_cel_operand(cel_expr, lhs);
+            cel_expr.push_str(operator.symbol());
+            push_cel_operand(cel_expr, rhs);
+        }
+        CelCondition::Index(value, index) => {
+            push_cel_operand(cel_expr, value);
+            cel_expr.push('[');
+            push_cel_condition(cel_expr, index);
+            cel_expr.push(']');
+        }
+        CelCondition::Call(function, args) => {
+            let args = match (function.is_method(), args.split_first()) {
+                (true, Some((target, args))) => {
+                    push_cel_operand(cel_expr, target);
+                    cel_expr.push('.');
+                    args
+                }
+                _ => args.as_slice(),
+            };
+
+            cel_expr.push_str(function.name());
+            cel_expr.push('(');
+            for (i, arg) in args.iter().enumerate() {
+                if i > 0 {
+                    cel_expr.push(',');
+                }
+                push_cel_condition(cel_expr, arg);
+            }
+            cel_expr.push(')');
+        }
+    }
+}
+
+fn push_cel_operand(cel_expr: &mut String, operand: &CelCondition) {
+    match operand {
+        CelCondition::Binary(..) => {
+            cel_expr.push('(');
+            push_cel_condition(cel_expr, operand);
+            cel_expr.push(')');
+        }
+        _ => push_cel_condition(cel_expr, operand),
+    }
+}
+
+fn push_cel_string(cel_expr: &mut String, value: &str) {
+    cel_expr.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => cel_expr.push_str(r#"\""#),
+            '\\' => cel_expr.push_str(r"\\"),
+            '\n' => cel_expr.push_str(r"\n"),
+            c => cel_expr.push(c),
+        }
+    }
+    cel_expr.push('"');
+}
+
 /// Converts a CEL expression from a [DefaultCelExpression] struct into it's [String] representation.
 ///
 /// [DefaultCelExpression::to_string](DefaultCelExpression::to_string()) is an alias of this function and
@@ -49,7 +156,11 @@ pub fn create_default_response_only_cel_expr(
     cel_expr.push_str("certification:Certification{");
     cel_expr.push_str("no_request_certification:Empty{},");
 
-    create_response_cel_expr(&mut cel_expr, &certification.response);
+    create_response_cel_expr(
+        &mut cel_expr,
+        &certification.response,
+        &certification.response_header_options,
+    );
 
     cel_expr.push('}');
 
@@ -67,7 +178,11 @@ pub fn create_default_full_cel_expr(certification: &DefaultFullCelExpression) ->
     cel_expr.push_str("certification:Certification{");
 
     create_request_cel_expr(&mut cel_expr, &certification.request);
-    create_response_cel_expr(&mut cel_expr, &certification.response);
+    create_response_cel_expr(
+        &mut cel_expr,
+        &certification.response,
+        &certification.response_header_options,
+    );
 
     cel_expr.push('}');
 
@@ -80,47 +195,67 @@ fn create_request_cel_expr(
     request_certification: &DefaultRequestCertification,
 ) {
     cel_expr.push_str("request_certification:RequestCertification{certified_request_headers:[");
+    push_cel_string_list(cel_expr, &request_certification.headers);
+
+    cel_expr.push_str("],certified_query_parameters:[");
+    push_cel_string_list(cel_expr, &request_certification.query_parameters);
+    cel_expr.push(']');
 
-    if !request_certification.headers.is_empty() {
-        cel_expr.push('"');
-        cel_expr.push_str(&request_certification.headers.join(r#"",""#));
-        cel_expr.push('"');
+    if request_certification.query_parameter_mode != QueryParameterCertificationMode::Raw {
+        cel_expr.push_str(",query_parameter_mode:");
+        push_cel_string(cel_expr, request_certification.query_parameter_mode.name());
     }
 
-    cel_expr.push_str("],certified_query_parameters:[");
-    if !request_certification.query_parameters.is_empty() {
-        cel_expr.push('"');
-        cel_expr.push_str(&request_certification.query_parameters.join(r#"",""#));
-        cel_expr.push('"');
+    if request_certification.body_mode != RequestBodyCertificationMode::Raw {
+        cel_expr.push_str(",body_mode:");
+        push_cel_string(cel_expr, request_certification.body_mode.name());
+    }
+
+    if !request_certification.methods.is_empty() {
+        cel_expr.push_str(",certified_methods:[");
+        push_cel_string_list(cel_expr, &request_certification.methods);
+        cel_expr.push(']');
     }
 
-    cel_expr.push_str("]},");
+    cel_expr.push_str("},");
 }
 
 fn create_response_cel_expr(
     cel_expr: &mut String,
     response_certification: &DefaultResponseCertification,
+    options: &ResponseHeaderCertificationOptions,
 ) {
     cel_expr.push_str("response_certification:ResponseCertification{");
 
-    let headers = match response_certification {
-        DefaultResponseCertification::CertifiedResponseHeaders(headers) => {
-            cel_expr.push_str("certified_response_headers");
-            headers
-        }
-        DefaultResponseCertification::ResponseHeaderExclusions(headers) => {
-            cel_expr.push_str("response_header_exclusions");
-            headers
-        }
-    };
+    if response_certification.is_inclusion() {
+        cel_expr.push_str("certified_response_headers");
+    } else {
+        cel_expr.push_str("response_header_exclusions");
+    }
 
     cel_expr.push_str(":ResponseHeaderList{headers:[");
-    if !headers.is_empty() {
-        cel_expr.push('"');
-        cel_expr.push_str(&headers.join(r#"",""#));
-        cel_expr.push('"');
+    push_cel_string_list(cel_expr, response_certification.headers());
+    cel_expr.push_str("]}");
+
+    if options.duplicate_header_mode != DuplicateResponseHeaderMode::Separate {
+        cel_expr.push_str(",duplicate_header_mode:");
+        push_cel_string(cel_expr, options.duplicate_header_mode.name());
+    }
+    if options.header_value_mode != ResponseHeaderValueMode::Raw {
+        cel_expr.push_str(",header_value_mode:");
+        push_cel_string(cel_expr, options.header_value_mode.name());
+    }
+
+    cel_expr.push('}');
+}
+
+fn push_cel_string_list(cel_expr: &mut String, values: &[impl AsRef<str>]) {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            cel_expr.push(',');
+        }
+        push_cel_string(cel_expr, value.as_ref());
     }
-    cel_expr.push_str("]}}");
 }
 
 #[cfg(test)]
@@ -128,7 +263,6 @@ mod tests {
     use super::*;
     use crate::cel::fixtures::*;
     use rstest::*;
-    use std::borrow::Cow;
 
     #[rstest]
     #[case::no_certification(no_certification(), no_certification_cel())]
@@ -192,6 +326,7 @@ mod tests {
                     "Content-Type",
                     "Content-Encoding",
                 ]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
             },
         ))
     }
@@ -204,6 +339,7 @@ mod tests {
                     "Cookie",
                     "Set-Cookie",
                 ]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
             },
         ))
     }
@@ -212,6 +348,7 @@ mod tests {
         CelExpression::Default(DefaultCelExpression::ResponseOnly(
             DefaultResponseOnlyCelExpression {
                 response: DefaultResponseCertification::certified_response_headers(&[]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
             },
         ))
     }
@@ -220,16 +357,17 @@ mod tests {
         CelExpression::Default(DefaultCelExpression::ResponseOnly(
             DefaultResponseOnlyCelExpression {
                 response: DefaultResponseCertification::response_header_exclusions(&[]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
             },
         ))
     }
 
     fn include_request_response_header_inclusions() -> CelExpression<'static> {
         CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
-            request: DefaultRequestCertification {
-                headers: Cow::Borrowed(&["Accept", "Accept-Encoding", "If-Match"]),
-                query_parameters: Cow::Borrowed(&["foo", "bar", "baz"]),
-            },
+            request: DefaultRequestCertification::new(
+                ["Accept", "Accept-Encoding", "If-Match"],
+                ["foo", "bar", "baz"],
+            ),
             response: DefaultResponseCertification::certified_response_headers(&[
                 "Cache-Control",
                 "ETag",
@@ -237,60 +375,60 @@ mod tests {
                 "Content-Type",
                 "Content-Encoding",
             ]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
         }))
     }
 
     fn include_request_response_header_exclusions() -> CelExpression<'static> {
         CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
-            request: DefaultRequestCertification {
-                headers: Cow::Borrowed(&["Accept", "Accept-Encoding", "If-Match"]),
-                query_parameters: Cow::Borrowed(&["foo", "bar", "baz"]),
-            },
+            request: DefaultRequestCertification::new(
+                ["Accept", "Accept-Encoding", "If-Match"],
+                ["foo", "bar", "baz"],
+            ),
             response: DefaultResponseCertification::response_header_exclusions(&[
                 "Date",
                 "Cookie",
                 "Set-Cookie",
             ]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
         }))
     }
 
     fn include_request_empty_response_inclusions() -> CelExpression<'static> {
         CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
-            request: DefaultRequestCertification {
-                headers: Cow::Borrowed(&["Accept", "Accept-Encoding", "If-Match"]),
-                query_parameters: Cow::Borrowed(&["foo", "bar", "baz"]),
-            },
+            request: DefaultRequestCertification::new(
+                ["Accept", "Accept-Encoding", "If-Match"],
+                ["foo", "bar", "baz"],
+            ),
             response: DefaultResponseCertification::certified_response_headers(&[]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
         }))
     }
 
     fn include_request_empty_response_exclusions() -> CelExpression<'static> {
         CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
-            request: DefaultRequestCertification {
-                headers: Cow::Borrowed(&["Accept", "Accept-Encoding", "If-Match"]),
-                query_parameters: Cow::Borrowed(&["foo", "bar", "baz"]),
-            },
+            request: DefaultRequestCertification::new(
+                ["Accept", "Accept-Encoding", "If-Match"],
+                ["foo", "bar", "baz"],
+            ),
             response: DefaultResponseCertification::response_header_exclusions(&[]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
         }))
     }
 
     fn empty_request_response_inclusions() -> CelExpression<'static> {
         CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
-            request: DefaultRequestCertification {
-                headers: Cow::Borrowed(&[]),
-                query_parameters: Cow::Borrowed(&[]),
-            },
+            request: DefaultRequestCertification::new(Vec::<&str>::new(), Vec::<&str>::new()),
             response: DefaultResponseCertification::certified_response_headers(&[]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
         }))
     }
 
     fn empty_request_response_exclusions() -> CelExpression<'static> {
         CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
-            request: DefaultRequestCertification {
-                headers: Cow::Borrowed(&[]),
-                query_parameters: Cow::Borrowed(&[]),
-            },
+            request: DefaultRequestCertification::new(Vec::<&str>::new(), Vec::<&str>::new()),
             response: DefaultResponseCertification::response_header_exclusions(&[]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
         }))
     }
 }
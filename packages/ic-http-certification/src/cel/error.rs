@@ -1,4 +1,5 @@
-pub(crate) type CelParserResult<T = ()> = Result<T, CelParserError>;
+/// CEL expression parsing result type.
+pub type CelParserResult<T = ()> = Result<T, CelParserError>;
 
 /// CEL expression parsing error.
 #[derive(thiserror::Error, Debug)]
@@ -74,6 +75,30 @@ pub enum CelParserError {
     #[error(r#"The request_certification object must specify at least one of the no_request_certification or request_certification properties"#)]
     MissingRequestCertificationProperty,
 
+    /// The CEL parser encountered an unsupported query parameter certification mode.
+    #[error(r#""{0}" is not a supported query parameter mode, expected "raw" or "canonical""#)]
+    UnrecognizedQueryParameterMode(String),
+
+    /// The CEL parser encountered an unsupported request body certification mode.
+    #[error(
+        r#""{0}" is not a supported request body mode, expected "raw", "skip" or "canonical_json""#
+    )]
+    UnrecognizedRequestBodyMode(String),
+
+    /// The CEL parser encountered an unsupported duplicate response header mode.
+    #[error(
+        r#""{0}" is not a supported duplicate header mode, expected "separate", "fold" or "array""#
+    )]
+    UnrecognizedDuplicateHeaderMode(String),
+
+    /// The CEL parser encountered an unsupported response header value mode.
+    #[error(r#""{0}" is not a supported header value mode, expected "raw" or "trim""#)]
+    UnrecognizedHeaderValueMode(String),
+
+    /// The CEL parser encountered a header name pattern with a `*` wildcard that is not the last character.
+    #[error(r#""{0}" is not a valid header name pattern, a "*" wildcard is only allowed as the last character"#)]
+    InvalidHeaderNamePattern(String),
+
     /// The CEL parser encountered an extraneous property on the response certification's CEL object.
     #[error(r#"The response_certification object must only specify one of the certified_response_headers or response_header_exclusions properties, not both"#)]
     ExtraneousResponseCertificationProperty,
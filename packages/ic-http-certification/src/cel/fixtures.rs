@@ -261,6 +261,84 @@ pub(super) fn empty_request_response_exclusions_cel() -> String {
     )
 }
 
+#[fixture]
+pub(super) fn include_request_canonical_query_response_inclusions_cel() -> String {
+    remove_whitespace(
+        r#"default_certification(
+            ValidationArgs {
+                certification: Certification {
+                    request_certification: RequestCertification {
+                        certified_request_headers: [
+                            "Accept"
+                        ],
+                        certified_query_parameters: [
+                            "foo",
+                            "bar"
+                        ],
+                        query_parameter_mode: "canonical"
+                    },
+                    response_certification: ResponseCertification {
+                        certified_response_headers: ResponseHeaderList {
+                            headers: [
+                                "ETag"
+                            ]
+                        }
+                    }
+                }
+            }
+        )"#,
+    )
+}
+
+#[fixture]
+pub(super) fn include_request_canonical_json_body_response_inclusions_cel() -> String {
+    remove_whitespace(
+        r#"default_certification(
+            ValidationArgs {
+                certification: Certification {
+                    request_certification: RequestCertification {
+                        certified_request_headers: [
+                            "Content-Type"
+                        ],
+                        certified_query_parameters: [],
+                        body_mode: "canonical_json"
+                    },
+                    response_certification: ResponseCertification {
+                        certified_response_headers: ResponseHeaderList {
+                            headers: [
+                                "ETag"
+                            ]
+                        }
+                    }
+                }
+            }
+        )"#,
+    )
+}
+
+#[fixture]
+pub(super) fn skip_request_response_inclusions_with_options_cel() -> String {
+    remove_whitespace(
+        r#"default_certification(
+            ValidationArgs {
+                certification: Certification {
+                    no_request_certification: Empty {},
+                    response_certification: ResponseCertification {
+                        certified_response_headers: ResponseHeaderList {
+                            headers: [
+                                "Cache-Control",
+                                "Set-Cookie"
+                            ]
+                        },
+                        duplicate_header_mode: "array",
+                        header_value_mode: "trim"
+                    }
+                }
+            }
+        )"#,
+    )
+}
+
 fn remove_whitespace(s: &str) -> String {
     s.chars().filter(|c| !c.is_whitespace()).collect()
 }
@@ -0,0 +1,64 @@
+const WILDCARD: char = '*';
+
+/// Checks if a header name matches a header name pattern from a CEL expression.
+///
+/// Header names are matched case-insensitively. A pattern that ends with a `*` wildcard matches
+/// every header name that starts with the rest of the pattern, so `x-app-*` matches `X-App-Version`
+/// and `*` matches every header name. Any other pattern must match the header name exactly.
+pub fn header_name_matches(pattern: &str, header_name: &str) -> bool {
+    match pattern.strip_suffix(WILDCARD) {
+        Some(prefix) => {
+            header_name
+                .as_bytes()
+                .get(..prefix.len())
+                .is_some_and(|header_name_prefix| {
+                    header_name_prefix.eq_ignore_ascii_case(prefix.as_bytes())
+                })
+        }
+        None => pattern.eq_ignore_ascii_case(header_name),
+    }
+}
+
+/// Checks if a header name pattern is valid.
+///
+/// A `*` wildcard is only allowed as the last character of a pattern.
+pub(crate) fn is_valid_header_name_pattern(pattern: &str) -> bool {
+    let pattern = pattern.strip_suffix(WILDCARD).unwrap_or(pattern);
+
+    !pattern.contains(WILDCARD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::exact("Cache-Control", "Cache-Control", true)]
+    #[case::exact_case_insensitive("cache-control", "CACHE-CONTROL", true)]
+    #[case::exact_mismatch("Cache-Control", "Cache-Controls", false)]
+    #[case::prefix("x-app-*", "x-app-version", true)]
+    #[case::prefix_case_insensitive("X-APP-*", "x-App-Version", true)]
+    #[case::prefix_without_suffix("x-app-*", "x-app-", true)]
+    #[case::prefix_mismatch("x-app-*", "x-amz-date", false)]
+    #[case::prefix_longer_than_name("x-app-*", "x-app", false)]
+    #[case::wildcard("*", "Content-Type", true)]
+    fn matches_header_name(
+        #[case] pattern: &str,
+        #[case] header_name: &str,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(header_name_matches(pattern, header_name), expected);
+    }
+
+    #[rstest]
+    #[case::exact("Cache-Control", true)]
+    #[case::prefix("x-app-*", true)]
+    #[case::wildcard("*", true)]
+    #[case::leading_wildcard("*-app", false)]
+    #[case::inner_wildcard("x-*-version", false)]
+    #[case::double_wildcard("x-app-**", false)]
+    fn validates_header_name_pattern(#[case] pattern: &str, #[case] expected: bool) {
+        assert_eq!(is_valid_header_name_pattern(pattern), expected);
+    }
+}
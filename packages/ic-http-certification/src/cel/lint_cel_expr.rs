@@ -0,0 +1,199 @@
+use super::{parse_cel_expr, CelParserError};
+use ic_certification::Hash;
+use ic_representation_independent_hash::hash;
+
+/// A warning about the `IC-CertificateExpression` header that a canister serves with a response,
+/// reported by [lint_cel_expr](lint_cel_expr()).
+#[derive(Debug)]
+pub enum CelExpressionLint {
+    /// The served CEL expression could not be parsed, so verification of the response will fail.
+    InvalidExpression(CelParserError),
+
+    /// The hash of the served CEL expression does not match the certified CEL expression hash,
+    /// so verification of the response will fail.
+    ///
+    /// Verifiers hash the literal bytes of the served header, so this is also reported if the served
+    /// expression is equivalent to the certified expression, but formatted differently.
+    HashMismatch {
+        /// The [String] representation of an expression that is equivalent to the served expression
+        /// and does match the certified CEL expression hash, if there is one.
+        /// Serving this expression instead of the current one will fix verification.
+        certified_cel_expr: Option<String>,
+    },
+
+    /// The certified CEL expression hash does not match the canonical form of the served CEL expression.
+    ///
+    /// Certifying the canonical form of an expression, and serving it exactly as it was certified,
+    /// ensures that differences in whitespace, header name case or header order cannot break verification.
+    /// See [canonicalize_cel_expr](super::canonicalize_cel_expr()) for details on the canonical form.
+    NonCanonicalExpression {
+        /// The [String] representation of the canonical form of the served expression.
+        canonical_cel_expr: String,
+    },
+}
+
+/// Checks the CEL expression that a canister serves in the `IC-CertificateExpression` header of a response
+/// against the CEL expression hash that was certified for that response, and returns a list of warnings.
+///
+/// The served CEL expression is expected to hash to the certified CEL expression hash, which should
+/// be the hash of the canonical form of the served expression. An empty list is returned if this is the case.
+/// The certified CEL expression hash of an [HttpCertification](crate::HttpCertification) can be retrieved
+/// with [cel_expr_hash](crate::HttpCertification::cel_expr_hash()).
+pub fn lint_cel_expr(
+    served_cel_expr: &str,
+    certified_cel_expr_hash: &Hash,
+) -> Vec<CelExpressionLint> {
+    let mut lints = vec![];
+    let served_hash_matches = hash(served_cel_expr.as_bytes()) == *certified_cel_expr_hash;
+
+    let cel_expr = match parse_cel_expr(served_cel_expr) {
+        Ok(cel_expr) => cel_expr,
+        Err(err) => {
+            lints.push(CelExpressionLint::InvalidExpression(err));
+            if !served_hash_matches {
+                lints.push(CelExpressionLint::HashMismatch {
+                    certified_cel_expr: None,
+                });
+            }
+
+            return lints;
+        }
+    };
+
+    let canonical_cel_expr = cel_expr.canonicalize().to_string();
+    let canonical_hash_matches = hash(canonical_cel_expr.as_bytes()) == *certified_cel_expr_hash;
+
+    if !served_hash_matches {
+        let formatted_cel_expr = cel_expr.to_string();
+        let certified_cel_expr = if hash(formatted_cel_expr.as_bytes()) == *certified_cel_expr_hash
+        {
+            Some(formatted_cel_expr)
+        } else if canonical_hash_matches {
+            Some(canonical_cel_expr.clone())
+        } else {
+            None
+        };
+
+        lints.push(CelExpressionLint::HashMismatch { certified_cel_expr });
+    }
+
+    if !canonical_hash_matches {
+        lints.push(CelExpressionLint::NonCanonicalExpression { canonical_cel_expr });
+    }
+
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cel::{CelExpression, DefaultCelExpression},
+        DefaultCelBuilder, DefaultResponseCertification,
+    };
+    use rstest::*;
+
+    #[fixture]
+    fn cel_expr() -> CelExpression<'static> {
+        CelExpression::Default(DefaultCelExpression::ResponseOnly(
+            DefaultCelBuilder::response_only_certification()
+                .with_response_certification(
+                    DefaultResponseCertification::certified_response_headers(&[
+                        "ETag",
+                        "Cache-Control",
+                    ]),
+                )
+                .build(),
+        ))
+        .into_owned()
+    }
+
+    #[rstest]
+    fn lint_canonical_cel_expr(cel_expr: CelExpression<'static>) {
+        let canonical_cel_expr = cel_expr.canonicalize().to_string();
+        let certified_cel_expr_hash = hash(canonical_cel_expr.as_bytes());
+
+        let result = lint_cel_expr(&canonical_cel_expr, &certified_cel_expr_hash);
+
+        assert!(result.is_empty());
+    }
+
+    #[rstest]
+    fn lint_non_canonical_cel_expr(cel_expr: CelExpression<'static>) {
+        let served_cel_expr = cel_expr.to_string();
+        let certified_cel_expr_hash = hash(served_cel_expr.as_bytes());
+
+        let result = lint_cel_expr(&served_cel_expr, &certified_cel_expr_hash);
+
+        assert!(matches!(
+            result.as_slice(),
+            [CelExpressionLint::NonCanonicalExpression { canonical_cel_expr }]
+                if *canonical_cel_expr == cel_expr.canonicalize().to_string()
+        ));
+    }
+
+    #[rstest]
+    fn lint_reformatted_cel_expr(cel_expr: CelExpression<'static>) {
+        let canonical_cel_expr = cel_expr.canonicalize().to_string();
+        let served_cel_expr = canonical_cel_expr.replace(',', ", ");
+        let certified_cel_expr_hash = hash(canonical_cel_expr.as_bytes());
+
+        let result = lint_cel_expr(&served_cel_expr, &certified_cel_expr_hash);
+
+        assert!(matches!(
+            result.as_slice(),
+            [CelExpressionLint::HashMismatch { certified_cel_expr: Some(certified_cel_expr) }]
+                if *certified_cel_expr == canonical_cel_expr
+        ));
+    }
+
+    #[rstest]
+    fn lint_reordered_cel_expr(cel_expr: CelExpression<'static>) {
+        let canonical_cel_expr = cel_expr.canonicalize().to_string();
+        let served_cel_expr = cel_expr.to_string();
+        let certified_cel_expr_hash = hash(canonical_cel_expr.as_bytes());
+
+        let result = lint_cel_expr(&served_cel_expr, &certified_cel_expr_hash);
+
+        assert!(matches!(
+            result.as_slice(),
+            [CelExpressionLint::HashMismatch { certified_cel_expr: Some(certified_cel_expr) }]
+                if *certified_cel_expr == canonical_cel_expr
+        ));
+    }
+
+    #[rstest]
+    fn lint_different_cel_expr(cel_expr: CelExpression<'static>) {
+        let served_cel_expr = cel_expr.canonicalize().to_string();
+        let certified_cel_expr_hash = hash(
+            CelExpression::Default(DefaultCelExpression::Skip)
+                .to_string()
+                .as_bytes(),
+        );
+
+        let result = lint_cel_expr(&served_cel_expr, &certified_cel_expr_hash);
+
+        assert!(matches!(
+            result.as_slice(),
+            [
+                CelExpressionLint::HashMismatch {
+                    certified_cel_expr: None
+                },
+                CelExpressionLint::NonCanonicalExpression { .. }
+            ]
+        ));
+    }
+
+    #[rstest]
+    fn lint_invalid_cel_expr() {
+        let served_cel_expr = "default_certification(";
+        let certified_cel_expr_hash = hash(served_cel_expr.as_bytes());
+
+        let result = lint_cel_expr(served_cel_expr, &certified_cel_expr_hash);
+
+        assert!(matches!(
+            result.as_slice(),
+            [CelExpressionLint::InvalidExpression(_)]
+        ));
+    }
+}
@@ -1,5 +1,6 @@
 //! The CEL module contains functions and builders for creating CEL expression
-//! definitions and converting them into their `String` representation.
+//! definitions, converting them into their `String` representation and parsing them
+//! back from their `String` representation.
 
 mod cel_builder;
 pub use cel_builder::*;
@@ -7,8 +8,32 @@ pub use cel_builder::*;
 mod cel_types;
 pub use cel_types::*;
 
+mod cel_condition;
+pub use cel_condition::*;
+
 mod create_cel_expr;
 pub use create_cel_expr::*;
 
+mod error;
+pub use error::*;
+
+mod header_name_pattern;
+pub use header_name_pattern::*;
+
+mod ast_mapping;
+mod parser;
+
+mod parse_cel_expr;
+pub use parse_cel_expr::*;
+
+mod canonical_cel_expr;
+pub use canonical_cel_expr::*;
+
+mod lint_cel_expr;
+pub use lint_cel_expr::*;
+
 #[cfg(test)]
 mod fixtures;
+
+#[cfg(test)]
+mod tests;
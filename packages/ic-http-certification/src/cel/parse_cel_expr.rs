@@ -0,0 +1,18 @@
+use super::{
+    ast_mapping::map_cel_ast, parser::parse_cel_expression, CelExpression, CelParserResult,
+};
+
+/// Parses a CEL expression from it's [String] representation into a [CelExpression].
+///
+/// The parsed expression does not borrow from the input, so it can be stored in a canister's state.
+/// Parsing the [String] representation of an expression, as produced by [create_cel_expr](super::create_cel_expr()),
+/// always results in an expression that is equal to the original one. Whitespace between tokens is ignored,
+/// so the [String] representation of the parsed expression may differ from the input.
+///
+/// [CelExpression::parse](CelExpression::parse()) is an alias of this function and can be used
+/// for ergonomics.
+pub fn parse_cel_expr(cel_expr: &str) -> CelParserResult<CelExpression<'static>> {
+    let cel_ast = parse_cel_expression(cel_expr)?;
+
+    map_cel_ast(&cel_ast).map(CelExpression::into_owned)
+}
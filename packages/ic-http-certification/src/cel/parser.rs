@@ -0,0 +1,443 @@
+use super::{
+    CelAttribute, CelBinaryOperator, CelCondition, CelFunction, CelParserError, CelParserResult,
+};
+use nom::branch::alt;
+use nom::bytes::complete::{escaped_transform, is_not, tag, take_while, take_while1};
+use nom::character::complete::{char, digit1, multispace0, satisfy};
+use nom::character::is_alphanumeric;
+use nom::combinator::{all_consuming, cut, map, map_opt, map_res, not, opt, recognize, value};
+use nom::error::{context, ContextError, FromExternalError, ParseError};
+use nom::multi::{fold_many0, separated_list0};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use nom::{IResult, Parser};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::num::ParseIntError;
+
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum CelValue<'a> {
+    String(Cow<'a, str>),
+    Array(Vec<CelValue<'a>>),
+    Object(&'a str, HashMap<&'a str, CelValue<'a>>),
+    Function(&'a str, Vec<CelValue<'a>>),
+    Conditional(CelCondition<'a>, Box<CelValue<'a>>, Box<CelValue<'a>>),
+}
+
+impl<'a> fmt::Display for CelValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+fn trim_whitespace<'a, O, P, E: ParseError<&'a str> + ContextError<&'a str>>(
+    parser: P,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
+where
+    P: Parser<&'a str, O, E>,
+{
+    context("trim_whitespace", preceded(multispace0, parser))
+}
+
+fn drop_separators<'a, O, P, E: ParseError<&'a str> + ContextError<&'a str>>(
+    opening_separator: char,
+    closing_separator: char,
+    parser: P,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
+where
+    P: Parser<&'a str, O, E>,
+{
+    context(
+        "drop_separators",
+        preceded(
+            trim_whitespace(char(opening_separator)),
+            cut(terminated(parser, trim_whitespace(char(closing_separator)))),
+        ),
+    )
+}
+
+fn ident<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    let acceptable_special_chars = "_";
+
+    context(
+        "parse_ident",
+        take_while(move |e| acceptable_special_chars.contains(e) || is_alphanumeric(e as u8)),
+    )(i)
+}
+
+fn parse_str<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Cow<'a, str>, E> {
+    context(
+        "parse_str",
+        map(
+            opt(escaped_transform(
+                is_not("\"\\"),
+                '\\',
+                alt((
+                    value("\\", char('\\')),
+                    value("\"", char('"')),
+                    value("\n", char('n')),
+                )),
+            )),
+            |value| Cow::Owned(value.unwrap_or_default()),
+        ),
+    )(i)
+}
+
+fn string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Cow<'a, str>, E> {
+    context("string", drop_separators('\"', '\"', parse_str))(i)
+}
+
+fn array<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Vec<CelValue<'a>>, E> {
+    context(
+        "array",
+        drop_separators(
+            '[',
+            ']',
+            separated_list0(trim_whitespace(char(',')), cel_value),
+        ),
+    )(i)
+}
+
+fn key_value<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, (&'a str, CelValue<'a>), E> {
+    context(
+        "key_value",
+        separated_pair(
+            trim_whitespace(ident),
+            trim_whitespace(char(':')),
+            cel_value,
+        ),
+    )(i)
+}
+
+fn object<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, (&'a str, HashMap<&'a str, CelValue<'a>>), E> {
+    context(
+        "object",
+        tuple((
+            ident,
+            drop_separators(
+                '{',
+                '}',
+                map(
+                    separated_list0(trim_whitespace(char(',')), key_value),
+                    |tuple_vec| tuple_vec.into_iter().collect(),
+                ),
+            ),
+        )),
+    )(i)
+}
+
+fn function<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, (&'a str, Vec<CelValue<'a>>), E> {
+    context(
+        "function",
+        tuple((
+            ident,
+            drop_separators(
+                '(',
+                ')',
+                map(
+                    separated_list0(trim_whitespace(char(',')), cel_value),
+                    |tuple_vec| tuple_vec.into_iter().collect(),
+                ),
+            ),
+        )),
+    )(i)
+}
+
+fn cel_value<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, CelValue<'a>, E> {
+    context(
+        "cel_value",
+        trim_whitespace(alt((
+            map(object, |(name, value)| CelValue::Object(name, value)),
+            map(function, |(name, value)| CelValue::Function(name, value)),
+            map(array, CelValue::Array),
+            map(string, CelValue::String),
+        ))),
+    )(i)
+}
+
+fn keyword<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    keyword: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str, E> {
+    terminated(
+        tag(keyword),
+        not(satisfy(|c: char| c.is_alphanumeric() || c == '_')),
+    )
+}
+
+fn condition_ident<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    take_while1(|c: char| c.is_alphanumeric() || c == '_')(i)
+}
+
+fn condition_int<
+    'a,
+    E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+>(
+    i: &'a str,
+) -> IResult<&'a str, i64, E> {
+    context(
+        "condition_int",
+        map_res(recognize(pair(opt(char('-')), digit1)), str::parse),
+    )(i)
+}
+
+fn condition_attribute<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, CelAttribute, E> {
+    context(
+        "condition_attribute",
+        map_opt(
+            recognize(tuple((
+                alt((keyword("request"), keyword("response"))),
+                char('.'),
+                condition_ident,
+            ))),
+            CelAttribute::from_name,
+        ),
+    )(i)
+}
+
+fn condition_args<
+    'a,
+    E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+>(
+    i: &'a str,
+) -> IResult<&'a str, Vec<CelCondition<'a>>, E> {
+    delimited(
+        trim_whitespace(char('(')),
+        separated_list0(trim_whitespace(char(',')), condition),
+        trim_whitespace(char(')')),
+    )(i)
+}
+
+fn condition_primary<
+    'a,
+    E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+>(
+    i: &'a str,
+) -> IResult<&'a str, CelCondition<'a>, E> {
+    let global_function = map_opt(condition_ident, CelFunction::from_name);
+
+    context(
+        "condition_primary",
+        trim_whitespace(alt((
+            delimited(char('('), condition, trim_whitespace(char(')'))),
+            map(
+                delimited(
+                    char('['),
+                    separated_list0(trim_whitespace(char(',')), condition),
+                    trim_whitespace(char(']')),
+                ),
+                CelCondition::List,
+            ),
+            map(string, CelCondition::String),
+            map(condition_int, CelCondition::Int),
+            value(CelCondition::Bool(true), keyword("true")),
+            value(CelCondition::Bool(false), keyword("false")),
+            value(CelCondition::Null, keyword("null")),
+            map(condition_attribute, CelCondition::Attribute),
+            map(pair(global_function, condition_args), |(function, args)| {
+                CelCondition::Call(function, args)
+            }),
+        ))),
+    )(i)
+}
+
+fn condition_postfix<
+    'a,
+    E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+>(
+    i: &'a str,
+) -> IResult<&'a str, CelCondition<'a>, E> {
+    enum Postfix<'a> {
+        Index(CelCondition<'a>),
+        Method(CelFunction, Vec<CelCondition<'a>>),
+    }
+
+    let (i, target) = condition_primary(i)?;
+    let method = map_opt(condition_ident, CelFunction::from_name);
+
+    context(
+        "condition_postfix",
+        fold_many0(
+            alt((
+                map(
+                    delimited(
+                        trim_whitespace(char('[')),
+                        condition,
+                        trim_whitespace(char(']')),
+                    ),
+                    Postfix::Index,
+                ),
+                map(
+                    preceded(
+                        trim_whitespace(char('.')),
+                        pair(trim_whitespace(method), condition_args),
+                    ),
+                    |(function, args)| Postfix::Method(function, args),
+                ),
+            )),
+            move || target.clone(),
+            |target, postfix| match postfix {
+                Postfix::Index(index) => CelCondition::Index(Box::new(target), Box::new(index)),
+                Postfix::Method(function, args) => {
+                    CelCondition::Call(function, [vec![target], args].concat())
+                }
+            },
+        ),
+    )(i)
+}
+
+fn condition_unary<
+    'a,
+    E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+>(
+    i: &'a str,
+) -> IResult<&'a str, CelCondition<'a>, E> {
+    context(
+        "condition_unary",
+        alt((
+            map(
+                preceded(trim_whitespace(char('!')), condition_unary),
+                |value| CelCondition::Not(Box::new(value)),
+            ),
+            condition_postfix,
+        )),
+    )(i)
+}
+
+fn condition_relation<
+    'a,
+    E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+>(
+    i: &'a str,
+) -> IResult<&'a str, CelCondition<'a>, E> {
+    let operator = trim_whitespace(alt((
+        value(CelBinaryOperator::Eq, tag("==")),
+        value(CelBinaryOperator::Ne, tag("!=")),
+        value(CelBinaryOperator::Le, tag("<=")),
+        value(CelBinaryOperator::Ge, tag(">=")),
+        value(CelBinaryOperator::Lt, tag("<")),
+        value(CelBinaryOperator::Gt, tag(">")),
+        value(CelBinaryOperator::In, keyword("in")),
+    )));
+
+    context(
+        "condition_relation",
+        map(
+            pair(condition_unary, opt(pair(operator, condition_unary))),
+            |(lhs, rhs)| match rhs {
+                Some((operator, rhs)) => CelCondition::binary(operator, lhs, rhs),
+                None => lhs,
+            },
+        ),
+    )(i)
+}
+
+fn condition_binary_chain<
+    'a,
+    E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+>(
+    operator: CelBinaryOperator,
+    mut operand: impl FnMut(&'a str) -> IResult<&'a str, CelCondition<'a>, E>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, CelCondition<'a>, E> {
+    move |i| {
+        let (i, first) = operand(i)?;
+
+        fold_many0(
+            preceded(trim_whitespace(tag(operator.symbol())), &mut operand),
+            move || first.clone(),
+            move |lhs, rhs| CelCondition::binary(operator, lhs, rhs),
+        )(i)
+    }
+}
+
+fn condition<
+    'a,
+    E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+>(
+    i: &'a str,
+) -> IResult<&'a str, CelCondition<'a>, E> {
+    context(
+        "condition",
+        condition_binary_chain(
+            CelBinaryOperator::Or,
+            condition_binary_chain(CelBinaryOperator::And, condition_relation),
+        ),
+    )(i)
+}
+
+fn conditional<
+    'a,
+    E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+>(
+    i: &'a str,
+) -> IResult<&'a str, CelValue<'a>, E> {
+    context(
+        "conditional",
+        map(
+            tuple((
+                condition,
+                preceded(trim_whitespace(char('?')), cut(cel_expression)),
+                preceded(trim_whitespace(char(':')), cut(cel_expression)),
+            )),
+            |(condition, if_true, if_false)| {
+                CelValue::Conditional(condition, Box::new(if_true), Box::new(if_false))
+            },
+        ),
+    )(i)
+}
+
+fn cel_expression<
+    'a,
+    E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+>(
+    i: &'a str,
+) -> IResult<&'a str, CelValue<'a>, E> {
+    context(
+        "cel_expression",
+        trim_whitespace(alt((conditional, cel_value))),
+    )(i)
+}
+
+pub(crate) fn parse_cel_expression(i: &str) -> CelParserResult<CelValue> {
+    #[cfg(feature = "debug")]
+    let result = all_consuming::<_, _, nom::error::VerboseError<&str>, _>(terminated(
+        cel_expression,
+        multispace0,
+    ))(i);
+
+    #[cfg(not(feature = "debug"))]
+    let result = all_consuming::<_, _, nom::error::Error<&str>, _>(terminated(
+        cel_expression,
+        multispace0,
+    ))(i);
+
+    match result {
+        #[cfg(feature = "debug")]
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+            let stacktrace = nom::error::convert_error(i, e);
+
+            Err(CelParserError::CelSyntaxException(stacktrace))
+        }
+        Err(e) => Err(CelParserError::CelSyntaxException(e.to_string())),
+        Ok((_remaining, result)) => Ok(result),
+    }
+}
@@ -0,0 +1,576 @@
+use super::{ast_mapping::map_cel_ast, parser::parse_cel_expression};
+use crate::{
+    cel::{
+        CelAttribute, CelBinaryOperator, CelCondition, CelExpression, CelFunction, CelParserError,
+        ConditionalCelExpression, DefaultCelExpression, DefaultFullCelExpression,
+        DefaultRequestCertification, DefaultResponseOnlyCelExpression, DuplicateResponseHeaderMode,
+        QueryParameterCertificationMode, RequestBodyCertificationMode,
+        ResponseHeaderCertificationOptions, ResponseHeaderValueMode,
+    },
+    DefaultResponseCertification,
+};
+
+fn remove_whitespace(s: &str) -> String {
+    s.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[test]
+fn parses_no_certification_expression() {
+    let cel_expression = r#"
+        default_certification (
+            ValidationArgs {
+                no_certification: Empty { }
+            }
+        )
+    "#
+    .to_string();
+    let expected_result = CelExpression::Default(DefaultCelExpression::Skip);
+
+    let parsed_cel_expr = parse_cel_expression(&cel_expression).unwrap();
+    let result = map_cel_ast(&parsed_cel_expr).unwrap();
+
+    let minified_cel_expression = remove_whitespace(&cel_expression);
+    let parsed_min_cel_expr = parse_cel_expression(&minified_cel_expression).unwrap();
+    let minified_result = map_cel_ast(&parsed_min_cel_expr).unwrap();
+
+    assert_eq!(&result, &expected_result);
+    assert_eq!(&minified_result, &expected_result);
+}
+
+#[test]
+fn parses_no_request_certification_expression() {
+    let cel_expression = r#"
+        default_certification (
+            ValidationArgs {
+                certification: Certification {
+                    no_request_certification: Empty {},
+                    response_certification: ResponseCertification {
+                        response_header_exclusions: ResponseHeaderList {
+                            headers: ["Server","Date","X-Cache-Status"]
+                        }
+                    }
+                }
+            }
+        )
+    "#
+    .to_string();
+    let expected_result = CelExpression::Default(DefaultCelExpression::ResponseOnly(
+        DefaultResponseOnlyCelExpression {
+            response: DefaultResponseCertification::response_header_exclusions(&[
+                "Server",
+                "Date",
+                "X-Cache-Status",
+            ]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        },
+    ));
+
+    let parsed_cel_expr = parse_cel_expression(&cel_expression).unwrap();
+    let result = map_cel_ast(&parsed_cel_expr).unwrap();
+
+    let minified_cel_expression = remove_whitespace(&cel_expression);
+    let parsed_min_cel_expr = parse_cel_expression(&minified_cel_expression).unwrap();
+    let minified_result = map_cel_ast(&parsed_min_cel_expr).unwrap();
+
+    assert_eq!(&result, &expected_result);
+    assert_eq!(&minified_result, &expected_result);
+}
+
+#[test]
+fn parses_full_certification_expression() {
+    let cel_expression = r#"
+        default_certification (
+            ValidationArgs {
+                certification: Certification {
+                    request_certification: RequestCertification {
+                        certified_request_headers: ["host"],
+                        certified_query_parameters: ["filter"]
+                    },
+                    response_certification: ResponseCertification {
+                        response_header_exclusions: ResponseHeaderList {
+                            headers: ["Content-Type","X-Frame-Options","Content-Security-Policy","Strict-Transport-Security","Referrer-Policy","Permissions-Policy"]
+                        }
+                    }
+                }
+            }
+        )
+    "#.to_string();
+    let expected_result =
+        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(["host"], ["filter"]),
+            response: DefaultResponseCertification::response_header_exclusions(&[
+                "Content-Type",
+                "X-Frame-Options",
+                "Content-Security-Policy",
+                "Strict-Transport-Security",
+                "Referrer-Policy",
+                "Permissions-Policy",
+            ]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        }));
+
+    let parsed_cel_expr = parse_cel_expression(&cel_expression).unwrap();
+    let result = map_cel_ast(&parsed_cel_expr).unwrap();
+
+    let minified_cel_expression = remove_whitespace(&cel_expression);
+    let parsed_min_cel_expr = parse_cel_expression(&minified_cel_expression).unwrap();
+    let minified_result = map_cel_ast(&parsed_min_cel_expr).unwrap();
+
+    assert_eq!(&result, &expected_result);
+    assert_eq!(&minified_result, &expected_result);
+}
+
+#[test]
+fn parses_conditional_expression() {
+    let cel_expression = r#"
+        request.method == "GET" && response.status_code == 200 ?
+            default_certification (
+                ValidationArgs {
+                    certification: Certification {
+                        no_request_certification: Empty {},
+                        response_certification: ResponseCertification {
+                            certified_response_headers: ResponseHeaderList {
+                                headers: ["Cache-Control"]
+                            }
+                        }
+                    }
+                }
+            ) :
+            default_certification (
+                ValidationArgs {
+                    certification: Certification {
+                        no_request_certification: Empty {},
+                        response_certification: ResponseCertification {
+                            certified_response_headers: ResponseHeaderList {
+                                headers: []
+                            }
+                        }
+                    }
+                }
+            )
+    "#
+    .to_string();
+    let expected_result = CelExpression::Conditional(ConditionalCelExpression {
+        condition: CelCondition::binary(
+            CelBinaryOperator::And,
+            CelCondition::binary(
+                CelBinaryOperator::Eq,
+                CelCondition::Attribute(CelAttribute::RequestMethod),
+                CelCondition::string("GET"),
+            ),
+            CelCondition::binary(
+                CelBinaryOperator::Eq,
+                CelCondition::Attribute(CelAttribute::ResponseStatusCode),
+                CelCondition::Int(200),
+            ),
+        ),
+        if_true: Box::new(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+            DefaultResponseOnlyCelExpression {
+                response: DefaultResponseCertification::certified_response_headers(&[
+                    "Cache-Control",
+                ]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            },
+        ))),
+        if_false: Box::new(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+            DefaultResponseOnlyCelExpression {
+                response: DefaultResponseCertification::certified_response_headers(&[]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            },
+        ))),
+    });
+
+    let parsed_cel_expr = parse_cel_expression(&cel_expression).unwrap();
+    let result = map_cel_ast(&parsed_cel_expr).unwrap();
+
+    let minified_cel_expression = remove_whitespace(&cel_expression);
+    let parsed_min_cel_expr = parse_cel_expression(&minified_cel_expression).unwrap();
+    let minified_result = map_cel_ast(&parsed_min_cel_expr).unwrap();
+
+    assert_eq!(&result, &expected_result);
+    assert_eq!(&minified_result, &expected_result);
+}
+
+#[test]
+fn parses_conditional_expression_string_representation() {
+    let skip = || Box::new(CelExpression::Default(DefaultCelExpression::Skip));
+    let full = || {
+        Box::new(CelExpression::Default(DefaultCelExpression::Full(
+            DefaultFullCelExpression {
+                request: DefaultRequestCertification::new(["Accept"], ["page"]),
+                response: DefaultResponseCertification::response_header_exclusions(&["Date"]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            },
+        )))
+    };
+    let conditions = [
+        CelCondition::Bool(true),
+        CelCondition::Not(Box::new(CelCondition::binary(
+            CelBinaryOperator::In,
+            CelCondition::string("accept-language"),
+            CelCondition::Attribute(CelAttribute::RequestHeaders),
+        ))),
+        CelCondition::binary(
+            CelBinaryOperator::Or,
+            CelCondition::binary(
+                CelBinaryOperator::And,
+                CelCondition::Call(
+                    CelFunction::StartsWith,
+                    vec![
+                        CelCondition::Attribute(CelAttribute::RequestPath),
+                        CelCondition::string("/api/\"v1\"\\"),
+                    ],
+                ),
+                CelCondition::binary(
+                    CelBinaryOperator::Ge,
+                    CelCondition::Call(
+                        CelFunction::Size,
+                        vec![CelCondition::Attribute(CelAttribute::RequestQuery)],
+                    ),
+                    CelCondition::Int(-1),
+                ),
+            ),
+            CelCondition::binary(
+                CelBinaryOperator::Ne,
+                CelCondition::Call(
+                    CelFunction::LowerAscii,
+                    vec![CelCondition::Index(
+                        Box::new(CelCondition::Attribute(CelAttribute::RequestHeaders)),
+                        Box::new(CelCondition::string("x-mode")),
+                    )],
+                ),
+                CelCondition::Null,
+            ),
+        ),
+        CelCondition::binary(
+            CelBinaryOperator::In,
+            CelCondition::Attribute(CelAttribute::RequestMethod),
+            CelCondition::List(vec![
+                CelCondition::string("GET"),
+                CelCondition::string("HEAD"),
+            ]),
+        ),
+    ];
+
+    for condition in conditions {
+        let expected_result = CelExpression::Conditional(ConditionalCelExpression {
+            condition: condition.clone(),
+            if_true: full(),
+            if_false: Box::new(CelExpression::Conditional(ConditionalCelExpression {
+                condition,
+                if_true: skip(),
+                if_false: full(),
+            })),
+        });
+        let result = CelExpression::parse(&expected_result.to_string()).unwrap();
+
+        assert_eq!(&result, &expected_result);
+    }
+}
+
+#[test]
+fn parses_default_expression_string_representation() {
+    let expressions = [
+        CelExpression::Default(DefaultCelExpression::Skip),
+        CelExpression::Default(DefaultCelExpression::ResponseOnly(
+            DefaultResponseOnlyCelExpression {
+                response: DefaultResponseCertification::certified_response_headers(&[]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            },
+        )),
+        CelExpression::Default(DefaultCelExpression::ResponseOnly(
+            DefaultResponseOnlyCelExpression {
+                response: DefaultResponseCertification::response_header_exclusions(&[
+                    "Date",
+                    "X_Custom.Header",
+                ]),
+                response_header_options: ResponseHeaderCertificationOptions::default(),
+            },
+        )),
+        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(
+                ["Accept", "If-None-Match"],
+                ["", "filter[name]", "quoted\"\\value"],
+            ),
+            response: DefaultResponseCertification::certified_response_headers(&["Cache-Control"]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(["Accept"], ["filter", "page"])
+                .with_query_parameter_mode(QueryParameterCertificationMode::Canonical),
+            response: DefaultResponseCertification::certified_response_headers(&[]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(Vec::<&str>::new(), Vec::<&str>::new())
+                .with_body_mode(RequestBodyCertificationMode::Skip),
+            response: DefaultResponseCertification::certified_response_headers(&[]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(Vec::<&str>::new(), ["filter"])
+                .with_query_parameter_mode(QueryParameterCertificationMode::Canonical)
+                .with_body_mode(RequestBodyCertificationMode::CanonicalJson),
+            response: DefaultResponseCertification::certified_response_headers(&[]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        })),
+    ];
+
+    for expected_result in expressions {
+        let cel_expression = expected_result.to_string();
+
+        let result = CelExpression::parse(&cel_expression).unwrap();
+        let from_str_result: CelExpression = cel_expression.parse().unwrap();
+
+        assert_eq!(&result, &expected_result);
+        assert_eq!(&from_str_result, &expected_result);
+        assert_eq!(result.to_string(), cel_expression);
+    }
+}
+
+#[test]
+fn parses_query_parameter_mode() {
+    let cel_expression = |query_parameter_mode: &str| {
+        format!(
+            r#"default_certification(ValidationArgs{{certification:Certification{{request_certification:RequestCertification{{certified_request_headers:[],certified_query_parameters:["filter"]{query_parameter_mode}}},response_certification:ResponseCertification{{certified_response_headers:ResponseHeaderList{{headers:[]}}}}}}}})"#
+        )
+    };
+    let expected_result = |query_parameter_mode| {
+        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(Vec::<&str>::new(), ["filter"])
+                .with_query_parameter_mode(query_parameter_mode),
+            response: DefaultResponseCertification::certified_response_headers(&[]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        }))
+    };
+
+    let result = CelExpression::parse(&cel_expression("")).unwrap();
+    assert_eq!(
+        result,
+        expected_result(QueryParameterCertificationMode::Raw)
+    );
+
+    let result = CelExpression::parse(&cel_expression(r#",query_parameter_mode:"raw""#)).unwrap();
+    assert_eq!(
+        result,
+        expected_result(QueryParameterCertificationMode::Raw)
+    );
+
+    let result =
+        CelExpression::parse(&cel_expression(r#",query_parameter_mode:"canonical""#)).unwrap();
+    assert_eq!(
+        result,
+        expected_result(QueryParameterCertificationMode::Canonical)
+    );
+
+    let result = CelExpression::parse(&cel_expression(r#",query_parameter_mode:"sorted""#));
+    assert!(matches!(
+        result,
+        Err(CelParserError::UnrecognizedQueryParameterMode(mode)) if mode == "sorted"
+    ));
+
+    let result = CelExpression::parse(&cel_expression(r#",query_parameter_mode:["canonical"]"#));
+    assert!(matches!(
+        result,
+        Err(CelParserError::UnexpectedNodeType { .. })
+    ));
+}
+
+#[test]
+fn parses_request_body_mode() {
+    let cel_expression = |body_mode: &str| {
+        format!(
+            r#"default_certification(ValidationArgs{{certification:Certification{{request_certification:RequestCertification{{certified_request_headers:[],certified_query_parameters:[]{body_mode}}},response_certification:ResponseCertification{{certified_response_headers:ResponseHeaderList{{headers:[]}}}}}}}})"#
+        )
+    };
+    let expected_result = |body_mode| {
+        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(Vec::<&str>::new(), Vec::<&str>::new())
+                .with_body_mode(body_mode),
+            response: DefaultResponseCertification::certified_response_headers(&[]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        }))
+    };
+
+    for (body_mode, expected_body_mode) in [
+        ("", RequestBodyCertificationMode::Raw),
+        (r#",body_mode:"raw""#, RequestBodyCertificationMode::Raw),
+        (r#",body_mode:"skip""#, RequestBodyCertificationMode::Skip),
+        (
+            r#",body_mode:"canonical_json""#,
+            RequestBodyCertificationMode::CanonicalJson,
+        ),
+    ] {
+        let result = CelExpression::parse(&cel_expression(body_mode)).unwrap();
+
+        assert_eq!(result, expected_result(expected_body_mode));
+    }
+
+    let result = CelExpression::parse(&cel_expression(r#",body_mode:"json""#));
+    assert!(matches!(
+        result,
+        Err(CelParserError::UnrecognizedRequestBodyMode(mode)) if mode == "json"
+    ));
+}
+
+#[test]
+fn parses_response_header_options() {
+    let cel_expression = |options: &str| {
+        format!(
+            r#"default_certification(ValidationArgs{{certification:Certification{{no_request_certification:Empty{{}},response_certification:ResponseCertification{{response_header_exclusions:ResponseHeaderList{{headers:["Date"]}}{options}}}}}}})"#
+        )
+    };
+    let expected_result = |duplicate_header_mode, header_value_mode| {
+        CelExpression::Default(DefaultCelExpression::ResponseOnly(
+            DefaultResponseOnlyCelExpression {
+                response: DefaultResponseCertification::response_header_exclusions(&["Date"]),
+                response_header_options: ResponseHeaderCertificationOptions::default()
+                    .with_duplicate_header_mode(duplicate_header_mode)
+                    .with_header_value_mode(header_value_mode),
+            },
+        ))
+    };
+
+    for (options, expected_duplicate_header_mode, expected_header_value_mode) in [
+        (
+            "",
+            DuplicateResponseHeaderMode::Separate,
+            ResponseHeaderValueMode::Raw,
+        ),
+        (
+            r#",duplicate_header_mode:"fold""#,
+            DuplicateResponseHeaderMode::Fold,
+            ResponseHeaderValueMode::Raw,
+        ),
+        (
+            r#",header_value_mode:"trim""#,
+            DuplicateResponseHeaderMode::Separate,
+            ResponseHeaderValueMode::Trim,
+        ),
+        (
+            r#",duplicate_header_mode:"array",header_value_mode:"trim""#,
+            DuplicateResponseHeaderMode::Array,
+            ResponseHeaderValueMode::Trim,
+        ),
+        (
+            r#",duplicate_header_mode:"separate",header_value_mode:"raw""#,
+            DuplicateResponseHeaderMode::Separate,
+            ResponseHeaderValueMode::Raw,
+        ),
+    ] {
+        let result = CelExpression::parse(&cel_expression(options)).unwrap();
+        let expected_result =
+            expected_result(expected_duplicate_header_mode, expected_header_value_mode);
+
+        assert_eq!(result, expected_result);
+        assert_eq!(
+            CelExpression::parse(&expected_result.to_string()).unwrap(),
+            expected_result
+        );
+    }
+
+    let result = CelExpression::parse(&cel_expression(r#",duplicate_header_mode:"merge""#));
+    assert!(matches!(
+        result,
+        Err(CelParserError::UnrecognizedDuplicateHeaderMode(mode)) if mode == "merge"
+    ));
+
+    let result = CelExpression::parse(&cel_expression(r#",header_value_mode:"lowercase""#));
+    assert!(matches!(
+        result,
+        Err(CelParserError::UnrecognizedHeaderValueMode(mode)) if mode == "lowercase"
+    ));
+}
+
+#[test]
+fn parses_header_name_patterns() {
+    let cel_expression = |request_headers: &str, response_headers: &str| {
+        format!(
+            r#"default_certification(ValidationArgs{{certification:Certification{{request_certification:RequestCertification{{certified_request_headers:[{request_headers}],certified_query_parameters:[]}},response_certification:ResponseCertification{{response_header_exclusions:ResponseHeaderList{{headers:[{response_headers}]}}}}}}}})"#
+        )
+    };
+
+    let result = CelExpression::parse(&cel_expression(r#""x-app-*""#, r#""x-amz-*","*""#));
+    assert_eq!(
+        result.unwrap(),
+        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(["x-app-*"], Vec::<&str>::new()),
+            response: DefaultResponseCertification::response_header_exclusions(&["x-amz-*", "*"]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        }))
+    );
+
+    let result = CelExpression::parse(&cel_expression(r#""x-*-version""#, ""));
+    assert!(matches!(
+        result,
+        Err(CelParserError::InvalidHeaderNamePattern(pattern)) if pattern == "x-*-version"
+    ));
+
+    let result = CelExpression::parse(&cel_expression("", r#""*-amz""#));
+    assert!(matches!(
+        result,
+        Err(CelParserError::InvalidHeaderNamePattern(pattern)) if pattern == "*-amz"
+    ));
+}
+
+#[test]
+fn parses_certified_methods() {
+    let cel_expression = |methods: &str| {
+        format!(
+            r#"default_certification(ValidationArgs{{certification:Certification{{request_certification:RequestCertification{{certified_request_headers:[],certified_query_parameters:[]{methods}}},response_certification:ResponseCertification{{certified_response_headers:ResponseHeaderList{{headers:[]}}}}}}}})"#
+        )
+    };
+    let expected_result = |methods: &[&'static str]| {
+        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(Vec::<&str>::new(), Vec::<&str>::new())
+                .with_methods(methods.iter().copied()),
+            response: DefaultResponseCertification::certified_response_headers(&[]),
+            response_header_options: ResponseHeaderCertificationOptions::default(),
+        }))
+    };
+
+    for (methods, expected_methods) in [
+        ("", &[][..]),
+        (r#",certified_methods:[]"#, &[][..]),
+        (r#",certified_methods:["GET","HEAD"]"#, &["GET", "HEAD"][..]),
+    ] {
+        let result = CelExpression::parse(&cel_expression(methods)).unwrap();
+
+        assert_eq!(result, expected_result(expected_methods));
+    }
+
+    let cel_expr = expected_result(&["GET", "HEAD"]).to_string();
+    assert_eq!(
+        cel_expr,
+        cel_expression(r#",certified_methods:["GET","HEAD"]"#)
+    );
+
+    let result = CelExpression::parse(&cel_expression(r#",certified_methods:"GET""#));
+    assert!(matches!(
+        result,
+        Err(CelParserError::UnexpectedNodeType { node_name, .. }) if node_name == "certified_methods"
+    ));
+}
+
+#[test]
+fn rejects_trailing_input() {
+    let cel_expression = format!(
+        "{} default_certification",
+        CelExpression::Default(DefaultCelExpression::Skip).to_string()
+    );
+
+    let result = CelExpression::parse(&cel_expression);
+
+    assert!(matches!(result, Err(CelParserError::CelSyntaxException(_))));
+}
+
+#[test]
+fn rejects_conditional_expression_with_unknown_attribute() {
+    let cel_expression = r#"request.body == "" ? default_certification(ValidationArgs{no_certification:Empty{}}) : default_certification(ValidationArgs{no_certification:Empty{}})"#;
+
+    let result = parse_cel_expression(cel_expression)
+        .map_err(|e| e.to_string())
+        .and_then(|cel| map_cel_ast(&cel).map(|_| ()).map_err(|e| e.to_string()));
+
+    assert!(result.is_err());
+}
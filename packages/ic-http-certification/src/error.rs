@@ -14,4 +14,112 @@ pub enum HttpCertificationError {
     /// Error converting UTF-8 string.
     #[error(r#"Error converting UTF8 string bytes: "{0}""#)]
     Utf8ConversionError(#[from] std::string::FromUtf8Error),
+
+    /// The request body could not be parsed as JSON.
+    #[error(r#"Failed to parse request body as JSON: "{0}""#)]
+    MalformedJsonBody(String),
+
+    /// The request method is not one of the methods that are listed in the request certification.
+    #[error(
+        r#"Request method "{method}" is not one of the certified methods {certified_methods:?}"#
+    )]
+    UncertifiedRequestMethod {
+        /// The method of the request.
+        method: String,
+        /// The methods that are listed in the request certification.
+        certified_methods: Vec<String>,
+    },
+
+    /// The response already has an `IC-Certificate` header. This header is added when the certified response
+    /// is served, so it can not be part of the response that is certified.
+    #[error(r#"The response already has an "IC-Certificate" header, it must only be added when the certified response is served"#)]
+    CertificateHeaderPresent,
+
+    /// The `IC-CertificateExpression` header of the response does not match the CEL expression that is used to
+    /// certify it, so verification of the response will fail.
+    #[error(r#"The "IC-CertificateExpression" header of the response is "{found}", but the response is certified with "{expected}""#)]
+    CertificateExpressionMismatch {
+        /// The CEL expression that is used to certify the response.
+        expected: String,
+        /// The value of the `IC-CertificateExpression` header of the response.
+        found: String,
+    },
+
+    /// A header is listed as a certified response header, but it is always excluded from certification.
+    #[error(r#"The "{0}" header is always excluded from certification and can not be listed as a certified response header"#)]
+    ExcludedHeaderCertified(String),
+
+    /// A header name pattern in a request or response certification is not valid. A `*` wildcard is only
+    /// allowed as the last character of a header name pattern.
+    #[error(r#"The "{0}" header name pattern is invalid, a wildcard is only allowed as the last character"#)]
+    InvalidHeaderNamePattern(String),
+
+    /// The `Content-Encoding` header of the response lists an encoding that HTTP gateways can not decode,
+    /// so verification of the response will fail.
+    #[error(r#"The "{0}" content encoding is not supported, only "gzip", "deflate", "br" and "identity" are supported"#)]
+    UnsupportedContentEncoding(String),
+
+    /// A header name is listed more than once in a request or response certification.
+    /// Header names are case-insensitive, so names that only differ in case are also duplicates.
+    #[error(r#"The "{0}" header is listed more than once, header names are case-insensitive"#)]
+    DuplicateHeaderName(String),
+
+    /// The response does not have an `IC-CertificateExpression` header, so it can not be verified.
+    #[error(r#"The response does not have an "IC-CertificateExpression" header"#)]
+    MissingCertificateExpressionHeader,
+
+    /// The `IC-CertificateExpression` header of the response could not be parsed.
+    #[error(r#"Failed to parse the "IC-CertificateExpression" header: "{0}""#)]
+    MalformedCertificateExpression(#[from] crate::cel::CelParserError),
+
+    /// There is no path in the certification tree that matches the request path.
+    #[error(r#"No path in the certification tree matches the request path "{0}""#)]
+    MissingCertificationPath(String),
+
+    /// The witness of the certification tree does not match the root hash of the tree.
+    #[error("The witness does not match the root hash of the certification tree")]
+    WitnessRootHashMismatch,
+
+    /// The witness of the certification tree does not prove that the expression path is the most specific
+    /// path in the tree that matches the request path.
+    #[error(
+        "The witness does not prove that the expression path {0:?} is valid for the request path"
+    )]
+    InvalidExpressionPath(Vec<String>),
+
+    /// The hash of the `IC-CertificateExpression` header of the response is not certified at the expression path.
+    #[error(r#"The "IC-CertificateExpression" header of the response is not certified at the expression path {0:?}"#)]
+    UncertifiedCertificateExpression(Vec<String>),
+
+    /// The request and response hashes are not certified at the expression path.
+    #[error("The request and response hashes are not certified at the expression path {0:?}")]
+    UncertifiedResponse(Vec<String>),
+
+    /// The bytes could not be decoded into a certification tree.
+    #[error(r#"Failed to decode certification tree: "{0}""#)]
+    MalformedCertificationTree(String),
+
+    /// A CEL operation or function was applied to values of the wrong type.
+    #[error(r#"Cannot apply CEL operation "{operation}" to values of type "{found}""#)]
+    CelTypeMismatch {
+        /// The operation or function that was applied.
+        operation: String,
+        /// The types of the values that the operation was applied to.
+        found: String,
+    },
+
+    /// A CEL function was called with the wrong number of arguments.
+    #[error(r#"CEL function "{function}" expects {expected} arguments, found {found}"#)]
+    CelArgumentCountMismatch {
+        /// The name of the function.
+        function: String,
+        /// The number of arguments that the function expects.
+        expected: usize,
+        /// The number of arguments that the function was called with.
+        found: usize,
+    },
+
+    /// A CEL condition accesses the response, but one of the branches that it selects between skips certification.
+    #[error("CEL conditions can not access the response if any branch of the expression skips certification")]
+    UncertifiedCelConditionResponse,
 }
@@ -0,0 +1,494 @@
+//! Canonicalization of JSON request bodies according to the
+//! [JSON Canonicalization Scheme (JCS)](https://www.rfc-editor.org/rfc/rfc8785), see
+//! [RequestBodyCertificationMode::CanonicalJson](crate::RequestBodyCertificationMode::CanonicalJson).
+
+use crate::{HttpCertificationError, HttpCertificationResult};
+use std::fmt::Write;
+
+/// The maximum nesting depth of arrays and objects in a JSON body.
+const MAX_DEPTH: usize = 128;
+
+/// Parses a JSON body and returns its canonical form according to RFC 8785.
+///
+/// Bodies that contain duplicate object keys, or integers that cannot be represented exactly as an
+/// IEEE 754 double, are rejected instead of being normalized, since implementations of JCS that
+/// normalize them differently would disagree on the canonical form of the body.
+pub(crate) fn canonical_json(body: &[u8]) -> HttpCertificationResult<String> {
+    let body = std::str::from_utf8(body)
+        .map_err(|err| HttpCertificationError::MalformedJsonBody(err.to_string()))?;
+
+    let mut parser = JsonParser {
+        input: body,
+        position: 0,
+        depth: 0,
+    };
+    parser.skip_whitespace();
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.position != body.len() {
+        return Err(parser.error("unexpected trailing characters"));
+    }
+
+    let mut canonical_body = String::new();
+    push_value(&mut canonical_body, &value);
+
+    Ok(canonical_body)
+}
+
+/// A parsed JSON value, with numbers converted to IEEE 754 doubles as required by RFC 8785.
+#[derive(Debug)]
+enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+/// A strict parser for JSON as defined in [RFC 8259](https://www.rfc-editor.org/rfc/rfc8259).
+struct JsonParser<'a> {
+    input: &'a str,
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn error(&self, message: &str) -> HttpCertificationError {
+        HttpCertificationError::MalformedJsonBody(format!(
+            "{} at position {}",
+            message, self.position
+        ))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> HttpCertificationResult<()> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected '{}'", expected as char)));
+        }
+        self.position += 1;
+
+        Ok(())
+    }
+
+    fn parse_value(&mut self) -> HttpCertificationResult<JsonValue> {
+        match self.peek() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(b't') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some(b'f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some(b'n') => self.parse_literal("null", JsonValue::Null),
+            Some(_) => Err(self.error("expected a JSON value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_literal(
+        &mut self,
+        literal: &str,
+        value: JsonValue,
+    ) -> HttpCertificationResult<JsonValue> {
+        if !self.input[self.position..].starts_with(literal) {
+            return Err(self.error("expected a JSON value"));
+        }
+        self.position += literal.len();
+
+        Ok(value)
+    }
+
+    fn enter(&mut self) -> HttpCertificationResult<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error("maximum nesting depth exceeded"));
+        }
+
+        Ok(())
+    }
+
+    fn parse_array(&mut self) -> HttpCertificationResult<JsonValue> {
+        self.enter()?;
+        self.expect(b'[')?;
+        self.skip_whitespace();
+
+        let mut values = vec![];
+        if self.peek() == Some(b']') {
+            self.position += 1;
+        } else {
+            loop {
+                self.skip_whitespace();
+                values.push(self.parse_value()?);
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(b',') => self.position += 1,
+                    Some(b']') => {
+                        self.position += 1;
+                        break;
+                    }
+                    _ => return Err(self.error("expected ',' or ']'")),
+                }
+            }
+        }
+
+        self.depth -= 1;
+        Ok(JsonValue::Array(values))
+    }
+
+    fn parse_object(&mut self) -> HttpCertificationResult<JsonValue> {
+        self.enter()?;
+        self.expect(b'{')?;
+        self.skip_whitespace();
+
+        let mut entries: Vec<(String, JsonValue)> = vec![];
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+        } else {
+            loop {
+                self.skip_whitespace();
+                let key_position = self.position;
+                let key = self.parse_string()?;
+                if entries.iter().any(|(other_key, _)| *other_key == key) {
+                    self.position = key_position;
+                    return Err(self.error(&format!("duplicate object key \"{}\"", key)));
+                }
+
+                self.skip_whitespace();
+                self.expect(b':')?;
+                self.skip_whitespace();
+                entries.push((key, self.parse_value()?));
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(b',') => self.position += 1,
+                    Some(b'}') => {
+                        self.position += 1;
+                        break;
+                    }
+                    _ => return Err(self.error("expected ',' or '}'")),
+                }
+            }
+        }
+
+        self.depth -= 1;
+        Ok(JsonValue::Object(entries))
+    }
+
+    fn parse_string(&mut self) -> HttpCertificationResult<String> {
+        self.expect(b'"')?;
+
+        let mut value = String::new();
+        loop {
+            let Some(c) = self.input[self.position..].chars().next() else {
+                return Err(self.error("unterminated string"));
+            };
+
+            match c {
+                '"' => {
+                    self.position += 1;
+                    return Ok(value);
+                }
+                '\\' => {
+                    self.position += 1;
+                    value.push(self.parse_escape()?);
+                }
+                c if c < ' ' => return Err(self.error("unescaped control character in string")),
+                c => {
+                    self.position += c.len_utf8();
+                    value.push(c);
+                }
+            }
+        }
+    }
+
+    fn parse_escape(&mut self) -> HttpCertificationResult<char> {
+        let escaped = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.position += 1;
+                let high = self.parse_hex_code_unit()?;
+                if !(0xD800..0xDC00).contains(&high) {
+                    return char::from_u32(high).ok_or_else(|| self.error("lone low surrogate"));
+                }
+
+                if !self.input[self.position..].starts_with("\\u") {
+                    return Err(self.error("lone high surrogate"));
+                }
+                self.position += 2;
+                let low = self.parse_hex_code_unit()?;
+                if !(0xDC00..0xE000).contains(&low) {
+                    return Err(self.error("lone high surrogate"));
+                }
+
+                let code_point = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                return char::from_u32(code_point).ok_or_else(|| self.error("invalid escape"));
+            }
+            _ => return Err(self.error("invalid escape")),
+        };
+        self.position += 1;
+
+        Ok(escaped)
+    }
+
+    fn parse_hex_code_unit(&mut self) -> HttpCertificationResult<u32> {
+        let hex = self
+            .input
+            .get(self.position..self.position + 4)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.position += 4;
+
+        Ok(u32::from_str_radix(hex, 16).expect("the escape consists of hex digits"))
+    }
+
+    fn parse_number(&mut self) -> HttpCertificationResult<JsonValue> {
+        let start = self.position;
+        let digits = |parser: &mut Self| {
+            let digits_start = parser.position;
+            while parser.peek().is_some_and(|b| b.is_ascii_digit()) {
+                parser.position += 1;
+            }
+
+            parser.position - digits_start
+        };
+
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        let integer_start = self.position;
+        match digits(self) {
+            0 => return Err(self.error("expected a digit")),
+            n if n > 1 && self.input.as_bytes()[integer_start] == b'0' => {
+                return Err(self.error("leading zeros are not allowed"))
+            }
+            _ => {}
+        }
+
+        let mut is_integer = true;
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            if digits(self) == 0 {
+                return Err(self.error("expected a digit"));
+            }
+            is_integer = false;
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.position += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.position += 1;
+            }
+            if digits(self) == 0 {
+                return Err(self.error("expected a digit"));
+            }
+            is_integer = false;
+        }
+
+        let literal = &self.input[start..self.position];
+        let number: f64 = literal.parse().map_err(|_| self.error("invalid number"))?;
+        if !number.is_finite() {
+            return Err(self.error(&format!(
+                "number {} is out of the range of IEEE 754 doubles",
+                literal
+            )));
+        }
+        // the exact decimal expansion of an integral double, which is never longer than 309 digits
+        if is_integer && format!("{:.0}", number.abs()) != literal.trim_start_matches('-') {
+            return Err(self.error(&format!(
+                "integer {} cannot be represented exactly as an IEEE 754 double",
+                literal
+            )));
+        }
+
+        Ok(JsonValue::Number(number))
+    }
+}
+
+fn push_value(canonical_json: &mut String, value: &JsonValue) {
+    match value {
+        JsonValue::Null => canonical_json.push_str("null"),
+        JsonValue::Bool(true) => canonical_json.push_str("true"),
+        JsonValue::Bool(false) => canonical_json.push_str("false"),
+        JsonValue::Number(number) => push_number(canonical_json, *number),
+        JsonValue::String(string) => push_string(canonical_json, string),
+        JsonValue::Array(values) => {
+            canonical_json.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    canonical_json.push(',');
+                }
+                push_value(canonical_json, value);
+            }
+            canonical_json.push(']');
+        }
+        JsonValue::Object(entries) => {
+            // RFC 8785 sorts keys by their UTF-16 code units, which differs from sorting them by
+            // their UTF-8 bytes for characters outside of the basic multilingual plane
+            let mut entries: Vec<_> = entries.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            canonical_json.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    canonical_json.push(',');
+                }
+                push_string(canonical_json, key);
+                canonical_json.push(':');
+                push_value(canonical_json, value);
+            }
+            canonical_json.push('}');
+        }
+    }
+}
+
+/// Serializes a number like ECMAScript's `Number.prototype.toString`, as required by RFC 8785.
+fn push_number(canonical_json: &mut String, number: f64) {
+    if number == 0.0 {
+        canonical_json.push('0');
+        return;
+    }
+    if number < 0.0 {
+        canonical_json.push('-');
+    }
+
+    // Rust formats the shortest digits that round trip to the same double, like ECMAScript does
+    let scientific = format!("{:e}", number.abs());
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("scientific notation has an exponent");
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().expect("the exponent is an integer") + 1;
+
+    if k <= n && n <= 21 {
+        canonical_json.push_str(&digits);
+        canonical_json.extend(std::iter::repeat('0').take((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        let (integer, fraction) = digits.split_at(n as usize);
+        write!(canonical_json, "{}.{}", integer, fraction).unwrap();
+    } else if -6 < n && n <= 0 {
+        canonical_json.push_str("0.");
+        canonical_json.extend(std::iter::repeat('0').take(-n as usize));
+        canonical_json.push_str(&digits);
+    } else {
+        let (first, rest) = digits.split_at(1);
+        canonical_json.push_str(first);
+        if !rest.is_empty() {
+            write!(canonical_json, ".{}", rest).unwrap();
+        }
+        let sign = if n > 0 { '+' } else { '-' };
+        write!(canonical_json, "e{}{}", sign, (n - 1).abs()).unwrap();
+    }
+}
+
+/// Serializes a string like ECMAScript's `JSON.stringify`, as required by RFC 8785.
+fn push_string(canonical_json: &mut String, string: &str) {
+    canonical_json.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => canonical_json.push_str("\\\""),
+            '\\' => canonical_json.push_str("\\\\"),
+            '\u{8}' => canonical_json.push_str("\\b"),
+            '\t' => canonical_json.push_str("\\t"),
+            '\n' => canonical_json.push_str("\\n"),
+            '\u{c}' => canonical_json.push_str("\\f"),
+            '\r' => canonical_json.push_str("\\r"),
+            c if c < ' ' => write!(canonical_json, "\\u{:04x}", c as u32).unwrap(),
+            c => canonical_json.push(c),
+        }
+    }
+    canonical_json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::object(r#"{"b":{"d":1,"c":2},"a":"x"}"#, r#"{"a":"x","b":{"c":2,"d":1}}"#)]
+    #[case::array(r#"[ 3, 1, 2 ]"#, r#"[3,1,2]"#)]
+    #[case::empty_containers(r#"[ {}, [ ] ]"#, r#"[{},[]]"#)]
+    #[case::literals(r#"[true, false, null]"#, r#"[true,false,null]"#)]
+    #[case::integers(
+        r#"[1, 1.0, -0, 1e2, 9007199254740993.5]"#,
+        r#"[1,1,0,100,9007199254740994]"#
+    )]
+    #[case::rfc_8785_numbers(
+        r#"[333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001]"#,
+        r#"[333333333.3333333,1e+30,4.5,0.002,1e-27]"#
+    )]
+    #[case::small_numbers(r#"[0.000001, 0.0000001, -1.5e-7]"#, r#"[0.000001,1e-7,-1.5e-7]"#)]
+    #[case::large_numbers(
+        r#"[1e21, 123e18, 1.7976931348623157e308]"#,
+        r#"[1e+21,123000000000000000000,1.7976931348623157e+308]"#
+    )]
+    #[case::exact_integers(
+        r#"[9007199254740992, 18446744073709551616]"#,
+        r#"[9007199254740992,18446744073709552000]"#
+    )]
+    #[case::strings(
+        r#""é\/\t\u001f\u007f\"\\\b\f\n\r😀""#,
+        "\"\u{e9}/\\t\\u001f\u{7f}\\\"\\\\\\b\\f\\n\\r\u{1f600}\""
+    )]
+    #[case::utf16_key_order(
+        r#"{"😀":1,"ﬁ":2,"é":3,"a":4}"#,
+        "{\"a\":4,\"\u{e9}\":3,\"\u{1f600}\":1,\"\u{fb01}\":2}"
+    )]
+    fn canonical_json_form(#[case] body: &str, #[case] expected: &str) {
+        let result = canonical_json(body.as_bytes()).unwrap();
+
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case::empty(b"")]
+    #[case::trailing_comma(br#"{"a":1,}"#)]
+    #[case::trailing_input(br#"{"a":1} {}"#)]
+    #[case::duplicate_keys(br#"{"a":1,"b":2,"a":3}"#)]
+    #[case::duplicate_escaped_keys(br#"{"a":1,"\u0061":1}"#)]
+    #[case::inexact_integer(b"9007199254740993")]
+    #[case::inexact_large_integer(b"123456789012345678901234567890")]
+    #[case::out_of_range(b"1e400")]
+    #[case::leading_zero(b"01")]
+    #[case::missing_fraction(b"1.")]
+    #[case::lone_high_surrogate(br#""\ud83d""#)]
+    #[case::lone_low_surrogate(br#""\ude00""#)]
+    #[case::control_character(b"\"\x01\"")]
+    #[case::single_quotes(b"'a'")]
+    #[case::invalid_utf8(b"\"\xff\"")]
+    fn canonical_json_rejects_invalid_body(#[case] body: &[u8]) {
+        let result = canonical_json(body);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::MalformedJsonBody(_))
+        ));
+    }
+
+    #[rstest]
+    fn canonical_json_rejects_deeply_nested_body() {
+        let body = "[".repeat(MAX_DEPTH) + &"]".repeat(MAX_DEPTH);
+        assert!(canonical_json(body.as_bytes()).is_ok());
+
+        let body = "[".repeat(MAX_DEPTH + 1) + &"]".repeat(MAX_DEPTH + 1);
+        assert!(matches!(
+            canonical_json(body.as_bytes()),
+            Err(HttpCertificationError::MalformedJsonBody(_))
+        ));
+    }
+}
@@ -2,6 +2,8 @@
 //! [Representation Independent Hashes](https://internetcomputer.org/docs/current/references/ic-interface-spec/#hash-of-map)
 //! of [crate::HttpRequest] and [crate::HttpRequest] objects.
 
+mod canonical_json;
+
 mod request_hash;
 pub use request_hash::*;
 
@@ -1,5 +1,11 @@
-use super::Hash;
-use crate::{cel::DefaultRequestCertification, HttpCertificationResult, HttpRequest};
+use super::{canonical_json::canonical_json, Hash};
+use crate::{
+    cel::{
+        header_name_matches, DefaultRequestCertification, QueryParameterCertificationMode,
+        RequestBodyCertificationMode,
+    },
+    HttpCertificationError, HttpCertificationResult, HttpRequest,
+};
 use ic_representation_independent_hash::{hash, representation_independent_hash, Value};
 
 /// Calculates the
@@ -13,25 +19,80 @@ pub fn request_hash<'a>(
 
     filtered_headers.push((
         ":ic-cert-method".into(),
-        Value::String(request.method.to_string()),
+        Value::String(get_certified_method(
+            &request.method,
+            request_certification,
+        )?),
     ));
 
     let filtered_query = request
         .get_query()?
-        .map(|query| get_filtered_query(&query, request_certification));
+        .map(|query| match request_certification.query_parameter_mode {
+            QueryParameterCertificationMode::Raw => {
+                Ok(get_filtered_query(&query, request_certification))
+            }
+            QueryParameterCertificationMode::Canonical => {
+                get_canonical_filtered_query(&query, request_certification)
+            }
+        })
+        .transpose()?;
     if let Some(query_hash) = filtered_query {
         filtered_headers.push((":ic-cert-query".into(), Value::String(query_hash)))
     }
 
+    let body_hash = match request_certification.body_mode {
+        RequestBodyCertificationMode::Raw => hash(&request.body),
+        RequestBodyCertificationMode::Skip => hash(&[]),
+        RequestBodyCertificationMode::CanonicalJson => {
+            hash(canonical_json(&request.body)?.as_bytes())
+        }
+    };
+
     let concatenated_hashes = [
         representation_independent_hash(&filtered_headers),
-        hash(&request.body),
+        body_hash,
     ]
     .concat();
 
     Ok(hash(concatenated_hashes.as_slice()))
 }
 
+fn get_certified_method(
+    method: &str,
+    request_certification: &DefaultRequestCertification,
+) -> HttpCertificationResult<String> {
+    if request_certification.methods.is_empty() {
+        return Ok(method.to_string());
+    }
+
+    if !request_certification
+        .methods
+        .iter()
+        .any(|certified_method| certified_method == method)
+    {
+        return Err(HttpCertificationError::UncertifiedRequestMethod {
+            method: method.to_string(),
+            certified_methods: request_certification
+                .methods
+                .iter()
+                .map(|certified_method| certified_method.to_string())
+                .collect(),
+        });
+    }
+
+    // the certified methods are sorted and deduplicated so that the order that they
+    // are listed in does not affect the request hash
+    let mut certified_methods: Vec<&str> = request_certification
+        .methods
+        .iter()
+        .map(|certified_method| certified_method.as_ref())
+        .collect();
+    certified_methods.sort_unstable();
+    certified_methods.dedup();
+
+    Ok(certified_methods.join(","))
+}
+
 fn get_filtered_headers(
     headers: &[(String, String)],
     request_certification: &DefaultRequestCertification,
@@ -39,13 +100,10 @@ fn get_filtered_headers(
     headers
         .iter()
         .filter_map(|(header_name, header_value)| {
-            let is_header_included =
-                request_certification
-                    .headers
-                    .iter()
-                    .any(|header_to_include| {
-                        header_to_include.eq_ignore_ascii_case(&header_name.to_string())
-                    });
+            let is_header_included = request_certification
+                .headers
+                .iter()
+                .any(|header_to_include| header_name_matches(header_to_include, header_name));
 
             if !is_header_included {
                 return None;
@@ -83,17 +141,73 @@ fn get_filtered_query(query: &str, request_certification: &DefaultRequestCertifi
     filtered_query_string
 }
 
+fn get_canonical_filtered_query(
+    query: &str,
+    request_certification: &DefaultRequestCertification,
+) -> HttpCertificationResult<String> {
+    let mut filtered_query_params = vec![];
+
+    for query_fragment in query.split('&').filter(|fragment| !fragment.is_empty()) {
+        let (query_param_name, query_param_value) = query_fragment
+            .split_once('=')
+            .unwrap_or((query_fragment, ""));
+
+        // a name that is not valid UTF-8 can never match one of the certified query parameters
+        let Ok(query_param_name) = decode_query_component(query_param_name) else {
+            continue;
+        };
+
+        let is_query_param_included =
+            request_certification
+                .query_parameters
+                .iter()
+                .any(|query_param_to_include| {
+                    query_param_to_include.eq_ignore_ascii_case(&query_param_name)
+                });
+        if !is_query_param_included {
+            continue;
+        }
+
+        filtered_query_params.push((
+            query_param_name.to_ascii_lowercase(),
+            decode_query_component(query_param_value)?,
+        ));
+    }
+
+    // stable sort, so that the values of repeated query parameters keep their original order
+    filtered_query_params.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let canonical_query_string = filtered_query_params
+        .iter()
+        .map(|(query_param_name, query_param_value)| {
+            format!(
+                "{}={}",
+                urlencoding::encode(query_param_name),
+                urlencoding::encode(query_param_value)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("&");
+
+    Ok(canonical_query_string)
+}
+
+/// Percent-decodes a query parameter name or value, decoding `+` as a space.
+pub(crate) fn decode_query_component(query_component: &str) -> HttpCertificationResult<String> {
+    let query_component = query_component.replace('+', " ");
+    let decoded_query_component = urlencoding::decode(&query_component)?;
+
+    Ok(decoded_query_component.into_owned())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::borrow::Cow;
+    use rstest::*;
 
     #[test]
     fn request_hash_without_query() {
-        let request_certification = DefaultRequestCertification {
-            headers: Cow::Borrowed(&["host"]),
-            query_parameters: Cow::Borrowed(&[]),
-        };
+        let request_certification = DefaultRequestCertification::new(["host"], Vec::<&str>::new());
         let request = create_request("https://ic0.app");
         let expected_hash =
             hex::decode("10796453466efb3e333891136b8a5931269f77e40ead9d437fcee94a02fa833c")
@@ -106,10 +220,7 @@ mod tests {
 
     #[test]
     fn request_hash_with_query() {
-        let request_certification = DefaultRequestCertification {
-            headers: Cow::Borrowed(&["host"]),
-            query_parameters: Cow::Borrowed(&["q", "name"]),
-        };
+        let request_certification = DefaultRequestCertification::new(["host"], ["q", "name"]);
         let request =
             create_request("https://ic0.app?q=hello+world&name=foo&name=bar&color=purple");
         let expected_hash =
@@ -123,10 +234,7 @@ mod tests {
 
     #[test]
     fn request_hash_query_order_matters() {
-        let request_certification = DefaultRequestCertification {
-            headers: Cow::Borrowed(&["host"]),
-            query_parameters: Cow::Borrowed(&["q", "name"]),
-        };
+        let request_certification = DefaultRequestCertification::new(["host"], ["q", "name"]);
         let request =
             create_request("https://ic0.app?q=hello+world&name=foo&name=bar&color=purple");
         let reordered_request =
@@ -140,10 +248,7 @@ mod tests {
 
     #[test]
     fn request_hash_query_with_fragment_does_not_change() {
-        let request_certification = DefaultRequestCertification {
-            headers: Cow::Borrowed(&["host"]),
-            query_parameters: Cow::Borrowed(&["q", "name"]),
-        };
+        let request_certification = DefaultRequestCertification::new(["host"], ["q", "name"]);
         let request =
             create_request("https://ic0.app?q=hello+world&name=foo&name=bar&color=purple");
         let request_with_fragment = create_request(
@@ -157,6 +262,218 @@ mod tests {
         assert_eq!(result, result_with_fragment);
     }
 
+    #[rstest]
+    #[case::sorted("https://ic0.app?name=foo&q=hello+world&color=purple")]
+    #[case::reordered("https://ic0.app?color=purple&q=hello+world&name=foo")]
+    #[case::percent_encoded("https://ic0.app?%6Eame=%66oo&q=hello%20world")]
+    #[case::name_case("https://ic0.app?NAME=foo&Q=hello+world")]
+    #[case::empty_fragments("https://ic0.app?&name=foo&&q=hello+world&")]
+    fn canonical_request_hash_with_query(#[case] uri: &str) {
+        let request_certification = DefaultRequestCertification::new(["host"], ["q", "name"])
+            .with_query_parameter_mode(QueryParameterCertificationMode::Canonical);
+        let request = create_request(uri);
+        let expected_hash =
+            hex::decode("9156265cbf88591fda1b30b855570925de1977fe76af699d14cee7451e437686")
+                .unwrap();
+
+        let result = request_hash(&request, &request_certification).unwrap();
+
+        assert_eq!(result, expected_hash.as_slice());
+    }
+
+    #[rstest]
+    #[case::query("name=foo&q=hello+world", "name=foo&q=hello%20world")]
+    #[case::reordered("q=hello+world&name=foo", "name=foo&q=hello%20world")]
+    #[case::repeated("name=foo&q=a&name=bar", "name=foo&name=bar&q=a")]
+    #[case::repeated_reordered("name=bar&q=a&name=foo", "name=bar&name=foo&q=a")]
+    #[case::missing_value("name&q=", "name=&q=")]
+    #[case::reserved_characters("q=a%26b%3Dc%2Bd", "q=a%26b%3Dc%2Bd")]
+    #[case::unicode("q=%E2%9C%93", "q=%E2%9C%93")]
+    #[case::uncertified("color=purple&Color=red", "")]
+    #[case::invalid_uncertified_name("%FF=1&q=a", "q=a")]
+    fn canonical_query(#[case] query: &str, #[case] expected: &str) {
+        let request_certification = DefaultRequestCertification::new(["host"], ["q", "name"])
+            .with_query_parameter_mode(QueryParameterCertificationMode::Canonical);
+
+        let result = get_canonical_filtered_query(query, &request_certification).unwrap();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn canonical_query_with_invalid_value() {
+        let request_certification = DefaultRequestCertification::new(["host"], ["q"])
+            .with_query_parameter_mode(QueryParameterCertificationMode::Canonical);
+
+        let result = get_canonical_filtered_query("q=%FF", &request_certification);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn canonical_request_hash_differs_from_raw_request_hash() {
+        let request_certification = DefaultRequestCertification::new(["host"], ["q", "name"]);
+        let canonical_request_certification = request_certification
+            .clone()
+            .with_query_parameter_mode(QueryParameterCertificationMode::Canonical);
+        let request = create_request("https://ic0.app?name=foo&q=hello+world");
+
+        let result = request_hash(&request, &request_certification).unwrap();
+        let canonical_result = request_hash(&request, &canonical_request_certification).unwrap();
+
+        assert_ne!(result, canonical_result);
+    }
+
+    #[test]
+    fn request_hash_with_skipped_body() {
+        let request_certification = DefaultRequestCertification::new(["host"], Vec::<&str>::new())
+            .with_body_mode(RequestBodyCertificationMode::Skip);
+        let request = create_request("https://ic0.app");
+        let request_without_body = HttpRequest {
+            body: vec![],
+            ..request.clone()
+        };
+
+        let result = request_hash(&request, &request_certification).unwrap();
+        let result_without_body =
+            request_hash(&request_without_body, &request_certification).unwrap();
+
+        assert_eq!(result, result_without_body);
+    }
+
+    #[rstest]
+    #[case::canonical(r#"{"a":[1,true,null],"b":"hello"}"#)]
+    #[case::whitespace("{ \"a\" : [ 1, true, null ],\n  \"b\": \"hello\" }")]
+    #[case::key_order(r#"{"b":"hello","a":[1,true,null]}"#)]
+    #[case::escaped_string(r#"{"b":"h\u0065llo","a":[1,true,null]}"#)]
+    fn request_hash_with_canonical_json_body(#[case] body: &str) {
+        let request_certification = DefaultRequestCertification::new(["host"], Vec::<&str>::new())
+            .with_body_mode(RequestBodyCertificationMode::CanonicalJson);
+        let request = HttpRequest {
+            body: body.as_bytes().to_vec(),
+            ..create_request("https://ic0.app")
+        };
+        let expected_hash =
+            hex::decode("3d846d5f86db821e0da1a7c35ccd08e86fd6135eb616af5d4cc7b4d5e3bfd907")
+                .unwrap();
+
+        let result = request_hash(&request, &request_certification).unwrap();
+
+        assert_eq!(result, expected_hash.as_slice());
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::trailing_comma(r#"{"a":1,}"#)]
+    #[case::trailing_input(r#"{"a":1} {}"#)]
+    #[case::duplicate_keys(r#"{"a":1,"a":2}"#)]
+    #[case::inexact_integer("12345678901234567891")]
+    fn canonical_json_with_invalid_body(#[case] body: &str) {
+        let request_certification = DefaultRequestCertification::new(["host"], Vec::<&str>::new())
+            .with_body_mode(RequestBodyCertificationMode::CanonicalJson);
+        let request = HttpRequest {
+            body: body.as_bytes().to_vec(),
+            ..create_request("https://ic0.app")
+        };
+
+        let result = request_hash(&request, &request_certification);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::MalformedJsonBody(_))
+        ));
+    }
+
+    #[rstest]
+    #[case::prefix(vec!["accept-*"])]
+    #[case::prefix_case_insensitive(vec!["ACCEPT-*"])]
+    #[case::prefix_and_exact(vec!["Accept-*", "Accept-Language"])]
+    fn request_hash_with_header_name_pattern(#[case] headers: Vec<&str>) {
+        let request_certification = DefaultRequestCertification::new(headers, Vec::<&str>::new());
+        let explicit_request_certification =
+            DefaultRequestCertification::new(["Accept-Language"], Vec::<&str>::new());
+        let request = create_request("https://ic0.app");
+
+        let result = request_hash(&request, &request_certification).unwrap();
+        let explicit_result = request_hash(&request, &explicit_request_certification).unwrap();
+
+        assert_eq!(result, explicit_result);
+    }
+
+    #[test]
+    fn request_hash_with_wildcard_header_name_pattern() {
+        let request_certification = DefaultRequestCertification::new(["*"], Vec::<&str>::new());
+        let explicit_request_certification =
+            DefaultRequestCertification::new(["Accept-Language", "Host"], Vec::<&str>::new());
+        let request = create_request("https://ic0.app");
+
+        let result = request_hash(&request, &request_certification).unwrap();
+        let explicit_result = request_hash(&request, &explicit_request_certification).unwrap();
+
+        assert_eq!(result, explicit_result);
+    }
+
+    #[rstest]
+    #[case::get("GET")]
+    #[case::head("HEAD")]
+    fn request_hash_with_certified_methods(#[case] method: &str) {
+        let request_certification = DefaultRequestCertification::new(["host"], Vec::<&str>::new())
+            .with_methods(["HEAD", "GET", "HEAD"]);
+        let method_class_request_certification =
+            DefaultRequestCertification::new(["host"], Vec::<&str>::new());
+        let request = HttpRequest {
+            method: method.into(),
+            ..create_request("https://ic0.app")
+        };
+        let method_class_request = HttpRequest {
+            method: "GET,HEAD".into(),
+            ..create_request("https://ic0.app")
+        };
+
+        let result = request_hash(&request, &request_certification).unwrap();
+        let method_class_result =
+            request_hash(&method_class_request, &method_class_request_certification).unwrap();
+
+        assert_eq!(result, method_class_result);
+    }
+
+    #[test]
+    fn request_hash_without_certified_methods_binds_method() {
+        let request_certification = DefaultRequestCertification::new(["host"], Vec::<&str>::new());
+        let request = create_request("https://ic0.app");
+        let head_request = HttpRequest {
+            method: "HEAD".into(),
+            ..create_request("https://ic0.app")
+        };
+
+        let result = request_hash(&request, &request_certification).unwrap();
+        let head_result = request_hash(&head_request, &request_certification).unwrap();
+
+        assert_ne!(result, head_result);
+    }
+
+    #[rstest]
+    #[case::uncertified_method("POST")]
+    #[case::different_case("get")]
+    fn request_hash_with_uncertified_method(#[case] method: &str) {
+        let request_certification = DefaultRequestCertification::new(["host"], Vec::<&str>::new())
+            .with_methods(["GET", "HEAD"]);
+        let request = HttpRequest {
+            method: method.into(),
+            ..create_request("https://ic0.app")
+        };
+
+        let result = request_hash(&request, &request_certification);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::UncertifiedRequestMethod {
+                method: uncertified_method,
+                certified_methods,
+            }) if uncertified_method == method && certified_methods == ["GET", "HEAD"]
+        ));
+    }
+
     fn create_request(uri: &str) -> HttpRequest {
         HttpRequest {
             url: uri.into(),
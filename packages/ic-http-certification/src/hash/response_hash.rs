@@ -1,9 +1,15 @@
 use super::Hash;
-use crate::{DefaultResponseCertification, HttpResponse};
+use crate::{
+    cel::{
+        header_name_matches, DuplicateResponseHeaderMode, ResponseHeaderCertificationOptions,
+        ResponseHeaderValueMode,
+    },
+    DefaultResponseCertification, HttpResponse,
+};
 use ic_representation_independent_hash::{hash, representation_independent_hash, Value};
 
-const CERTIFICATE_HEADER_NAME: &str = "IC-Certificate";
-const CERTIFICATE_EXPRESSION_HEADER_NAME: &str = "IC-CertificateExpression";
+pub(crate) const CERTIFICATE_HEADER_NAME: &str = "IC-Certificate";
+pub(crate) const CERTIFICATE_EXPRESSION_HEADER_NAME: &str = "IC-CertificateExpression";
 const RESPONSE_STATUS_PSEUDO_HEADER_NAME: &str = ":ic-cert-status";
 
 /// Representation of response headers filtered by [filter_response_headers].
@@ -26,16 +32,16 @@ pub fn filter_response_headers(
     let headers_filter: Box<dyn Fn(_) -> _> = match response_certification {
         DefaultResponseCertification::CertifiedResponseHeaders(headers_to_include) => {
             Box::new(move |header_name: &String| {
-                headers_to_include.iter().any(|header_to_include| {
-                    header_to_include.eq_ignore_ascii_case(&header_name.to_string())
-                })
+                headers_to_include
+                    .iter()
+                    .any(|header_to_include| header_name_matches(header_to_include, header_name))
             })
         }
         DefaultResponseCertification::ResponseHeaderExclusions(headers_to_exclude) => {
             Box::new(move |header_name: &String| {
-                !headers_to_exclude.iter().any(|header_to_exclude| {
-                    header_to_exclude.eq_ignore_ascii_case(&header_name.to_string())
-                })
+                !headers_to_exclude
+                    .iter()
+                    .any(|header_to_exclude| header_name_matches(header_to_exclude, header_name))
             })
         }
     };
@@ -83,17 +89,32 @@ pub fn filter_response_headers(
 /// Calculates the
 /// [Representation Independent Hash](https://internetcomputer.org/docs/current/references/ic-interface-spec/#hash-of-map)
 /// of [ResponseHeaders] that have been filtered with [filter_response_headers].
+///
+/// Every occurrence of a header is certified separately, exactly as it appears in the response.
+/// See [response_headers_hash_with_options] for certifying headers according to
+/// [ResponseHeaderCertificationOptions].
 pub fn response_headers_hash(status_code: &u64, response_headers: &ResponseHeaders) -> Hash {
-    let mut headers_to_verify: Vec<(String, Value)> = response_headers
-        .headers
-        .iter()
-        .map(|(header_name, header_value)| {
-            (
-                header_name.to_string(),
-                Value::String(String::from(header_value)),
-            )
-        })
-        .collect();
+    response_headers_hash_with_options(
+        status_code,
+        response_headers,
+        &ResponseHeaderCertificationOptions::default(),
+    )
+}
+
+/// Calculates the
+/// [Representation Independent Hash](https://internetcomputer.org/docs/current/references/ic-interface-spec/#hash-of-map)
+/// of [ResponseHeaders] that have been filtered with [filter_response_headers].
+///
+/// The values of the filtered headers are normalized according to the given
+/// [ResponseHeaderCertificationOptions], which should be the
+/// [response_header_options](crate::DefaultFullCelExpression::response_header_options) of the CEL
+/// expression that the headers were filtered with.
+pub fn response_headers_hash_with_options(
+    status_code: &u64,
+    response_headers: &ResponseHeaders,
+    options: &ResponseHeaderCertificationOptions,
+) -> Hash {
+    let mut headers_to_verify = certified_header_values(&response_headers.headers, options);
 
     if let Some(certificate_expression) = &response_headers.certificate_expression {
         headers_to_verify.push((
@@ -110,22 +131,103 @@ pub fn response_headers_hash(status_code: &u64, response_headers: &ResponseHeade
     representation_independent_hash(&headers_to_verify)
 }
 
+fn certified_header_values(
+    headers: &[(String, String)],
+    options: &ResponseHeaderCertificationOptions,
+) -> Vec<(String, Value)> {
+    let headers = headers.iter().map(|(header_name, header_value)| {
+        let header_value = match options.header_value_mode {
+            ResponseHeaderValueMode::Raw => header_value.as_str(),
+            ResponseHeaderValueMode::Trim => header_value.trim_matches([' ', '\t']),
+        };
+
+        (header_name, header_value)
+    });
+
+    if options.duplicate_header_mode == DuplicateResponseHeaderMode::Separate {
+        return headers
+            .map(|(header_name, header_value)| {
+                (header_name.to_string(), Value::String(header_value.into()))
+            })
+            .collect();
+    }
+
+    // group the values of each header, keeping the order that they appear in the response
+    let mut grouped_headers: Vec<(&String, Vec<&str>)> = vec![];
+    for (header_name, header_value) in headers {
+        match grouped_headers
+            .iter_mut()
+            .find(|(grouped_header_name, _)| *grouped_header_name == header_name)
+        {
+            Some((_, header_values)) => header_values.push(header_value),
+            None => grouped_headers.push((header_name, vec![header_value])),
+        }
+    }
+
+    grouped_headers
+        .into_iter()
+        .map(|(header_name, header_values)| {
+            let header_value = match options.duplicate_header_mode {
+                // the hash of an array is the hash of the concatenated hashes of its elements,
+                // which is the same as the hash of those concatenated hashes as bytes
+                DuplicateResponseHeaderMode::Array => Value::Bytes(
+                    header_values
+                        .into_iter()
+                        .flat_map(|header_value| hash(header_value.as_bytes()))
+                        .collect(),
+                ),
+                _ => Value::String(header_values.join(", ")),
+            };
+
+            (header_name.to_string(), header_value)
+        })
+        .collect()
+}
+
 /// Calculates the
 /// [Representation Independent Hash](https://internetcomputer.org/docs/current/references/ic-interface-spec/#hash-of-map)
 /// of an [HttpResponse] according to a CEL expression defined by [DefaultResponseCertification].
 ///
 /// An optional response body hash may be provided if this is known beforehand. If this override is not
 /// provided then the response body hash will be calculated by this function.
+///
+/// Every occurrence of a certified header is certified separately, exactly as it appears in the response.
+/// See [response_hash_with_options] for certifying headers according to [ResponseHeaderCertificationOptions].
 pub fn response_hash(
     response: &HttpResponse,
     response_certification: &DefaultResponseCertification,
     response_body_hash: Option<Hash>,
+) -> Hash {
+    response_hash_with_options(
+        response,
+        response_certification,
+        &ResponseHeaderCertificationOptions::default(),
+        response_body_hash,
+    )
+}
+
+/// Calculates the
+/// [Representation Independent Hash](https://internetcomputer.org/docs/current/references/ic-interface-spec/#hash-of-map)
+/// of an [HttpResponse] according to a CEL expression defined by [DefaultResponseCertification] and
+/// [ResponseHeaderCertificationOptions].
+///
+/// An optional response body hash may be provided if this is known beforehand. If this override is not
+/// provided then the response body hash will be calculated by this function.
+pub fn response_hash_with_options(
+    response: &HttpResponse,
+    response_certification: &DefaultResponseCertification,
+    options: &ResponseHeaderCertificationOptions,
+    response_body_hash: Option<Hash>,
 ) -> Hash {
     let response_body_hash = response_body_hash.unwrap_or(hash(&response.body));
 
     let filtered_headers = filter_response_headers(response, response_certification);
     let concatenated_hashes = [
-        response_headers_hash(&response.status_code.into(), &filtered_headers),
+        response_headers_hash_with_options(
+            &response.status_code.into(),
+            &filtered_headers,
+            options,
+        ),
         response_body_hash,
     ]
     .concat();
@@ -136,6 +238,8 @@ pub fn response_hash(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::DefaultCelBuilder;
+    use rstest::*;
 
     const HELLO_WORLD_BODY: &[u8] = &[72, 101, 108, 108, 111, 32, 87, 111, 114, 108, 100, 33];
     const CERTIFICATE: &str = "certificate=:SGVsbG8gQ2VydGlmaWNhdGUh:,tree=:SGVsbG8gVHJlZSE=:";
@@ -410,6 +514,185 @@ mod tests {
         assert_eq!(result, expected_hash.as_slice());
     }
 
+    #[test]
+    fn response_with_certified_header_name_pattern() {
+        let response_certification =
+            DefaultResponseCertification::certified_response_headers(&["Accept-*", "cache-*"]);
+        let response = create_response(CERTIFIED_HEADERS_CEL_EXPRESSION);
+        let response_headers = filter_response_headers(&response, &response_certification);
+
+        assert_eq!(
+            response_headers.headers,
+            vec![
+                ("accept-encoding".into(), "gzip".into()),
+                ("cache-control".into(), "no-cache".into()),
+                ("cache-control".into(), "no-store".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn response_with_excluded_header_name_pattern() {
+        let response_certification =
+            DefaultResponseCertification::response_header_exclusions(&["CACHE-*", "content-*"]);
+        let response = create_response(HEADER_EXCLUSIONS_CEL_EXPRESSION);
+        let response_headers = filter_response_headers(&response, &response_certification);
+
+        assert_eq!(
+            response_headers.headers,
+            vec![("accept-encoding".into(), "gzip".into())]
+        );
+    }
+
+    #[test]
+    fn response_with_wildcard_header_name_pattern() {
+        let response_certification =
+            DefaultResponseCertification::certified_response_headers(&["*"]);
+        let response = create_response(CERTIFIED_HEADERS_CEL_EXPRESSION);
+        let response_headers = filter_response_headers(&response, &response_certification);
+
+        assert_eq!(response_headers.headers.len(), 4);
+        assert!(response_headers.certificate.is_some());
+        assert!(response_headers.certificate_expression.is_some());
+    }
+
+    #[rstest]
+    #[case::separate_raw(
+        DuplicateResponseHeaderMode::Separate,
+        ResponseHeaderValueMode::Raw,
+        "9f2ea0eb925135cfbc31f4b4ef77dd568d307c66e87e30313410049ba768da77"
+    )]
+    #[case::separate_trim(
+        DuplicateResponseHeaderMode::Separate,
+        ResponseHeaderValueMode::Trim,
+        "55167fcb5b9574c9c3b49da90b9224882383b5829b15858cb725db6f59e45046"
+    )]
+    #[case::fold_raw(
+        DuplicateResponseHeaderMode::Fold,
+        ResponseHeaderValueMode::Raw,
+        "a17f20a0662fabb9789d9d186f9b261565485cff0e1bbf232f8975f6c6ed605d"
+    )]
+    #[case::fold_trim(
+        DuplicateResponseHeaderMode::Fold,
+        ResponseHeaderValueMode::Trim,
+        "cb831f4488dd026ec03a0f8249475050a5fcf8fa96bdcea32b62fe1bbd912ca1"
+    )]
+    #[case::array_raw(
+        DuplicateResponseHeaderMode::Array,
+        ResponseHeaderValueMode::Raw,
+        "d64deb19bc3a41c1c3f576d571958019fbb0142a23a2c9689890b4f4303ec5c5"
+    )]
+    #[case::array_trim(
+        DuplicateResponseHeaderMode::Array,
+        ResponseHeaderValueMode::Trim,
+        "d84dda9bf14e0a1db390959c7343c1052d08f7be9d5fc85cff86c00f6d527b7c"
+    )]
+    fn response_headers_hash_with_header_options(
+        #[case] duplicate_header_mode: DuplicateResponseHeaderMode,
+        #[case] header_value_mode: ResponseHeaderValueMode,
+        #[case] expected_hash: &str,
+    ) {
+        let response_headers = ResponseHeaders {
+            headers: vec![
+                ("cache-control".into(), " no-cache".into()),
+                ("accept-encoding".into(), "gzip".into()),
+                ("cache-control".into(), "no-store\t".into()),
+            ],
+            certificate: None,
+            certificate_expression: None,
+        };
+        let options = ResponseHeaderCertificationOptions::default()
+            .with_duplicate_header_mode(duplicate_header_mode)
+            .with_header_value_mode(header_value_mode);
+        let expected_hash = hex::decode(expected_hash).unwrap();
+
+        let result = response_headers_hash_with_options(&200, &response_headers, &options);
+
+        assert_eq!(result, expected_hash.as_slice());
+    }
+
+    #[test]
+    fn response_headers_hash_uses_default_header_options() {
+        let response = create_response(CERTIFIED_HEADERS_CEL_EXPRESSION);
+        let response_certification =
+            DefaultResponseCertification::certified_response_headers(&["Cache-Control"]);
+        let response_headers = filter_response_headers(&response, &response_certification);
+
+        let result = response_headers_hash(&200, &response_headers);
+
+        assert_eq!(
+            result,
+            response_headers_hash_with_options(
+                &200,
+                &response_headers,
+                &ResponseHeaderCertificationOptions::default()
+            )
+        );
+    }
+
+    #[rstest]
+    #[case::separate_raw(
+        DuplicateResponseHeaderMode::Separate,
+        ResponseHeaderValueMode::Raw,
+        "b269295fe6dd9de2509451e93ce0032e9b1848881b61cf294ec5e1a8498e26c4"
+    )]
+    #[case::separate_trim(
+        DuplicateResponseHeaderMode::Separate,
+        ResponseHeaderValueMode::Trim,
+        "ec4b3867295e8ef8ded8c5889ab0737fff231486d85950a6b3308003e447b352"
+    )]
+    #[case::fold_raw(
+        DuplicateResponseHeaderMode::Fold,
+        ResponseHeaderValueMode::Raw,
+        "d5aab020fc8bd9fd22e7927b3fd244109beb08c5dd7b1d32fc81d7a35bfd3ef5"
+    )]
+    #[case::fold_trim(
+        DuplicateResponseHeaderMode::Fold,
+        ResponseHeaderValueMode::Trim,
+        "bd90336f1320275689f045d10e17b99766dfe7f54291eae08a7923dc318aeece"
+    )]
+    #[case::array_raw(
+        DuplicateResponseHeaderMode::Array,
+        ResponseHeaderValueMode::Raw,
+        "ed5b3e1be7e3215981864380078b55f1eaced168999b55d22e5ca4cdd5401511"
+    )]
+    #[case::array_trim(
+        DuplicateResponseHeaderMode::Array,
+        ResponseHeaderValueMode::Trim,
+        "1f5f71d700f503da76573484b7fa4fb16ea3d1ec92d7a7399ce6b7660f92861b"
+    )]
+    fn response_hash_with_header_options(
+        #[case] duplicate_header_mode: DuplicateResponseHeaderMode,
+        #[case] header_value_mode: ResponseHeaderValueMode,
+        #[case] expected_hash: &str,
+    ) {
+        let response_certification =
+            DefaultResponseCertification::certified_response_headers(&["Cache-Control"]);
+        let options = ResponseHeaderCertificationOptions::default()
+            .with_duplicate_header_mode(duplicate_header_mode)
+            .with_header_value_mode(header_value_mode);
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(response_certification.clone())
+            .with_response_header_options(options)
+            .build();
+        let response = HttpResponse {
+            status_code: 200,
+            headers: vec![
+                ("IC-CertificateExpression".into(), cel_expr.to_string()),
+                ("Cache-Control".into(), " no-cache".into()),
+                ("Accept-Encoding".into(), "gzip".into()),
+                ("Cache-Control".into(), "no-store\t".into()),
+            ],
+            body: HELLO_WORLD_BODY.into(),
+            upgrade: None,
+        };
+        let expected_hash = hex::decode(expected_hash).unwrap();
+
+        let result = response_hash_with_options(&response, &response_certification, &options, None);
+
+        assert_eq!(result, expected_hash.as_slice());
+    }
+
     fn create_response(cel_expression: &str) -> HttpResponse {
         HttpResponse {
             status_code: 200,
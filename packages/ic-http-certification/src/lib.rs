@@ -36,11 +36,66 @@ let certification = CelExpression::Default(DefaultCelExpression::Skip);
 let cel_expr = create_cel_expr(&certification);
 ```
 
+### Parsing CEL expressions from their `String` representation
+
+To convert the [String] representation of a CEL expression back into a [CelExpression], use [CelExpression::parse](cel::CelExpression::parse()), [str::parse](str::parse()) or [parse_cel_expr](cel::parse_cel_expr()). The parsed expression owns all of its data, so it can be loaded from a configuration file and stored in a canister's state. Parsing the [String] representation of an expression always produces an expression that is equal to the original one, so expressions can be compared semantically rather than by their [String] representation.
+
+```rust
+use ic_http_certification::{cel::{CelExpression, DefaultCelExpression}, DefaultCelBuilder};
+
+let cel_expr = DefaultCelBuilder::full_certification()
+  .with_request_headers(&["Accept"])
+  .build();
+let cel_expr = CelExpression::Default(DefaultCelExpression::Full(cel_expr));
+
+let parsed_cel_expr = CelExpression::parse(&cel_expr.to_string()).unwrap();
+assert_eq!(parsed_cel_expr, cel_expr);
+```
+
+### Canonical CEL expressions
+
+Verifiers hash the exact bytes of the `IC-CertificateExpression` header, so a response fails verification if the header that is served differs from the expression that was certified, even if the difference is only whitespace, the case of a header name or the order of a list of headers. To avoid this, use [CelExpression::canonicalize](cel::CelExpression::canonicalize()) or [canonical_cel_expr](cel::canonical_cel_expr()) to convert an expression into its canonical form, and both certify and serve that form. [CelExpression::is_equivalent](cel::CelExpression::is_equivalent()) compares two expressions by their canonical forms.
+
+[lint_cel_expr](cel::lint_cel_expr()) checks a served `IC-CertificateExpression` header against the [certified CEL expression hash](HttpCertification::cel_expr_hash()) and returns a list of [warnings](cel::CelExpressionLint), for example if the served header does not match the certified hash, or if the certified hash does not match the canonical form of the served header.
+
+```rust
+use ic_http_certification::{cel::{lint_cel_expr, CelExpression, DefaultCelExpression}, DefaultCelBuilder, DefaultResponseCertification, HttpCertification, HttpRequest, HttpResponse};
+
+let cel_expr = CelExpression::Default(DefaultCelExpression::ResponseOnly(
+  DefaultCelBuilder::response_only_certification()
+    .with_response_certification(DefaultResponseCertification::certified_response_headers(&["ETag", "Cache-Control"]))
+    .build(),
+));
+let canonical_cel_expr = cel_expr.canonicalize();
+assert!(canonical_cel_expr.is_equivalent(&cel_expr));
+
+let request = HttpRequest {
+  method: "GET".to_string(),
+  url: "/".to_string(),
+  headers: vec![],
+  body: vec![],
+};
+let response = HttpResponse {
+  status_code: 200,
+  headers: vec![("IC-CertificateExpression".to_string(), canonical_cel_expr.to_string())],
+  body: vec![],
+  upgrade: None,
+};
+let certification = HttpCertification::from_cel_expr(&canonical_cel_expr, &request, &response, None).unwrap();
+
+assert!(lint_cel_expr(&canonical_cel_expr.to_string(), &certification.cel_expr_hash()).is_empty());
+assert!(!lint_cel_expr(&cel_expr.to_string(), &certification.cel_expr_hash()).is_empty());
+```
+
 ### Using the CEL builder
 
 The CEL builder interface is provided to ease the creation of CEL expressions through an ergonomic interface. If this interface does not meet your needs, you can also [create CEL expressions directly](#directly-creating-a-cel-expression). To define a CEL expression, start with [DefaultCelBuilder]. This struct provides a set of associated functions that can be used to define how your request and response pair should be certified.
 
-When certifying requests, the request body and method are always certified. To additionally certify request headers and query parameters, use [with_request_headers](cel::DefaultFullCelExpressionBuilder::with_request_headers()) and [with_request_query_parameters](cel::DefaultFullCelExpressionBuilder::with_request_query_parameters()) respectively. Both associated functions take a [str] slice as an argument.
+When certifying requests, the request method is always certified and the request body is certified by default. To additionally certify request headers and query parameters, use [with_request_headers](cel::DefaultFullCelExpressionBuilder::with_request_headers()) and [with_request_query_parameters](cel::DefaultFullCelExpressionBuilder::with_request_query_parameters()) respectively. Both associated functions take a [str] slice as an argument.
+
+By default, certified query parameters are included exactly as they appear in the request URL, so reordering or re-encoding them changes the request hash. To certify query parameters in a canonical form instead, where they are percent-decoded and sorted by name while repeated parameters keep the order of their values, use [with_request_query_parameter_mode](cel::DefaultFullCelExpressionBuilder::with_request_query_parameter_mode()) with [QueryParameterCertificationMode::Canonical](cel::QueryParameterCertificationMode::Canonical). Verifiers apply the same canonicalization when they calculate the request hash.
+
+The request body can be excluded from certification, or certified in the canonical JSON form defined by the [JSON Canonicalization Scheme](https://www.rfc-editor.org/rfc/rfc8785) so that bodies that only differ in whitespace, in the order of object keys or in the formatting of strings and numbers result in the same request hash, using [with_request_body_mode](cel::DefaultFullCelExpressionBuilder::with_request_body_mode()) with the [RequestBodyCertificationMode](cel::RequestBodyCertificationMode) enum. This is useful for API canisters that accept JSON bodies.
 
 When certifying a response, the response body and status code are always certified. To additionally certify response headers, use [with_response_certification](cel::DefaultFullCelExpressionBuilder::with_response_certification()). This associated function takes the [DefaultResponseCertification](DefaultResponseCertification) enum as an argument. To specify header inclusions, use the [certified_response_headers](DefaultResponseCertification::certified_response_headers) associated function of the [DefaultResponseCertification](DefaultResponseCertification) enum. Or to certify all response headers, with some exclusions, use the [response_header_exclusions](DefaultResponseCertification::response_header_exclusions) associated function of the [DefaultResponseCertification](DefaultResponseCertification) enum. Both associated functions take a [str] slice as an argument.
 
@@ -93,6 +148,36 @@ let cel_expr = DefaultCelBuilder::full_certification()
     .build();
 ```
 
+#### Certifying request methods
+
+The request method is always certified, so by default a certification for a `GET` request can not be used for a `HEAD` request to the same URL. To let multiple methods share the same request certification, use [with_request_methods](cel::DefaultFullCelExpressionBuilder::with_request_methods()). The listed methods are certified instead of the exact request method, and requests with any other method fail to be certified.
+
+A response to a `HEAD` request has the same status code and headers as the response to a `GET` request, but an empty body, so it needs its own certification. Use [HttpCertification::full_head](HttpCertification::full_head()) or [HttpCertification::response_only_head](HttpCertification::response_only_head()) to create it from the `GET` request and response, then add both certifications to the certification tree. For example:
+
+```rust
+use ic_http_certification::{DefaultCelBuilder, HttpCertification, HttpRequest, HttpResponse};
+
+let cel_expr = DefaultCelBuilder::full_certification()
+    .with_request_methods(&["GET", "HEAD"])
+    .build();
+
+let request = HttpRequest {
+    method: "GET".to_string(),
+    url: "/".to_string(),
+    headers: vec![],
+    body: vec![],
+};
+let response = HttpResponse {
+    status_code: 200,
+    headers: vec![("IC-CertificateExpression".to_string(), cel_expr.to_string())],
+    body: b"Hello World!".to_vec(),
+    upgrade: None,
+};
+
+let get_certification = HttpCertification::full(&cel_expr, &request, &response, None).unwrap();
+let head_certification = HttpCertification::full_head(&cel_expr, &request, &response).unwrap();
+```
+
 #### Skipping request certification
 
 Request certification can be skipped entirely by using [DefaultCelBuilder::response_only_certification](DefaultCelBuilder::response_only_certification()) instead of [DefaultCelBuilder::full_certification](DefaultCelBuilder::full_certification()). For example:
@@ -145,6 +230,48 @@ let cel_expr = DefaultCelBuilder::full_certification()
 
 To skip response certification completely, then certification overall must be skipped completely. It wouldn't be useful to certify a request without certifying a response. So if anything is certified, then it must at least include the response. See the next section for more details on skipping certification entirely.
 
+#### Header name patterns
+
+Request and response header names are matched case-insensitively. A header name that ends with a `*` wildcard matches every header that starts with the rest of the name, so headers can be certified or excluded by prefix without listing all of them. A `*` wildcard on its own matches every header. A wildcard anywhere else in a header name is not supported and the resulting CEL expression will fail to parse. For example, to certify all request headers starting with `X-App-`, and all response headers except those starting with `X-Amz-`:
+
+```rust
+use ic_http_certification::{DefaultCelBuilder, DefaultResponseCertification};
+
+let cel_expr = DefaultCelBuilder::full_certification()
+    .with_request_headers(&["X-App-*"])
+    .with_response_certification(DefaultResponseCertification::response_header_exclusions(&[
+        "X-Amz-*",
+    ]))
+    .build();
+```
+
+The [header_name_matches](cel::header_name_matches()) function can be used to check whether a header name matches one of these patterns.
+
+#### Multi-valued response headers
+
+By default, every occurrence of a certified response header is certified separately and header values are certified exactly as they are served. This can be changed by passing [ResponseHeaderCertificationOptions](cel::ResponseHeaderCertificationOptions) to the [with_response_header_options](cel::DefaultResponseOnlyCelBuilder::with_response_header_options()) method of the CEL expression builders, which sets the `response_header_options` field of the CEL expression.
+
+The [duplicate_header_mode](cel::ResponseHeaderCertificationOptions::duplicate_header_mode) option controls how a response header that occurs multiple times is certified. [Separate](cel::DuplicateResponseHeaderMode::Separate) certifies each occurrence separately, [Fold](cel::DuplicateResponseHeaderMode::Fold) joins all values of the header into a single comma separated value and [Array](cel::DuplicateResponseHeaderMode::Array) certifies all values of the header as an ordered list. The [header_value_mode](cel::ResponseHeaderCertificationOptions::header_value_mode) option controls how header values are normalized before they are certified. [Raw](cel::ResponseHeaderValueMode::Raw) certifies values exactly as they are served and [Trim](cel::ResponseHeaderValueMode::Trim) removes leading and trailing spaces and tabs. For example, to certify multiple `Set-Cookie` headers in the order that they are served, regardless of any surrounding whitespace:
+
+```rust
+use ic_http_certification::{
+    cel::{DuplicateResponseHeaderMode, ResponseHeaderCertificationOptions, ResponseHeaderValueMode},
+    DefaultCelBuilder, DefaultResponseCertification,
+};
+
+let cel_expr = DefaultCelBuilder::response_only_certification()
+    .with_response_certification(DefaultResponseCertification::certified_response_headers(&[
+        "Cache-Control",
+        "Set-Cookie",
+    ]))
+    .with_response_header_options(
+        ResponseHeaderCertificationOptions::default()
+            .with_duplicate_header_mode(DuplicateResponseHeaderMode::Array)
+            .with_header_value_mode(ResponseHeaderValueMode::Trim),
+    )
+    .build();
+```
+
 #### Skipping certification
 
 To skip certification entirely, use [DefaultCelBuilder::skip_certification](DefaultCelBuilder::skip_certification()), for example:
@@ -207,6 +334,8 @@ let response = HttpResponse {
 let certification = HttpCertification::full(&cel_expr, &request, &response, None);
 ```
 
+Both [HttpCertification::full](HttpCertification::full()) and [HttpCertification::response_only](HttpCertification::response_only()) validate the request and response before certifying them, and return an [HttpCertificationError] if the certification could never be verified. For example, if the response already has an `IC-Certificate` header, if its `IC-CertificateExpression` header does not match the CEL expression, if a header name is listed more than once or is not a valid header name pattern, if the response has a `Content-Encoding` that HTTP gateways can not decode, or if the request URL is malformed.
+
 ### Response-only certification
 
 To perform a response-only certification, a CEL expression created from [DefaultCelBuilder::response_only_certification] is required, along with an [HttpResponse] and optionally, a pre-calculated response body hash. For example:
@@ -231,7 +360,7 @@ let response = HttpResponse {
     upgrade: None,
 };
 
-let certification = HttpCertification::response_only(&cel_expr, &response, None);
+let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
 ```
 
 ### Skipping certification
@@ -257,7 +386,7 @@ In this example, the certification entered into the tree with this path will be
 ```rust
 use ic_http_certification::HttpCertificationPath;
 
-let path = HttpCertificationPath::Wildcard("/js");
+let path = HttpCertificationPath::wildcard("/js");
 ```
 
 Exact paths are used to match the entire request URL. An exact path ending with a trailing slash referes to a file system directory, where as one without a trailing slash refers to an individual file. Both are separate paths within the certification tree and will be treated completely independently.
@@ -267,7 +396,7 @@ In this example, the certification entered into the tree with this path will onl
 ```rust
 use ic_http_certification::HttpCertificationPath;
 
-let path = HttpCertificationPath::Exact("/js/example.js");
+let path = HttpCertificationPath::exact("/js/example.js");
 ```
 
 ### Using the HTTP certification tree
@@ -308,7 +437,7 @@ let response = HttpResponse {
 };
 
 let request_url = "/example.json";
-let path = HttpCertificationPath::Exact(request_url);
+let path = HttpCertificationPath::exact(request_url);
 let certification = HttpCertification::full(&cel_expr, &request, &response, None).unwrap();
 
 let mut http_certification_tree = HttpCertificationTree::default();
@@ -325,11 +454,131 @@ let witness = http_certification_tree.witness(&entry, request_url);
 http_certification_tree.delete(&entry);
 ```
 
+When certifying a large number of entries at once, for example in a canister's `init` or `post_upgrade` hooks, prefer the batch operations [insert_many](HttpCertificationTree::insert_many), [delete_many](HttpCertificationTree::delete_many) and [replace](HttpCertificationTree::replace). These only recompute the hashes of the tree once for the whole batch, and [replace](HttpCertificationTree::replace) rebuilds the entire tree in linear time.
+
+```rust
+use ic_http_certification::{HttpCertification, HttpCertificationTree, HttpCertificationTreeEntry, HttpCertificationPath};
+
+let paths = [
+    HttpCertificationPath::exact("/index.html"),
+    HttpCertificationPath::exact("/app.js"),
+    HttpCertificationPath::wildcard("/"),
+];
+let certification = HttpCertification::skip();
+let entries: Vec<_> = paths
+    .iter()
+    .map(|path| HttpCertificationTreeEntry::new(path, &certification))
+    .collect();
+
+let mut http_certification_tree = HttpCertificationTree::default();
+
+// rebuild the tree with all of the entries
+http_certification_tree.replace(&entries);
+
+// delete multiple entries from the tree
+http_certification_tree.delete_many(&entries[1..]);
+
+// insert multiple entries into the tree
+http_certification_tree.insert_many(&entries[1..]);
+```
+
+The tree is stored in heap memory, so it is lost when a canister is upgraded. Rather than recomputing the certification of every response in the `post_upgrade` hook, the tree can be serialized with [to_bytes](HttpCertificationTree::to_bytes) in the `pre_upgrade` hook, written to stable memory and then restored with [from_bytes](HttpCertificationTree::from_bytes) in the `post_upgrade` hook. The restored tree has exactly the same shape, and therefore the same [root hash](HttpCertificationTree::root_hash), as the serialized tree, so the canister's certified variable doesn't need to change and witnesses that were already issued remain valid.
+
+```rust
+use ic_http_certification::{HttpCertification, HttpCertificationTree, HttpCertificationTreeEntry, HttpCertificationPath};
+
+let certification = HttpCertification::skip();
+let path = HttpCertificationPath::wildcard("/");
+let entry = HttpCertificationTreeEntry::new(&path, &certification);
+
+let mut http_certification_tree = HttpCertificationTree::default();
+http_certification_tree.insert(&entry);
+
+// serialize the tree before upgrading the canister
+let bytes = http_certification_tree.to_bytes();
+
+// restore the tree after upgrading the canister
+let restored_tree = HttpCertificationTree::from_bytes(&bytes).unwrap();
+assert_eq!(restored_tree.root_hash(), http_certification_tree.root_hash());
+```
+
+To inspect which entries are currently certified, use [iter](HttpCertificationTree::iter) to list all entries in the tree, [get](HttpCertificationTree::get) to list the certifications for a given path, and [contains](HttpCertificationTree::contains) to check whether a given entry is present in the tree. This can help to debug responses that fail verification, or to find and delete certifications that are no longer needed.
+
+```rust
+use ic_http_certification::{HttpCertification, HttpCertificationTree, HttpCertificationTreeEntry, HttpCertificationPath};
+
+let path = HttpCertificationPath::exact("/index.html");
+let certification = HttpCertification::skip();
+let entry = HttpCertificationTreeEntry::new(&path, &certification);
+
+let mut http_certification_tree = HttpCertificationTree::default();
+http_certification_tree.insert(&entry);
+
+assert!(http_certification_tree.contains(&entry));
+assert_eq!(http_certification_tree.get(&path), vec![certification.clone()]);
+
+for item in http_certification_tree.iter() {
+    assert_eq!(item.path(), &path);
+    assert_eq!(item.certification(), &certification);
+}
+```
+
+To certify several responses with a single certificate, for example an HTML page together with its critical assets, use [witness_many](HttpCertificationTree::witness_many) to generate one witness that covers all of the entries and their request URLs. Each response can then include the same certificate and witness, and clients can verify all of the responses at once using `verify_request_response_pairs` from the `ic-response-verification` crate.
+
+```rust
+use ic_http_certification::{HttpCertification, HttpCertificationTree, HttpCertificationTreeEntry, HttpCertificationPath};
+
+let index_path = HttpCertificationPath::exact("/index.html");
+let app_path = HttpCertificationPath::exact("/app.js");
+let certification = HttpCertification::skip();
+let index_entry = HttpCertificationTreeEntry::new(&index_path, &certification);
+let app_entry = HttpCertificationTreeEntry::new(&app_path, &certification);
+
+let mut http_certification_tree = HttpCertificationTree::default();
+http_certification_tree.insert_many([&index_entry, &app_entry]);
+
+let witness = http_certification_tree.witness_many(&[
+    (&index_entry, "/index.html"),
+    (&app_entry, "/app.js"),
+]);
+assert_eq!(witness.digest(), http_certification_tree.root_hash());
+```
+
+To check that a response will pass verification before serving it, use [debug_self_verify] in unit tests or in a debug build of the canister. It repeats the verifier's checks against the tree's witness instead of a real certificate: it looks up the most specific path in the tree that matches the request URL, checks that the witness proves there is no more specific path, and checks that the hash of the response's `IC-CertificateExpression` header and the request and response hashes are certified at that path. It returns an error that explains why verification would fail, for example if the served header differs from the certified expression, or if a more specific wildcard path shadows the certified one.
+
+```rust
+use ic_http_certification::{debug_self_verify, DefaultCelBuilder, HttpCertification, HttpCertificationPath, HttpCertificationTree, HttpCertificationTreeEntry, HttpRequest, HttpResponse};
+
+let cel_expr = DefaultCelBuilder::response_only_certification().build();
+let request = HttpRequest {
+    method: "GET".to_string(),
+    url: "/index.html".to_string(),
+    headers: vec![],
+    body: vec![],
+};
+let response = HttpResponse {
+    status_code: 200,
+    headers: vec![("IC-CertificateExpression".to_string(), cel_expr.to_string())],
+    body: b"<html></html>".to_vec(),
+    upgrade: None,
+};
+
+let path = HttpCertificationPath::exact("/index.html");
+let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+let mut http_certification_tree = HttpCertificationTree::default();
+http_certification_tree.insert(&HttpCertificationTreeEntry::new(&path, &certification));
+
+if cfg!(debug_assertions) {
+    let item = debug_self_verify(&request, &response, &http_certification_tree).unwrap();
+    assert_eq!(item.certification(), &certification);
+}
+```
+
 ## Directly creating a CEL expression
 
 To define a CEL expression, start with the [CelExpression](cel::CelExpression) enum. This enum provides a set of variants that can be used to define different types of CEL expressions supported by Internet Computer HTTP Gateways. Currently only one variant is supported, known as the "default" certification expression, but more may be added in the future as the HTTP certification protocol evolves over time.
 
-When certifying requests, the request body and method are always certified. To additionally certify request headers and query parameters, use the [headers](cel::DefaultRequestCertification::headers) and [query_parameters](cel::DefaultRequestCertification::query_parameters) fields of the [DefaultRequestCertification](cel::DefaultRequestCertification) struct. Both fields take a [str] slice as an argument.
+When certifying requests, the request method is always certified and the request body is certified by default. To additionally certify request headers and query parameters, use the [headers](cel::DefaultRequestCertification::headers) and [query_parameters](cel::DefaultRequestCertification::query_parameters) fields of the [DefaultRequestCertification](cel::DefaultRequestCertification) struct. Both fields take a [str] slice as an argument. How the query parameters and the request body are certified can be configured with the [query_parameter_mode](cel::DefaultRequestCertification::query_parameter_mode) and [body_mode](cel::DefaultRequestCertification::body_mode) fields, and the [methods](cel::DefaultRequestCertification::methods) field lists request methods that share the same certification.
 
 When certifying a response, the response body and status code are always certified. To additionally certify response headers, use the [certified_response_headers](DefaultResponseCertification::certified_response_headers) associated function of the [DefaultResponseCertification](DefaultResponseCertification) enum. Or to certify all response headers, with some exclusions, use the [response_header_exclusions](DefaultResponseCertification::response_header_exclusions) associated function of the [DefaultResponseCertification](DefaultResponseCertification) enum. Both associated functions take a [str] slice as an argument.
 
@@ -340,19 +589,19 @@ Note that the example CEL expressions provided below are formatted for readabili
 To define a fully certified request and response pair, including request headers, query parameters, and response headers:
 
 ```rust
-use std::borrow::Cow;
-use ic_http_certification::cel::{CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification, DefaultResponseCertification};
+use ic_http_certification::cel::{CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification, DefaultResponseCertification, ResponseHeaderCertificationOptions};
 
 let cel_expr = CelExpression::Default(DefaultCelExpression::Full(
   DefaultFullCelExpression {
-    request: DefaultRequestCertification {
-      headers: Cow::Borrowed(&["Accept", "Accept-Encoding", "If-None-Match"]),
-      query_parameters: Cow::Borrowed(&["foo", "bar", "baz"]),
-    },
+    request: DefaultRequestCertification::new(
+      ["Accept", "Accept-Encoding", "If-None-Match"],
+      ["foo", "bar", "baz"],
+    ),
     response: DefaultResponseCertification::certified_response_headers(&[
       "ETag",
       "Cache-Control",
     ]),
+    response_header_options: ResponseHeaderCertificationOptions::default(),
   }));
 ```
 
@@ -384,19 +633,22 @@ Any number of request headers or query parameters can be provided via the [heade
 For example, to certify only the request body and method:
 
 ```rust
-use std::borrow::Cow;
-use ic_http_certification::cel::{CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification, DefaultResponseCertification};
+use ic_http_certification::cel::{CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification, DefaultResponseCertification, QueryParameterCertificationMode, RequestBodyCertificationMode, ResponseHeaderCertificationOptions};
 
 let cel_expr = CelExpression::Default(DefaultCelExpression::Full(
   DefaultFullCelExpression {
     request: DefaultRequestCertification {
-      headers: Cow::Borrowed(&[]),
-      query_parameters: Cow::Borrowed(&[]),
+      headers: vec![],
+      query_parameters: vec![],
+      query_parameter_mode: QueryParameterCertificationMode::Raw,
+      body_mode: RequestBodyCertificationMode::Raw,
+      methods: vec![],
     },
     response: DefaultResponseCertification::certified_response_headers(&[
       "ETag",
       "Cache-Control",
     ]),
+    response_header_options: ResponseHeaderCertificationOptions::default(),
   }));
 ```
 
@@ -426,8 +678,7 @@ default_certification (
 Request certification can be skipped entirely by using the [ResponseOnly](DefaultCelExpression::ResponseOnly) variant of the [DefaultCelExpression](DefaultCelExpression). For example:
 
 ```rust
-use std::borrow::Cow;
-use ic_http_certification::cel::{CelExpression, DefaultCelExpression, DefaultResponseOnlyCelExpression, DefaultResponseCertification};
+use ic_http_certification::cel::{CelExpression, DefaultCelExpression, DefaultResponseOnlyCelExpression, DefaultResponseCertification, ResponseHeaderCertificationOptions};
 
 let cel_expr = CelExpression::Default(DefaultCelExpression::ResponseOnly(
   DefaultResponseOnlyCelExpression {
@@ -435,6 +686,7 @@ let cel_expr = CelExpression::Default(DefaultCelExpression::ResponseOnly(
       "ETag",
       "Cache-Control",
     ]),
+    response_header_options: ResponseHeaderCertificationOptions::default(),
   }));
 ```
 
@@ -461,17 +713,17 @@ default_certification (
 Similiarly to request certification, any number of response headers can be provided via the [certified_response_headers](DefaultResponseCertification::certified_response_headers) associated function of the [DefaultResponseCertification](DefaultResponseCertification) enum, and it can also be an empty array. If the array is empty, no response headers will be certified. For example:
 
 ```rust
-use std::borrow::Cow;
-use ic_http_certification::cel::{CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification, DefaultResponseCertification};
+use ic_http_certification::cel::{CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification, DefaultResponseCertification, ResponseHeaderCertificationOptions};
 
 
 let cel_expr = CelExpression::Default(DefaultCelExpression::Full(
   DefaultFullCelExpression {
-    request: DefaultRequestCertification {
-      headers: Cow::Borrowed(&["Accept", "Accept-Encoding", "If-None-Match"]),
-      query_parameters: Cow::Borrowed(&["foo", "bar", "baz"]),
-    },
+    request: DefaultRequestCertification::new(
+      ["Accept", "Accept-Encoding", "If-None-Match"],
+      ["foo", "bar", "baz"],
+    ),
     response: DefaultResponseCertification::certified_response_headers(&[]),
+    response_header_options: ResponseHeaderCertificationOptions::default(),
   }));
 ```
 
@@ -496,16 +748,16 @@ default_certification (
 If the [response_header_exclusions](DefaultResponseCertification::response_header_exclusions) associated function is used, an empty array will certify _all_ response headers. For example:
 
 ```rust
-use std::borrow::Cow;
-use ic_http_certification::cel::{CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification, DefaultResponseCertification};
+use ic_http_certification::cel::{CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification, DefaultResponseCertification, ResponseHeaderCertificationOptions};
 
 let cel_expr = CelExpression::Default(DefaultCelExpression::Full(
   DefaultFullCelExpression {
-    request: DefaultRequestCertification {
-      headers: Cow::Borrowed(&["Accept", "Accept-Encoding", "If-None-Match"]),
-      query_parameters: Cow::Borrowed(&["foo", "bar", "baz"]),
-    },
+    request: DefaultRequestCertification::new(
+      ["Accept", "Accept-Encoding", "If-None-Match"],
+      ["foo", "bar", "baz"],
+    ),
     response: DefaultResponseCertification::response_header_exclusions(&[]),
+    response_header_options: ResponseHeaderCertificationOptions::default(),
   }));
 ```
 
@@ -548,6 +800,83 @@ default_certification (
   }
 )
 ```
+
+### Conditional certification
+
+A [ConditionalCelExpression](cel::ConditionalCelExpression) selects between two CEL expressions based on a [condition](cel::CelCondition) that is evaluated against the request and the response, for example to only certify a header if the response was successful, or to only certify query parameters for `GET` requests. Conditions support a subset of CEL, including boolean logic, comparisons, request and response attributes such as `request.method`, `request.headers` and `response.status_code`, and string and list functions such as `startsWith` and `size`.
+
+The hash of the whole expression is certified, while the request and response hashes are calculated according to the expression that the condition selects. Use [HttpCertification::from_cel_expr](HttpCertification::from_cel_expr()) to create a certification from a conditional expression, so that the canister selects the same expression as the verifier. Conditions that access the response are not allowed if any branch skips certification, since the response could then be modified to select that branch.
+
+```rust
+use ic_http_certification::{
+  CelExpression, DefaultCelBuilder, DefaultResponseCertification, HttpCertification, HttpRequest, HttpResponse,
+  cel::{CelAttribute, CelBinaryOperator, CelCondition, ConditionalCelExpression, DefaultCelExpression},
+};
+
+let cel_expr = CelExpression::Conditional(ConditionalCelExpression {
+  condition: CelCondition::binary(
+    CelBinaryOperator::Eq,
+    CelCondition::Attribute(CelAttribute::ResponseStatusCode),
+    CelCondition::Int(200),
+  ),
+  if_true: Box::new(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+    DefaultCelBuilder::response_only_certification()
+      .with_response_certification(DefaultResponseCertification::certified_response_headers(&["Cache-Control"]))
+      .build(),
+  ))),
+  if_false: Box::new(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+    DefaultCelBuilder::response_only_certification().build(),
+  ))),
+});
+
+let request = HttpRequest {
+  method: "GET".to_string(),
+  url: "/".to_string(),
+  headers: vec![],
+  body: vec![],
+};
+let response = HttpResponse {
+  status_code: 200,
+  headers: vec![
+    ("IC-CertificateExpression".to_string(), cel_expr.to_string()),
+    ("Cache-Control".to_string(), "no-cache".to_string()),
+  ],
+  body: vec![],
+  upgrade: None,
+};
+
+let certification = HttpCertification::from_cel_expr(&cel_expr, &request, &response, None).unwrap();
+```
+
+This will produce the following CEL expression, where each `default_certification` call is the same as in the previous examples:
+
+```protobuf
+response.status_code == 200 ? default_certification(...) : default_certification(...)
+```
+
+Conditions for common route-level rules can be created with [CelCondition::request_method_in](cel::CelCondition::request_method_in()) and [CelCondition::response_status_code_class](cel::CelCondition::response_status_code_class()). For example, `CelCondition::response_status_code_class(5)` matches all server errors, so it can be used to certify server errors differently from other responses. Since conditions that access the response are not allowed if any branch skips certification, server errors can never be left uncertified this way.
+
+### Building CEL expressions at runtime
+
+The CEL expression types store header and query parameter names as [Cow](std::borrow::Cow) strings, so they can either borrow `'static` string slices, as in the examples above, or own strings that are only known at runtime, such as configuration that is passed to a canister's `init` hook. Expressions, paths and tree entries can be converted into owned versions with their `into_owned` methods, so they can be stored in a canister's state without borrowing from it.
+
+```rust
+use ic_http_certification::{DefaultFullCelExpression, DefaultResponseCertification, cel::{CelExpression, DefaultCelExpression, DefaultRequestCertification, ResponseHeaderCertificationOptions}};
+use std::borrow::Cow;
+
+let request_headers: Vec<String> = vec!["Accept".to_string(), "Accept-Encoding".to_string()];
+let response_headers: Vec<String> = vec!["Cache-Control".to_string(), "ETag".to_string()];
+
+let cel_expr: CelExpression<'static> = CelExpression::Default(DefaultCelExpression::Full(
+  DefaultFullCelExpression {
+    request: DefaultRequestCertification::new(request_headers, ["foo", "bar", "baz"]),
+    response: DefaultResponseCertification::CertifiedResponseHeaders(
+      response_headers.into_iter().map(Cow::Owned).collect(),
+    ),
+    response_header_options: ResponseHeaderCertificationOptions::default(),
+  },
+)).into_owned();
+```
 */
 
 #![deny(missing_docs, missing_debug_implementations, rustdoc::all, clippy::all)]
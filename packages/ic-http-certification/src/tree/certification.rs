@@ -1,10 +1,14 @@
+use super::certification_validation::{validate_request, validate_response};
 use crate::{
-    request_hash, response_hash, DefaultCelBuilder, DefaultFullCelExpression,
-    DefaultResponseOnlyCelExpression, HttpCertificationResult, HttpRequest, HttpResponse,
+    cel::DefaultCelExpression, request_hash, response_hash_with_options, CelExpression,
+    DefaultCelBuilder, DefaultFullCelExpression, DefaultResponseOnlyCelExpression,
+    HttpCertificationResult, HttpRequest, HttpResponse,
 };
 use ic_certification::Hash;
 use ic_representation_independent_hash::hash;
 
+const HEAD_METHOD: &str = "HEAD";
+
 /// A certified [request](crate::HttpResponse) and [response](crate::HttpResponse) pair.
 ///
 /// It contains three variants:
@@ -21,7 +25,7 @@ use ic_representation_independent_hash::hash;
 /// - The [Full](HttpCertification::Full) variant includes both an [HTTP response](crate::HttpResponse) and
 /// the corresponding [HTTP request](crate::HttpRequest) in certification. Create this variant using
 /// the [full()](HttpCertification::full()) function.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HttpCertification {
     /// A certification that excludes both the [HTTP request](crate::HttpRequest) and
     /// the corresponding [HTTP response](crate::HttpResponse).
@@ -141,32 +145,72 @@ impl HttpCertification {
     /// Creates the [ResponseOnly](HttpCertification::ResponseOnly) variant of the [HttpCertification] enum,
     /// including an [HTTP response](crate::HttpResponse) but excluding the corresponding
     /// [HTTP request](crate::HttpRequest) from certification.
+    ///
+    /// The response is validated before it is certified, and an [HttpCertificationError](crate::HttpCertificationError)
+    /// is returned if the certification could never be verified:
+    ///
+    /// - [CertificateHeaderPresent](crate::HttpCertificationError::CertificateHeaderPresent) if the response already
+    ///   has an `IC-Certificate` header.
+    /// - [CertificateExpressionMismatch](crate::HttpCertificationError::CertificateExpressionMismatch) if the response
+    ///   has an `IC-CertificateExpression` header that is different from the CEL expression.
+    /// - [ExcludedHeaderCertified](crate::HttpCertificationError::ExcludedHeaderCertified) if the `IC-Certificate` or
+    ///   `IC-CertificateExpression` headers are listed as certified response headers.
+    /// - [DuplicateHeaderName](crate::HttpCertificationError::DuplicateHeaderName) if a response header name is
+    ///   listed more than once, ignoring case.
+    /// - [InvalidHeaderNamePattern](crate::HttpCertificationError::InvalidHeaderNamePattern) if a response header
+    ///   name pattern has a `*` wildcard that is not its last character.
+    /// - [UnsupportedContentEncoding](crate::HttpCertificationError::UnsupportedContentEncoding) if the
+    ///   `Content-Encoding` header of the response lists an encoding other than `gzip`, `deflate`, `br` or `identity`.
     pub fn response_only(
         cel_expr: &DefaultResponseOnlyCelExpression,
         response: &HttpResponse,
         response_body_hash: Option<Hash>,
-    ) -> HttpCertification {
-        let cel_expr_hash = hash(cel_expr.to_string().as_bytes());
-        let response_hash = response_hash(response, &cel_expr.response, response_body_hash);
+    ) -> HttpCertificationResult<HttpCertification> {
+        let cel_expr_str = cel_expr.to_string();
+        validate_response(&cel_expr_str, response, &cel_expr.response)?;
 
-        HttpCertification::ResponseOnly {
+        let cel_expr_hash = hash(cel_expr_str.as_bytes());
+        let response_hash = response_hash_with_options(
+            response,
+            &cel_expr.response,
+            &cel_expr.response_header_options,
+            response_body_hash,
+        );
+
+        Ok(HttpCertification::ResponseOnly {
             cel_expr_hash,
             response_hash,
-        }
+        })
     }
 
     /// Creates the [Full](HttpCertification::Full) variant of the [HttpCertification] enum, including both an
     /// [HTTP request](crate::HttpRequest) and the corresponding [HTTP request](crate::HttpRequest)
     /// in certification.
+    ///
+    /// The request and response are validated before they are certified. In addition to the errors that are
+    /// returned by [response_only](HttpCertification::response_only()), a
+    /// [MalformedUrl](crate::HttpCertificationError::MalformedUrl) error is returned if the request URL can not be
+    /// parsed, and a [DuplicateHeaderName](crate::HttpCertificationError::DuplicateHeaderName) or
+    /// [InvalidHeaderNamePattern](crate::HttpCertificationError::InvalidHeaderNamePattern) error is returned if
+    /// a request header name is listed more than once, ignoring case, or is not a valid header name pattern.
     pub fn full(
         cel_expr: &DefaultFullCelExpression,
         request: &HttpRequest,
         response: &HttpResponse,
         response_body_hash: Option<Hash>,
     ) -> HttpCertificationResult<HttpCertification> {
-        let cel_expr_hash = hash(cel_expr.to_string().as_bytes());
+        let cel_expr_str = cel_expr.to_string();
+        validate_request(request, &cel_expr.request)?;
+        validate_response(&cel_expr_str, response, &cel_expr.response)?;
+
+        let cel_expr_hash = hash(cel_expr_str.as_bytes());
         let request_hash = request_hash(request, &cel_expr.request)?;
-        let response_hash = response_hash(response, &cel_expr.response, response_body_hash);
+        let response_hash = response_hash_with_options(
+            response,
+            &cel_expr.response,
+            &cel_expr.response_header_options,
+            response_body_hash,
+        );
 
         Ok(HttpCertification::Full {
             cel_expr_hash,
@@ -175,6 +219,107 @@ impl HttpCertification {
         })
     }
 
+    /// Creates the [ResponseOnly](HttpCertification::ResponseOnly) variant of the [HttpCertification] enum
+    /// for the response to a `HEAD` request, from the [HTTP response](crate::HttpResponse) to the corresponding
+    /// `GET` request.
+    ///
+    /// A response to a `HEAD` request has the same status code and headers as the response to a `GET` request,
+    /// but it has an empty body. The response is certified with an empty body, regardless of its actual body.
+    pub fn response_only_head(
+        cel_expr: &DefaultResponseOnlyCelExpression,
+        response: &HttpResponse,
+    ) -> HttpCertificationResult<HttpCertification> {
+        HttpCertification::response_only(cel_expr, response, Some(hash(&[])))
+    }
+
+    /// Creates the [Full](HttpCertification::Full) variant of the [HttpCertification] enum for the response
+    /// to a `HEAD` request, from the [HTTP request](crate::HttpRequest) and [HTTP response](crate::HttpResponse)
+    /// of the corresponding `GET` request.
+    ///
+    /// The request is certified with the `HEAD` method and the response is certified with an empty body,
+    /// regardless of their actual method and body. If the [methods](crate::cel::DefaultRequestCertification::methods)
+    /// of the CEL expression include both `GET` and `HEAD`, the request hash is the same as the request hash of the
+    /// `GET` request, so both certifications share the same CEL expression and request hash.
+    /// If the [methods](crate::cel::DefaultRequestCertification::methods) are not empty, but do not include `HEAD`,
+    /// an [UncertifiedRequestMethod](crate::HttpCertificationError::UncertifiedRequestMethod) error is returned.
+    pub fn full_head(
+        cel_expr: &DefaultFullCelExpression,
+        request: &HttpRequest,
+        response: &HttpResponse,
+    ) -> HttpCertificationResult<HttpCertification> {
+        let head_request = HttpRequest {
+            method: HEAD_METHOD.to_string(),
+            ..request.clone()
+        };
+
+        HttpCertification::full(cel_expr, &head_request, response, Some(hash(&[])))
+    }
+
+    /// Creates an [HttpCertification] from any [CelExpression], including
+    /// [conditional expressions](crate::cel::ConditionalCelExpression).
+    ///
+    /// The expression is [evaluated](CelExpression::evaluate()) against the [HTTP request](crate::HttpRequest)
+    /// and [HTTP response](crate::HttpResponse) to select a [DefaultCelExpression]. The request and response
+    /// hashes are calculated according to the selected expression, while the CEL expression hash is calculated
+    /// from the whole expression, since that is the expression that is sent to the verifier.
+    /// The request and response are validated in the same way as by [full](HttpCertification::full()) and
+    /// [response_only](HttpCertification::response_only()), depending on the selected expression.
+    pub fn from_cel_expr(
+        cel_expr: &CelExpression,
+        request: &HttpRequest,
+        response: &HttpResponse,
+        response_body_hash: Option<Hash>,
+    ) -> HttpCertificationResult<HttpCertification> {
+        let cel_expr_str = cel_expr.to_string();
+        let cel_expr_hash = hash(cel_expr_str.as_bytes());
+
+        let certification = match cel_expr.evaluate(request, response)? {
+            DefaultCelExpression::Skip => HttpCertification::Skip { cel_expr_hash },
+            DefaultCelExpression::ResponseOnly(cel_expr) => {
+                validate_response(&cel_expr_str, response, &cel_expr.response)?;
+
+                HttpCertification::ResponseOnly {
+                    cel_expr_hash,
+                    response_hash: response_hash_with_options(
+                        response,
+                        &cel_expr.response,
+                        &cel_expr.response_header_options,
+                        response_body_hash,
+                    ),
+                }
+            }
+            DefaultCelExpression::Full(cel_expr) => {
+                validate_request(request, &cel_expr.request)?;
+                validate_response(&cel_expr_str, response, &cel_expr.response)?;
+
+                HttpCertification::Full {
+                    cel_expr_hash,
+                    request_hash: request_hash(request, &cel_expr.request)?,
+                    response_hash: response_hash_with_options(
+                        response,
+                        &cel_expr.response,
+                        &cel_expr.response_header_options,
+                        response_body_hash,
+                    ),
+                }
+            }
+        };
+
+        Ok(certification)
+    }
+
+    /// Returns the hash of the CEL expression that was used to create this certification.
+    ///
+    /// The `IC-CertificateExpression` header that is served with the certified response must hash to this value,
+    /// [lint_cel_expr](crate::cel::lint_cel_expr()) can be used to check this.
+    pub fn cel_expr_hash(&self) -> Hash {
+        match self {
+            HttpCertification::Skip { cel_expr_hash }
+            | HttpCertification::ResponseOnly { cel_expr_hash, .. }
+            | HttpCertification::Full { cel_expr_hash, .. } => *cel_expr_hash,
+        }
+    }
+
     pub(crate) fn to_tree_path(&self) -> Vec<Vec<u8>> {
         match self {
             HttpCertification::Skip { cel_expr_hash } => vec![cel_expr_hash.to_vec()],
@@ -197,12 +342,37 @@ impl HttpCertification {
             ],
         }
     }
+
+    pub(crate) fn from_tree_path(tree_path: &[impl AsRef<[u8]>]) -> Option<HttpCertification> {
+        let to_hash = |segment: &[u8]| Hash::try_from(segment).ok();
+
+        match tree_path {
+            [cel_expr_hash] => Some(HttpCertification::Skip {
+                cel_expr_hash: to_hash(cel_expr_hash.as_ref())?,
+            }),
+            [cel_expr_hash, request_hash, response_hash] if request_hash.as_ref().is_empty() => {
+                Some(HttpCertification::ResponseOnly {
+                    cel_expr_hash: to_hash(cel_expr_hash.as_ref())?,
+                    response_hash: to_hash(response_hash.as_ref())?,
+                })
+            }
+            [cel_expr_hash, request_hash, response_hash] => Some(HttpCertification::Full {
+                cel_expr_hash: to_hash(cel_expr_hash.as_ref())?,
+                request_hash: to_hash(request_hash.as_ref())?,
+                response_hash: to_hash(response_hash.as_ref())?,
+            }),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::DefaultResponseCertification;
+    use crate::{
+        cel::{CelAttribute, CelBinaryOperator, CelCondition, ConditionalCelExpression},
+        response_hash, DefaultResponseCertification, HttpCertificationError,
+    };
     use rstest::*;
 
     #[rstest]
@@ -217,6 +387,11 @@ mod tests {
             HttpCertification::Skip { cel_expr_hash } if cel_expr_hash == expected_cel_expr_hash
         ));
         assert_eq!(result.to_tree_path(), vec![expected_cel_expr_hash.to_vec()]);
+        assert_eq!(result.cel_expr_hash(), expected_cel_expr_hash);
+        assert_eq!(
+            HttpCertification::from_tree_path(&result.to_tree_path()),
+            Some(result)
+        );
     }
 
     #[rstest]
@@ -234,9 +409,14 @@ mod tests {
             headers: vec![],
             upgrade: None,
         };
-        let expected_response_hash = response_hash(response, &cel_expr.response, None);
+        let expected_response_hash = response_hash_with_options(
+            response,
+            &cel_expr.response,
+            &cel_expr.response_header_options,
+            None,
+        );
 
-        let result = HttpCertification::response_only(&cel_expr, response, None);
+        let result = HttpCertification::response_only(&cel_expr, response, None).unwrap();
 
         assert!(matches!(
             result,
@@ -254,6 +434,168 @@ mod tests {
                 expected_response_hash.to_vec()
             ]
         );
+        assert_eq!(result.cel_expr_hash(), expected_cel_expr_hash);
+        assert_eq!(
+            HttpCertification::from_tree_path(&result.to_tree_path()),
+            Some(result)
+        );
+    }
+
+    #[rstest]
+    fn response_only_head_certification() {
+        let cel_expr = DefaultCelBuilder::response_only_certification().build();
+        let response = &HttpResponse {
+            status_code: 200,
+            body: b"Hello World!".to_vec(),
+            headers: vec![],
+            upgrade: None,
+        };
+        let head_response = &HttpResponse {
+            body: vec![],
+            ..response.clone()
+        };
+
+        let result = HttpCertification::response_only_head(&cel_expr, response).unwrap();
+
+        assert_eq!(
+            result,
+            HttpCertification::response_only(&cel_expr, head_response, None).unwrap()
+        );
+        assert_ne!(
+            result,
+            HttpCertification::response_only(&cel_expr, response, None).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn full_head_certification_shares_request_hash() {
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_methods(&["GET", "HEAD"])
+            .build();
+        let request = &HttpRequest {
+            body: vec![],
+            headers: vec![],
+            method: "GET".to_string(),
+            url: "/index.html".to_string(),
+        };
+        let response = &HttpResponse {
+            status_code: 200,
+            body: b"Hello World!".to_vec(),
+            headers: vec![],
+            upgrade: None,
+        };
+        let head_request = &HttpRequest {
+            method: "HEAD".to_string(),
+            ..request.clone()
+        };
+        let head_response = &HttpResponse {
+            body: vec![],
+            ..response.clone()
+        };
+
+        let get_certification =
+            HttpCertification::full(&cel_expr, request, response, None).unwrap();
+        let result = HttpCertification::full_head(&cel_expr, request, response).unwrap();
+
+        assert_eq!(
+            result,
+            HttpCertification::full(&cel_expr, head_request, head_response, None).unwrap()
+        );
+        assert!(matches!(
+            (&result, &get_certification),
+            (
+                HttpCertification::Full { request_hash, response_hash, .. },
+                HttpCertification::Full { request_hash: get_request_hash, response_hash: get_response_hash, .. },
+            ) if request_hash == get_request_hash && response_hash != get_response_hash
+        ));
+    }
+
+    #[rstest]
+    fn full_head_certification_with_uncertified_head_method() {
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_methods(&["GET"])
+            .build();
+        let request = &HttpRequest {
+            body: vec![],
+            headers: vec![],
+            method: "GET".to_string(),
+            url: "/index.html".to_string(),
+        };
+        let response = &HttpResponse {
+            status_code: 200,
+            body: vec![],
+            headers: vec![],
+            upgrade: None,
+        };
+
+        let result = HttpCertification::full_head(&cel_expr, request, response);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::UncertifiedRequestMethod { method, .. }) if method == "HEAD"
+        ));
+    }
+
+    #[rstest]
+    fn full_certification_with_invalid_request_and_response() {
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(&["Accept", "accept"])
+            .build();
+        let request = &HttpRequest {
+            body: vec![],
+            headers: vec![],
+            method: "GET".to_string(),
+            url: "/index.html".to_string(),
+        };
+        let response = &HttpResponse {
+            status_code: 200,
+            body: vec![],
+            headers: vec![("IC-Certificate".to_string(), "certificate=::".to_string())],
+            upgrade: None,
+        };
+
+        let result = HttpCertification::full(&cel_expr, request, response, None);
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::DuplicateHeaderName(header_name)) if header_name == "accept"
+        ));
+
+        let cel_expr = DefaultCelBuilder::full_certification().build();
+        let result = HttpCertification::full(&cel_expr, request, response, None);
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::CertificateHeaderPresent)
+        ));
+    }
+
+    #[rstest]
+    fn from_cel_expr_validates_certificate_expression_header() {
+        let cel_expr = CelExpression::Default(DefaultCelExpression::ResponseOnly(
+            DefaultCelBuilder::response_only_certification().build(),
+        ));
+        let request = &HttpRequest {
+            body: vec![],
+            headers: vec![],
+            method: "GET".to_string(),
+            url: "/index.html".to_string(),
+        };
+        let response = &HttpResponse {
+            status_code: 200,
+            body: vec![],
+            headers: vec![(
+                "IC-CertificateExpression".to_string(),
+                DefaultCelBuilder::skip_certification().to_string(),
+            )],
+            upgrade: None,
+        };
+
+        let result = HttpCertification::from_cel_expr(&cel_expr, request, response, None);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::CertificateExpressionMismatch { expected, .. })
+                if expected == cel_expr.to_string()
+        ));
     }
 
     #[rstest]
@@ -281,7 +623,12 @@ mod tests {
             headers: vec![],
             upgrade: None,
         };
-        let expected_response_hash = response_hash(response, &cel_expr.response, None);
+        let expected_response_hash = response_hash_with_options(
+            response,
+            &cel_expr.response,
+            &cel_expr.response_header_options,
+            None,
+        );
 
         let result = HttpCertification::full(&cel_expr, request, response, None).unwrap();
 
@@ -303,5 +650,106 @@ mod tests {
                 expected_response_hash.to_vec()
             ]
         );
+        assert_eq!(result.cel_expr_hash(), expected_cel_expr_hash);
+        assert_eq!(
+            HttpCertification::from_tree_path(&result.to_tree_path()),
+            Some(result)
+        );
+    }
+
+    #[rstest]
+    #[case::get("GET", true)]
+    #[case::post("POST", false)]
+    fn conditional_certification(#[case] method: &str, #[case] is_full: bool) {
+        let full_cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_query_parameters(&["page"])
+            .build();
+        let response_only_cel_expr = DefaultCelBuilder::response_only_certification().build();
+        let cel_expr = CelExpression::Conditional(ConditionalCelExpression {
+            condition: CelCondition::binary(
+                CelBinaryOperator::Eq,
+                CelCondition::Attribute(CelAttribute::RequestMethod),
+                CelCondition::string("GET"),
+            ),
+            if_true: Box::new(CelExpression::Default(DefaultCelExpression::Full(
+                full_cel_expr.clone(),
+            ))),
+            if_false: Box::new(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+                response_only_cel_expr.clone(),
+            ))),
+        });
+        let expected_cel_expr_hash = hash(cel_expr.to_string().as_bytes());
+
+        let request = &HttpRequest {
+            body: vec![],
+            headers: vec![],
+            method: method.to_string(),
+            url: "/index.html?page=1".to_string(),
+        };
+        let response = &HttpResponse {
+            status_code: 200,
+            body: vec![],
+            headers: vec![],
+            upgrade: None,
+        };
+
+        let result = HttpCertification::from_cel_expr(&cel_expr, request, response, None).unwrap();
+
+        let expected_result = if is_full {
+            HttpCertification::Full {
+                cel_expr_hash: expected_cel_expr_hash,
+                request_hash: request_hash(request, &full_cel_expr.request).unwrap(),
+                response_hash: response_hash(response, &full_cel_expr.response, None),
+            }
+        } else {
+            HttpCertification::ResponseOnly {
+                cel_expr_hash: expected_cel_expr_hash,
+                response_hash: response_hash(response, &response_only_cel_expr.response, None),
+            }
+        };
+        assert_eq!(result, expected_result);
+    }
+
+    #[rstest]
+    fn default_cel_expr_certification() {
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(&["If-Match"])
+            .build();
+
+        let request = &HttpRequest {
+            body: vec![],
+            headers: vec![],
+            method: "GET".to_string(),
+            url: "/index.html".to_string(),
+        };
+        let response = &HttpResponse {
+            status_code: 200,
+            body: vec![],
+            headers: vec![],
+            upgrade: None,
+        };
+
+        let result = HttpCertification::from_cel_expr(
+            &CelExpression::Default(DefaultCelExpression::Full(cel_expr.clone())),
+            request,
+            response,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            result,
+            HttpCertification::full(&cel_expr, request, response, None).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case::empty(vec![])]
+    #[case::invalid_cel_expr_hash(vec![vec![1, 2, 3]])]
+    #[case::invalid_response_hash(vec![vec![0; 32], vec![], vec![1, 2, 3]])]
+    #[case::invalid_request_hash(vec![vec![0; 32], vec![1, 2, 3], vec![0; 32]])]
+    #[case::too_many_segments(vec![vec![0; 32]; 4])]
+    fn from_invalid_tree_path(#[case] tree_path: Vec<Vec<u8>>) {
+        assert_eq!(HttpCertification::from_tree_path(&tree_path), None);
     }
 }
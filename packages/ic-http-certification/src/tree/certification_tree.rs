@@ -1,16 +1,33 @@
 use super::{
     certification_tree_entry::HttpCertificationTreeEntry,
+    certification_tree_item::HttpCertificationTreeItem,
     certification_tree_path::{CertificationTreePathSegment, PATH_PREFIX_BYTES},
 };
-use crate::{tree::WILDCARD_PATH_TERMINATOR_BYTES, HttpCertificationPath};
+use crate::{
+    tree::WILDCARD_PATH_TERMINATOR_BYTES, HttpCertification, HttpCertificationError,
+    HttpCertificationPath, HttpCertificationResult,
+};
 use ic_certification::{
-    empty, labeled, labeled_hash, merge_hash_trees, AsHashTree, HashTree, NestedTree,
+    empty, labeled, labeled_hash, pruned, try_merge_hash_trees, AsHashTree, HashTree, NestedTree,
+    RbTree,
 };
 use ic_representation_independent_hash::Sha256Digest;
 use std::borrow::Borrow;
 
 type CertificationTree = NestedTree<CertificationTreePathSegment, Vec<u8>>;
 
+/// The version of the encoding used by [to_bytes](HttpCertificationTree::to_bytes).
+const TREE_ENCODING_VERSION: u8 = 1;
+
+/// The maximum nesting depth of subtrees accepted by [from_bytes](HttpCertificationTree::from_bytes).
+const MAX_DEPTH: usize = 128;
+
+/// The tag of an encoded leaf of a [CertificationTree].
+const LEAF_NODE_TAG: u8 = 0;
+
+/// The tag of an encoded nested subtree of a [CertificationTree].
+const NESTED_NODE_TAG: u8 = 1;
+
 /// A certification tree for generic HTTP requests.
 #[derive(Debug)]
 pub struct HttpCertificationTree {
@@ -44,6 +61,23 @@ impl HttpCertificationTree {
         self.tree.insert(&tree_path, vec![]);
     }
 
+    /// Inserts multiple [HttpCertificationTreeEntry]s into the tree.
+    /// This is equivalent to calling [insert](HttpCertificationTree::insert) for every entry,
+    /// but the hashes of the tree are only recomputed once for the whole batch.
+    /// After performing this operation, the canister's certified variable will need to be updated
+    /// with the new [root hash](HttpCertificationTree::root_hash) of the tree.
+    pub fn insert_many<'a: 'b, 'b>(
+        &mut self,
+        entries: impl IntoIterator<Item = &'b HttpCertificationTreeEntry<'a>>,
+    ) {
+        let tree_paths: Vec<_> = entries
+            .into_iter()
+            .map(|entry| entry.to_tree_path())
+            .collect();
+        self.tree
+            .insert_many(tree_paths.iter().map(|tree_path| (&tree_path[..], vec![])));
+    }
+
     /// Deletes a given [HttpCertificationTreeEntry] from the tree.
     /// After performing this operation, the canister's certified variable will need to be updated
     /// with the new [root hash](HttpCertificationTree::root_hash) of the tree.
@@ -52,16 +86,169 @@ impl HttpCertificationTree {
         self.tree.delete(&tree_path);
     }
 
+    /// Deletes multiple [HttpCertificationTreeEntry]s from the tree.
+    /// This is equivalent to calling [delete](HttpCertificationTree::delete) for every entry,
+    /// but the hashes of the tree are only recomputed once for the whole batch.
+    /// After performing this operation, the canister's certified variable will need to be updated
+    /// with the new [root hash](HttpCertificationTree::root_hash) of the tree.
+    pub fn delete_many<'a: 'b, 'b>(
+        &mut self,
+        entries: impl IntoIterator<Item = &'b HttpCertificationTreeEntry<'a>>,
+    ) {
+        let tree_paths: Vec<_> = entries
+            .into_iter()
+            .map(|entry| entry.to_tree_path())
+            .collect();
+        self.tree
+            .delete_many(tree_paths.iter().map(|tree_path| &tree_path[..]));
+    }
+
+    /// Replaces all entries in the tree with the given [HttpCertificationTreeEntry]s.
+    /// The tree is rebuilt from scratch in linear time, which makes this the most efficient way
+    /// to certify a large number of entries at once, for example in a canister's `init` or
+    /// `post_upgrade` hooks. The resulting tree has the same root hash as a tree that the entries
+    /// are [inserted](HttpCertificationTree::insert) into in ascending order of their paths.
+    /// After performing this operation, the canister's certified variable will need to be updated
+    /// with the new [root hash](HttpCertificationTree::root_hash) of the tree.
+    pub fn replace<'a: 'b, 'b>(
+        &mut self,
+        entries: impl IntoIterator<Item = &'b HttpCertificationTreeEntry<'a>>,
+    ) {
+        let mut tree_paths: Vec<_> = entries
+            .into_iter()
+            .map(|entry| entry.to_tree_path())
+            .collect();
+        tree_paths.sort();
+        tree_paths.dedup();
+
+        self.tree = CertificationTree::from_sorted(
+            tree_paths.into_iter().map(|tree_path| (tree_path, vec![])),
+        );
+    }
+
+    /// Returns true if the given [HttpCertificationTreeEntry] is present in the tree.
+    pub fn contains(&self, entry: &HttpCertificationTreeEntry) -> bool {
+        self.tree.contains_leaf(&entry.to_tree_path())
+    }
+
+    /// Returns all [HttpCertification]s that are present in the tree for the given [HttpCertificationPath].
+    ///
+    /// Only certifications inserted with exactly the same path are returned, certifications that are
+    /// inserted with a [wildcard](HttpCertificationPath::Wildcard) path matching the given path are not.
+    pub fn get(&self, path: &HttpCertificationPath) -> Vec<HttpCertification> {
+        let mut certifications = vec![];
+        if let Some(subtree) = self.tree.get_subtree(&path.to_tree_path()) {
+            subtree.for_each_leaf(|tree_path, _| {
+                if let Some(certification) = HttpCertification::from_tree_path(tree_path) {
+                    certifications.push(certification);
+                }
+            });
+        }
+
+        certifications
+    }
+
+    /// Returns an iterator over all entries that are present in the tree, decoded into
+    /// [HttpCertificationTreeItem]s, in the order of their paths in the tree.
+    ///
+    /// This can be used to debug which certifications are present in the tree, or to find and
+    /// [delete](HttpCertificationTree::delete) certifications that are no longer needed.
+    /// Leaves of the tree that cannot be decoded into an [HttpCertificationTreeItem], which can only
+    /// happen if the tree was created from a malformed [CertificationTree](HttpCertificationTree::new),
+    /// are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = HttpCertificationTreeItem> {
+        let mut items = vec![];
+        self.tree.for_each_leaf(|tree_path, _| {
+            if let Some(item) = HttpCertificationTreeItem::from_tree_path(tree_path) {
+                items.push(item);
+            }
+        });
+
+        items.into_iter()
+    }
+
+    /// Serializes the tree into bytes that can be persisted across canister upgrades,
+    /// for example by writing them to stable memory in a canister's `pre_upgrade` hook.
+    /// The tree can be restored from these bytes with [from_bytes](HttpCertificationTree::from_bytes).
+    ///
+    /// The entries already contain the hashes of their certified requests and responses, so only the
+    /// nested structure of the tree is serialized, together with the
+    /// [levels](ic_certification::RbTree::entries_with_levels) that determine its shape.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![TREE_ENCODING_VERSION];
+        write_node(&mut bytes, &self.tree);
+
+        bytes
+    }
+
+    /// Deserializes a tree from bytes that were produced by [to_bytes](HttpCertificationTree::to_bytes),
+    /// for example after reading them from stable memory in a canister's `post_upgrade` hook.
+    ///
+    /// The tree is rebuilt in linear time without needing to recompute the certification of any response.
+    /// The restored tree has exactly the same shape as the serialized tree, and therefore the same
+    /// [root hash](HttpCertificationTree::root_hash), so witnesses that were issued for the serialized tree
+    /// remain valid.
+    ///
+    /// Returns [MalformedCertificationTree](HttpCertificationError::MalformedCertificationTree) if the bytes
+    /// are not a valid encoding, including when subtrees are nested more than 128 levels deep.
+    pub fn from_bytes(bytes: &[u8]) -> HttpCertificationResult<Self> {
+        let mut reader = TreeBytesReader { bytes };
+
+        let version = reader.read_u8()?;
+        if version != TREE_ENCODING_VERSION {
+            return Err(HttpCertificationError::MalformedCertificationTree(format!(
+                "Unsupported encoding version {}",
+                version
+            )));
+        }
+
+        let tree = read_node(&mut reader, 0)?;
+
+        if !reader.bytes.is_empty() {
+            return Err(HttpCertificationError::MalformedCertificationTree(format!(
+                "Unexpected {} trailing bytes",
+                reader.bytes.len()
+            )));
+        }
+
+        Ok(Self::new(tree))
+    }
+
     /// Returns a pruned [HashTree] that will prove the presence of a given [HttpCertificationTreeEntry]
     /// in the full [HttpCertificationTree], without needing to return the full tree.
     ///
     /// `request_url` is required so that the witness can be generated with respect to the request URL.
     pub fn witness(&self, entry: &HttpCertificationTreeEntry, request_url: &str) -> HashTree {
-        let witness = match entry.path.borrow() {
+        labeled(PATH_PREFIX_BYTES, self.inner_witness(entry, request_url))
+    }
+
+    /// Returns a single pruned [HashTree] that will prove the presence of all of the given
+    /// [HttpCertificationTreeEntry]s in the full [HttpCertificationTree], together with their
+    /// corresponding request URLs.
+    ///
+    /// This is equivalent to [merging](ic_certification::try_merge_hash_trees) the
+    /// [witnesses](HttpCertificationTree::witness) of every entry, and can be used to certify several
+    /// responses with a single certificate, for example when bundling a page together with its assets.
+    /// If no entries are given, the witness contains only the root hash of the tree.
+    pub fn witness_many(&self, entries: &[(&HttpCertificationTreeEntry, &str)]) -> HashTree {
+        let witness = entries.iter().fold(
+            pruned(self.tree.root_hash()),
+            |acc, (entry, request_url)| {
+                // every witness is generated from this tree, so they are always consistent
+                try_merge_hash_trees(acc, self.inner_witness(entry, request_url))
+                    .expect("witnesses of the same tree are always consistent")
+            },
+        );
+
+        labeled(PATH_PREFIX_BYTES, witness)
+    }
+
+    fn inner_witness(&self, entry: &HttpCertificationTreeEntry, request_url: &str) -> HashTree {
+        match entry.path.borrow() {
             HttpCertificationPath::Exact(_) => self.tree.witness(&entry.to_tree_path()),
 
             HttpCertificationPath::Wildcard(_) => {
-                let request_url_path = HttpCertificationPath::Exact(request_url).to_tree_path();
+                let request_url_path = HttpCertificationPath::exact(request_url).to_tree_path();
 
                 // For wildcards we need to prove that there is not a more specific wildcard in the tree that
                 // matches the request URL. So we step through the path and generate a witness for each subpath,
@@ -84,11 +271,491 @@ impl HttpCertificationTree {
                         [without_trailing_slash, with_trailing_slash]
                     })
                     .fold(empty(), |acc, path| {
-                        merge_hash_trees(acc, self.tree.witness(&path))
+                        // every witness is generated from this tree, so they are always consistent
+                        try_merge_hash_trees(acc, self.tree.witness(&path))
+                            .expect("witnesses of the same tree are always consistent")
                     })
             }
-        };
+        }
+    }
+}
 
-        labeled(PATH_PREFIX_BYTES, witness)
+fn write_node(bytes: &mut Vec<u8>, node: &CertificationTree) {
+    match node {
+        NestedTree::Leaf(value) => {
+            bytes.push(LEAF_NODE_TAG);
+            write_bytes(bytes, value);
+        }
+        NestedTree::Nested(tree) => {
+            let entries = tree.entries_with_levels();
+
+            bytes.push(NESTED_NODE_TAG);
+            write_length(bytes, entries.len());
+            for (segment, child, level) in entries {
+                write_bytes(bytes, segment);
+                bytes.push(level);
+                write_node(bytes, child);
+            }
+        }
+    }
+}
+
+fn read_node(
+    reader: &mut TreeBytesReader,
+    depth: usize,
+) -> HttpCertificationResult<CertificationTree> {
+    if depth > MAX_DEPTH {
+        return Err(HttpCertificationError::MalformedCertificationTree(
+            "Maximum nesting depth exceeded".to_string(),
+        ));
+    }
+
+    match reader.read_u8()? {
+        LEAF_NODE_TAG => Ok(NestedTree::Leaf(reader.read_bytes()?.to_vec())),
+        NESTED_NODE_TAG => {
+            let num_entries = reader.read_length()?;
+            let mut entries = vec![];
+            for _ in 0..num_entries {
+                let segment = reader.read_bytes()?.to_vec();
+                let level = reader.read_u8()?;
+                let child = read_node(reader, depth + 1)?;
+
+                entries.push((segment, child, level));
+            }
+
+            let tree = RbTree::from_entries_with_levels(entries).map_err(|err| {
+                HttpCertificationError::MalformedCertificationTree(format!(
+                    "Invalid tree shape: {}",
+                    err
+                ))
+            })?;
+
+            Ok(NestedTree::Nested(tree))
+        }
+        tag => Err(HttpCertificationError::MalformedCertificationTree(format!(
+            "Unknown node tag {}",
+            tag
+        ))),
+    }
+}
+
+fn write_length(bytes: &mut Vec<u8>, length: usize) {
+    let length = u32::try_from(length).expect("Length does not fit into 32 bits");
+    bytes.extend_from_slice(&length.to_le_bytes());
+}
+
+fn write_bytes(bytes: &mut Vec<u8>, value: &[u8]) {
+    write_length(bytes, value.len());
+    bytes.extend_from_slice(value);
+}
+
+struct TreeBytesReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> TreeBytesReader<'a> {
+    fn read_u8(&mut self) -> HttpCertificationResult<u8> {
+        Ok(self.read_exact(1)?[0])
+    }
+
+    fn read_length(&mut self) -> HttpCertificationResult<usize> {
+        let length = self.read_exact(4)?;
+        let length = u32::from_le_bytes([length[0], length[1], length[2], length[3]]);
+
+        Ok(length as usize)
+    }
+
+    fn read_bytes(&mut self) -> HttpCertificationResult<&'a [u8]> {
+        let length = self.read_length()?;
+
+        self.read_exact(length)
+    }
+
+    fn read_exact(&mut self, length: usize) -> HttpCertificationResult<&'a [u8]> {
+        if self.bytes.len() < length {
+            return Err(HttpCertificationError::MalformedCertificationTree(format!(
+                "Expected {} more bytes, but only {} remain",
+                length,
+                self.bytes.len()
+            )));
+        }
+
+        let (value, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DefaultCelBuilder, HttpCertification, HttpResponse};
+    use rstest::*;
+
+    #[fixture]
+    fn paths() -> Vec<HttpCertificationPath<'static>> {
+        vec![
+            HttpCertificationPath::exact("/index.html"),
+            HttpCertificationPath::exact("/js/app.js"),
+            HttpCertificationPath::exact("/js/"),
+            HttpCertificationPath::wildcard("/js"),
+            HttpCertificationPath::exact("/css/app.css"),
+            HttpCertificationPath::wildcard("/"),
+        ]
+    }
+
+    #[fixture]
+    fn certifications() -> Vec<HttpCertification> {
+        let cel_expr = DefaultCelBuilder::response_only_certification().build();
+
+        (0..3u8)
+            .map(|i| {
+                let response = HttpResponse {
+                    status_code: 200,
+                    headers: vec![],
+                    body: vec![i],
+                    upgrade: None,
+                };
+
+                HttpCertification::response_only(&cel_expr, &response, None).unwrap()
+            })
+            .chain([HttpCertification::skip()])
+            .collect()
+    }
+
+    fn entries<'a>(
+        paths: &'a [HttpCertificationPath<'a>],
+        certifications: &'a [HttpCertification],
+    ) -> Vec<HttpCertificationTreeEntry<'a>> {
+        paths
+            .iter()
+            .flat_map(|path| {
+                certifications
+                    .iter()
+                    .map(move |certification| HttpCertificationTreeEntry::new(path, certification))
+            })
+            .collect()
+    }
+
+    #[rstest]
+    fn insert_many(
+        paths: Vec<HttpCertificationPath<'static>>,
+        certifications: Vec<HttpCertification>,
+    ) {
+        let entries = entries(&paths, &certifications);
+
+        let mut expected_tree = HttpCertificationTree::default();
+        for entry in entries.iter() {
+            expected_tree.insert(entry);
+        }
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert_many(&entries[..5]);
+        tree.insert_many(&entries[5..]);
+
+        assert_eq!(tree.root_hash(), expected_tree.root_hash());
+        for entry in entries.iter() {
+            let witness = tree.witness(entry, "/js/app.js");
+            assert_eq!(
+                witness.digest(),
+                tree.root_hash(),
+                "witness {:?} does not match the tree root hash",
+                witness
+            );
+        }
+    }
+
+    #[rstest]
+    fn delete_many(
+        paths: Vec<HttpCertificationPath<'static>>,
+        certifications: Vec<HttpCertification>,
+    ) {
+        let entries = entries(&paths, &certifications);
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert_many(&entries);
+
+        let mut expected_tree = HttpCertificationTree::default();
+        for entry in entries.iter() {
+            expected_tree.insert(entry);
+        }
+
+        for entry in entries.iter().step_by(3) {
+            expected_tree.delete(entry);
+        }
+        tree.delete_many(entries.iter().step_by(3));
+
+        assert_eq!(tree.root_hash(), expected_tree.root_hash());
+    }
+
+    #[rstest]
+    fn delete_prunes_empty_paths(
+        paths: Vec<HttpCertificationPath<'static>>,
+        certifications: Vec<HttpCertification>,
+    ) {
+        let entries = entries(&paths, &certifications);
+        let (deleted_entries, remaining_entries) = entries.split_at(certifications.len() * 2);
+
+        let mut expected_tree = HttpCertificationTree::default();
+        expected_tree.replace(remaining_entries);
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert_many(&entries);
+        for entry in deleted_entries.iter() {
+            tree.delete(entry);
+        }
+        assert_eq!(
+            tree.iter().collect::<Vec<_>>(),
+            expected_tree.iter().collect::<Vec<_>>()
+        );
+
+        tree.delete_many(remaining_entries);
+        assert_eq!(
+            tree.root_hash(),
+            HttpCertificationTree::default().root_hash()
+        );
+    }
+
+    #[rstest]
+    fn replace(paths: Vec<HttpCertificationPath<'static>>, certifications: Vec<HttpCertification>) {
+        let entries = entries(&paths, &certifications);
+
+        let mut sorted_entries: Vec<_> = entries.iter().collect();
+        sorted_entries.sort_by_key(|entry| entry.to_tree_path());
+
+        let mut expected_tree = HttpCertificationTree::default();
+        for entry in sorted_entries {
+            expected_tree.insert(entry);
+        }
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert(&HttpCertificationTreeEntry::new(
+            &HttpCertificationPath::exact("/stale.html"),
+            &HttpCertification::skip(),
+        ));
+        tree.replace(entries.iter().rev());
+
+        assert_eq!(tree.root_hash(), expected_tree.root_hash());
+        for entry in entries.iter() {
+            let witness = tree.witness(entry, "/css/app.css");
+            assert_eq!(witness.digest(), tree.root_hash());
+        }
+    }
+
+    #[rstest]
+    fn to_bytes_from_bytes_round_trip(
+        paths: Vec<HttpCertificationPath<'static>>,
+        certifications: Vec<HttpCertification>,
+    ) {
+        let entries = entries(&paths, &certifications);
+
+        let mut tree = HttpCertificationTree::default();
+        tree.replace(&entries);
+
+        let restored_tree = HttpCertificationTree::from_bytes(&tree.to_bytes()).unwrap();
+
+        assert_eq!(restored_tree.root_hash(), tree.root_hash());
+        assert_eq!(restored_tree.to_bytes(), tree.to_bytes());
+        for entry in entries.iter() {
+            let witness = restored_tree.witness(entry, "/js/app.js");
+            assert_eq!(witness.digest(), restored_tree.root_hash());
+        }
+    }
+
+    #[rstest]
+    fn to_bytes_from_bytes_preserves_root_hash(
+        paths: Vec<HttpCertificationPath<'static>>,
+        certifications: Vec<HttpCertification>,
+    ) {
+        let entries = entries(&paths, &certifications);
+
+        let mut tree = HttpCertificationTree::default();
+        for entry in entries.iter().rev() {
+            tree.insert(entry);
+        }
+        for entry in entries.iter().step_by(3) {
+            tree.delete(entry);
+        }
+        for entry in entries.iter().step_by(6) {
+            tree.insert(entry);
+        }
+
+        let restored_tree = HttpCertificationTree::from_bytes(&tree.to_bytes()).unwrap();
+
+        assert_eq!(restored_tree.root_hash(), tree.root_hash());
+        for entry in entries.iter().step_by(2) {
+            assert_eq!(
+                restored_tree.witness(entry, "/js/app.js"),
+                tree.witness(entry, "/js/app.js")
+            );
+        }
+    }
+
+    #[rstest]
+    fn to_bytes_from_bytes_empty_tree() {
+        let tree = HttpCertificationTree::default();
+
+        let restored_tree = HttpCertificationTree::from_bytes(&tree.to_bytes()).unwrap();
+
+        assert_eq!(restored_tree.root_hash(), tree.root_hash());
+    }
+
+    #[rstest]
+    #[case::empty(vec![])]
+    #[case::unsupported_version(vec![2, 1, 0, 0, 0, 0])]
+    #[case::truncated_length(vec![1, 1, 1, 0])]
+    #[case::truncated_segment(vec![1, 1, 1, 0, 0, 0, 5, 0, 0, 0, 1])]
+    #[case::unknown_tag(vec![1, 2])]
+    #[case::unsorted_segments(vec![1, 1, 2, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0])]
+    #[case::invalid_levels(vec![1, 1, 2, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0])]
+    #[case::trailing_bytes(vec![1, 1, 0, 0, 0, 0, 1])]
+    fn from_bytes_malformed(#[case] bytes: Vec<u8>) {
+        let result = HttpCertificationTree::from_bytes(&bytes);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::MalformedCertificationTree(_))
+        ));
+    }
+
+    /// Encodes a chain of `depth` nested subtrees, each with a single empty segment, ending in an empty leaf.
+    fn nested_tree_bytes(depth: usize) -> Vec<u8> {
+        let mut bytes = vec![TREE_ENCODING_VERSION];
+        for _ in 0..depth {
+            bytes.extend_from_slice(&[NESTED_NODE_TAG, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        }
+        bytes.extend_from_slice(&[LEAF_NODE_TAG, 0, 0, 0, 0]);
+
+        bytes
+    }
+
+    #[rstest]
+    fn from_bytes_max_depth() {
+        assert!(HttpCertificationTree::from_bytes(&nested_tree_bytes(MAX_DEPTH)).is_ok());
+        assert!(matches!(
+            HttpCertificationTree::from_bytes(&nested_tree_bytes(MAX_DEPTH + 1)),
+            Err(HttpCertificationError::MalformedCertificationTree(_))
+        ));
+    }
+
+    #[rstest]
+    fn contains(
+        paths: Vec<HttpCertificationPath<'static>>,
+        certifications: Vec<HttpCertification>,
+    ) {
+        let entries = entries(&paths, &certifications);
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert_many(entries.iter().step_by(2));
+
+        for (i, entry) in entries.iter().enumerate() {
+            assert_eq!(tree.contains(entry), i % 2 == 0);
+        }
+    }
+
+    #[rstest]
+    fn get(paths: Vec<HttpCertificationPath<'static>>, certifications: Vec<HttpCertification>) {
+        let entries = entries(&paths, &certifications);
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert_many(&entries);
+
+        for path in paths.iter() {
+            let mut result = tree.get(path);
+            let mut expected = certifications.clone();
+            result.sort_by_key(|certification| certification.to_tree_path());
+            expected.sort_by_key(|certification| certification.to_tree_path());
+
+            assert_eq!(result, expected);
+        }
+
+        assert!(tree.get(&HttpCertificationPath::exact("/js")).is_empty());
+        assert!(tree
+            .get(&HttpCertificationPath::wildcard("/index.html"))
+            .is_empty());
+    }
+
+    #[rstest]
+    fn iter(paths: Vec<HttpCertificationPath<'static>>, certifications: Vec<HttpCertification>) {
+        let entries = entries(&paths, &certifications);
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert_many(&entries);
+
+        let items: Vec<_> = tree.iter().collect();
+        assert_eq!(items.len(), entries.len());
+        for item in items.iter() {
+            let entry = item.to_entry();
+            assert!(tree.contains(&entry));
+            assert!(entries.iter().any(|expected_entry| {
+                expected_entry.path.as_ref() == entry.path.as_ref()
+                    && expected_entry.certification.as_ref() == entry.certification.as_ref()
+            }));
+        }
+
+        for item in items.iter() {
+            tree.delete(&item.to_entry());
+        }
+        assert_eq!(tree.iter().count(), 0);
+    }
+
+    #[rstest]
+    fn witness_many(
+        paths: Vec<HttpCertificationPath<'static>>,
+        certifications: Vec<HttpCertification>,
+    ) {
+        let entries = entries(&paths, &certifications);
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert_many(&entries);
+
+        let request_urls = [
+            "/index.html",
+            "/js/app.js",
+            "/js/",
+            "/js/lib.js",
+            "/css/app.css",
+        ];
+        let witness_entries: Vec<_> = entries
+            .iter()
+            .zip(request_urls.iter().cycle())
+            .map(|(entry, request_url)| (entry, *request_url))
+            .collect();
+
+        let witness = tree.witness_many(&witness_entries);
+        assert_eq!(witness.digest(), tree.root_hash());
+
+        let expected_witness = witness_entries
+            .iter()
+            .map(|(entry, request_url)| tree.witness(entry, request_url))
+            .reduce(|lhs, rhs| try_merge_hash_trees(lhs, rhs).unwrap())
+            .unwrap();
+        assert_eq!(witness, expected_witness);
+
+        for (entry, _) in witness_entries.iter() {
+            let mut path: Vec<_> = vec![PATH_PREFIX_BYTES.to_vec()];
+            path.extend(entry.to_tree_path());
+
+            assert!(matches!(
+                witness.lookup_subtree(&path),
+                ic_certification::SubtreeLookupResult::Found(_)
+            ));
+        }
+    }
+
+    #[rstest]
+    fn witness_many_without_entries(
+        paths: Vec<HttpCertificationPath<'static>>,
+        certifications: Vec<HttpCertification>,
+    ) {
+        let entries = entries(&paths, &certifications);
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert_many(&entries);
+
+        let witness = tree.witness_many(&[]);
+
+        assert_eq!(witness.digest(), tree.root_hash());
     }
 }
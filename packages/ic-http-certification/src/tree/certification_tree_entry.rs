@@ -37,6 +37,15 @@ impl<'a> HttpCertificationTreeEntry<'a> {
         }
     }
 
+    /// Converts this [HttpCertificationTreeEntry] into an owned version that does not borrow any data,
+    /// for example to store it in a canister's state.
+    pub fn into_owned(self) -> HttpCertificationTreeEntry<'static> {
+        HttpCertificationTreeEntry {
+            path: Cow::Owned(self.path.into_owned().into_owned()),
+            certification: Cow::Owned(self.certification.into_owned()),
+        }
+    }
+
     pub(super) fn to_tree_path(&self) -> InnerTreePath {
         let mut tree_path = vec![];
         tree_path.append(&mut self.path.to_tree_path());
@@ -59,8 +68,8 @@ mod tests {
 
     #[template]
     #[rstest]
-    #[case(HttpCertificationPath::Exact("/foo/bar"), vec!["foo", "bar", "<$>"])]
-    #[case(HttpCertificationPath::Wildcard("/foo/bar"), vec!["foo", "bar", "<*>"])]
+    #[case(HttpCertificationPath::exact("/foo/bar"), vec!["foo", "bar", "<$>"])]
+    #[case(HttpCertificationPath::wildcard("/foo/bar"), vec!["foo", "bar", "<*>"])]
     fn certification_paths(
         #[case] path: HttpCertificationPath<'static>,
         #[case] expected: Vec<&str>,
@@ -114,7 +123,7 @@ mod tests {
         };
         let expected_response_hash = response_hash(&response, &cel_expr.response, None);
 
-        let certification = HttpCertification::response_only(&cel_expr, &response, None);
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
         let entry = HttpCertificationTreeEntry::new(&path, &certification);
 
         let result = entry.to_tree_path();
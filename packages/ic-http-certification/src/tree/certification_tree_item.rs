@@ -0,0 +1,65 @@
+use super::{
+    certification_tree_entry::HttpCertificationTreeEntry,
+    certification_tree_path::HttpCertificationPath,
+};
+use crate::HttpCertification;
+use std::borrow::Cow;
+
+/// An entry that is currently certified in an [HttpCertificationTree](crate::HttpCertificationTree),
+/// decoded from the tree itself.
+///
+/// Items are returned by [iter](crate::HttpCertificationTree::iter) and can be used to inspect which
+/// [paths](HttpCertificationTreeItem::path) and [certifications](HttpCertificationTreeItem::certification)
+/// are present in the tree, or converted back into an [HttpCertificationTreeEntry] with
+/// [to_entry](HttpCertificationTreeItem::to_entry), for example to [delete](crate::HttpCertificationTree::delete)
+/// a stale certification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpCertificationTreeItem {
+    path: HttpCertificationPath<'static>,
+    certification: HttpCertification,
+}
+
+impl HttpCertificationTreeItem {
+    pub(super) fn new(
+        path: HttpCertificationPath<'static>,
+        certification: HttpCertification,
+    ) -> Self {
+        Self {
+            path,
+            certification,
+        }
+    }
+
+    pub(super) fn from_tree_path(tree_path: &[impl AsRef<[u8]>]) -> Option<Self> {
+        let (path, rest) = HttpCertificationPath::split_tree_path(tree_path)?;
+        let certification = HttpCertification::from_tree_path(rest)?;
+
+        Some(Self {
+            path,
+            certification,
+        })
+    }
+
+    /// The [HttpCertificationPath] that the [certification](HttpCertificationTreeItem::certification)
+    /// is valid for.
+    ///
+    /// Paths are normalized when they are inserted into the tree, so the returned path always starts with
+    /// a slash, unless it is empty, even if the path of the original [HttpCertificationTreeEntry] did not.
+    pub fn path(&self) -> &HttpCertificationPath<'static> {
+        &self.path
+    }
+
+    /// The [HttpCertification] containing the CEL expression hash and the request and response hashes
+    /// that are certified for the [path](HttpCertificationTreeItem::path).
+    pub fn certification(&self) -> &HttpCertification {
+        &self.certification
+    }
+
+    /// Converts this item into an [HttpCertificationTreeEntry] that refers to the same entry in the tree.
+    pub fn to_entry(&self) -> HttpCertificationTreeEntry<'_> {
+        HttpCertificationTreeEntry {
+            path: Cow::Borrowed(&self.path),
+            certification: Cow::Borrowed(&self.certification),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 pub(super) type CertificationTreePathSegment = Vec<u8>;
 pub(super) type InnerTreePath = Vec<CertificationTreePathSegment>;
 
@@ -21,21 +23,40 @@ pub(super) const WILDCARD_PATH_TERMINATOR_BYTES: &[u8] = WILDCARD_PATH_TERMINATO
 ///
 /// - The [Wildcard](HttpCertificationPath::Wildcard) variant is used for paths that match a URL path prefix.
 /// For example, `HttpCertificationPath::Wildcard('/foo')` will match the URL paths `/foo/bar` and `/foo/baz`.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HttpCertificationPath<'a> {
     /// An exact path to an [HttpCertification](crate::HttpCertification) in an
     /// [HttpCertificationTree](crate::HttpCertificationTree). This path will match only
     /// [HttpRequest](crate::HttpRequest) URL paths that are exactly the same as the given path.
-    Exact(&'a str),
+    Exact(Cow<'a, str>),
 
     /// A wildcard path to an [HttpCertification](crate::HttpCertification) in an
     /// [HttpCertificationTree](crate::HttpCertificationTree). This path will match all
     /// [HttpRequest](crate::HttpRequest) URL paths that start with the given prefix.
-    Wildcard(&'a str),
+    Wildcard(Cow<'a, str>),
 }
 
 impl<'a> HttpCertificationPath<'a> {
-    pub(super) fn to_tree_path(self) -> InnerTreePath {
+    /// Creates an [Exact](HttpCertificationPath::Exact) path from a borrowed or owned string.
+    pub fn exact(path: impl Into<Cow<'a, str>>) -> Self {
+        Self::Exact(path.into())
+    }
+
+    /// Creates a [Wildcard](HttpCertificationPath::Wildcard) path from a borrowed or owned string.
+    pub fn wildcard(path: impl Into<Cow<'a, str>>) -> Self {
+        Self::Wildcard(path.into())
+    }
+
+    /// Converts this [HttpCertificationPath] into an owned version that does not borrow any data,
+    /// for example to store it in a canister's state.
+    pub fn into_owned(self) -> HttpCertificationPath<'static> {
+        match self {
+            Self::Exact(path) => HttpCertificationPath::Exact(Cow::Owned(path.into_owned())),
+            Self::Wildcard(path) => HttpCertificationPath::Wildcard(Cow::Owned(path.into_owned())),
+        }
+    }
+
+    pub(super) fn to_tree_path(&self) -> InnerTreePath {
         match self {
             Self::Exact(path) => Self::path_to_segments(path, EXACT_PATH_TERMINATOR_BYTES),
             Self::Wildcard(path) => Self::path_to_segments(path, WILDCARD_PATH_TERMINATOR_BYTES),
@@ -50,6 +71,44 @@ impl<'a> HttpCertificationPath<'a> {
         }
     }
 
+    /// Splits a tree path into the [HttpCertificationPath] and the remaining segments
+    /// that follow the path's terminator.
+    pub(super) fn split_tree_path<T: AsRef<[u8]>>(
+        tree_path: &[T],
+    ) -> Option<(HttpCertificationPath<'static>, &[T])> {
+        // the path's terminator is followed by either one or three segments,
+        // depending on the type of certification
+        [2, 4]
+            .into_iter()
+            .filter(|offset| tree_path.len() >= *offset)
+            .find_map(|offset| {
+                let (path_segments, rest) = tree_path.split_at(tree_path.len() - offset);
+                let (terminator, rest) = rest.split_first()?;
+                let path = Self::segments_to_path(path_segments);
+                let path = match terminator.as_ref() {
+                    EXACT_PATH_TERMINATOR_BYTES => HttpCertificationPath::exact(path),
+                    WILDCARD_PATH_TERMINATOR_BYTES => HttpCertificationPath::wildcard(path),
+                    _ => return None,
+                };
+
+                Some((path, rest))
+            })
+    }
+
+    fn segments_to_path(path_segments: &[impl AsRef<[u8]>]) -> String {
+        if path_segments.is_empty() {
+            return String::new();
+        }
+
+        path_segments
+            .iter()
+            .fold(String::new(), |mut path, segment| {
+                path.push('/');
+                path.push_str(&String::from_utf8_lossy(segment.as_ref()));
+                path
+            })
+    }
+
     fn path_to_segments(path: &str, terminator: &[u8]) -> InnerTreePath {
         let mut path_segments = path
             .split('/')
@@ -121,7 +180,7 @@ mod tests {
 
     #[apply(exact_paths)]
     fn exact_path_to_tree_path(#[case] path: &str, #[case] expected: Vec<&str>) {
-        let path = HttpCertificationPath::Exact(path);
+        let path = HttpCertificationPath::exact(path);
 
         let result = path.to_tree_path();
         let expected = expected
@@ -134,7 +193,7 @@ mod tests {
 
     #[apply(wildcard_paths)]
     fn wildcard_path_to_tree_path(#[case] path: &str, #[case] expected: Vec<&str>) {
-        let path = HttpCertificationPath::Wildcard(path);
+        let path = HttpCertificationPath::wildcard(path);
 
         let result = path.to_tree_path();
         let expected = expected
@@ -147,7 +206,7 @@ mod tests {
 
     #[apply(exact_paths)]
     fn exact_path_to_expr_path(#[case] path: &str, #[case] expected: Vec<&str>) {
-        let path = HttpCertificationPath::Exact(path);
+        let path = HttpCertificationPath::exact(path);
 
         let result = path.to_expr_path();
         let expected = [PATH_PREFIX]
@@ -161,7 +220,7 @@ mod tests {
 
     #[apply(wildcard_paths)]
     fn wildcard_path_to_expr_path(#[case] path: &str, #[case] expected: Vec<&str>) {
-        let path = HttpCertificationPath::Wildcard(path);
+        let path = HttpCertificationPath::wildcard(path);
 
         let result = path.to_expr_path();
         let expected = [PATH_PREFIX]
@@ -172,4 +231,43 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[apply(exact_paths)]
+    fn exact_path_from_tree_path(#[case] path: &str, #[case] _expected: Vec<&str>) {
+        let path = HttpCertificationPath::exact(path);
+        let mut tree_path = path.to_tree_path();
+        tree_path.push(vec![0; 32]);
+
+        let (result_path, rest) = HttpCertificationPath::split_tree_path(&tree_path).unwrap();
+
+        assert!(matches!(result_path, HttpCertificationPath::Exact(_)));
+        assert_eq!(result_path.to_tree_path(), path.to_tree_path());
+        assert_eq!(rest, &[vec![0; 32]]);
+    }
+
+    #[apply(wildcard_paths)]
+    fn wildcard_path_from_tree_path(#[case] path: &str, #[case] _expected: Vec<&str>) {
+        let path = HttpCertificationPath::wildcard(path);
+        let mut tree_path = path.to_tree_path();
+        tree_path.append(&mut vec![vec![0; 32], vec![], vec![1; 32]]);
+
+        let (result_path, rest) = HttpCertificationPath::split_tree_path(&tree_path).unwrap();
+
+        assert!(matches!(result_path, HttpCertificationPath::Wildcard(_)));
+        assert_eq!(result_path.to_tree_path(), path.to_tree_path());
+        assert_eq!(rest, &[vec![0; 32], vec![], vec![1; 32]]);
+    }
+
+    #[rstest]
+    #[case::no_terminator(vec!["foo", "bar"])]
+    #[case::misplaced_terminator(vec!["<$>", "foo", "bar"])]
+    #[case::too_short(vec!["<$>"])]
+    fn invalid_tree_path(#[case] tree_path: Vec<&str>) {
+        let tree_path: InnerTreePath = tree_path
+            .iter()
+            .map(|segment| segment.as_bytes().to_vec())
+            .collect();
+
+        assert!(HttpCertificationPath::split_tree_path(&tree_path).is_none());
+    }
 }
@@ -0,0 +1,353 @@
+use crate::{
+    cel::{is_valid_header_name_pattern, DefaultRequestCertification},
+    hash::{CERTIFICATE_EXPRESSION_HEADER_NAME, CERTIFICATE_HEADER_NAME},
+    DefaultResponseCertification, HttpCertificationError, HttpCertificationResult, HttpRequest,
+    HttpResponse,
+};
+use std::borrow::Cow;
+
+const CONTENT_ENCODING_HEADER_NAME: &str = "Content-Encoding";
+const SUPPORTED_CONTENT_ENCODINGS: [&str; 4] = ["gzip", "deflate", "br", "identity"];
+
+/// Checks that a request can be certified according to a [DefaultRequestCertification].
+pub(crate) fn validate_request(
+    request: &HttpRequest,
+    request_certification: &DefaultRequestCertification,
+) -> HttpCertificationResult {
+    request.get_path()?;
+
+    validate_header_names(&request_certification.headers)
+}
+
+/// Checks that a response can be certified according to a CEL expression and the
+/// [DefaultResponseCertification] that was selected from it.
+pub(crate) fn validate_response(
+    cel_expr: &str,
+    response: &HttpResponse,
+    response_certification: &DefaultResponseCertification,
+) -> HttpCertificationResult {
+    for (header_name, header_value) in &response.headers {
+        if header_name.eq_ignore_ascii_case(CERTIFICATE_HEADER_NAME) {
+            return Err(HttpCertificationError::CertificateHeaderPresent);
+        }
+
+        if header_name.eq_ignore_ascii_case(CERTIFICATE_EXPRESSION_HEADER_NAME)
+            && header_value != cel_expr
+        {
+            return Err(HttpCertificationError::CertificateExpressionMismatch {
+                expected: cel_expr.to_string(),
+                found: header_value.clone(),
+            });
+        }
+
+        if header_name.eq_ignore_ascii_case(CONTENT_ENCODING_HEADER_NAME) {
+            validate_content_encoding(header_value)?;
+        }
+    }
+
+    validate_header_names(response_certification.headers())?;
+
+    if response_certification.is_inclusion() {
+        // these headers are always excluded from the response hash, so listing them can not certify them
+        if let Some(header_name) = response_certification.headers().iter().find(|header_name| {
+            header_name.eq_ignore_ascii_case(CERTIFICATE_HEADER_NAME)
+                || header_name.eq_ignore_ascii_case(CERTIFICATE_EXPRESSION_HEADER_NAME)
+        }) {
+            return Err(HttpCertificationError::ExcludedHeaderCertified(
+                header_name.to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that every encoding that is listed in a `Content-Encoding` header value can be decoded by
+/// HTTP gateways. Encodings are listed in the order that they were applied, separated by commas.
+fn validate_content_encoding(content_encoding: &str) -> HttpCertificationResult {
+    for encoding in content_encoding.split(',').map(str::trim) {
+        if !SUPPORTED_CONTENT_ENCODINGS
+            .iter()
+            .any(|supported_encoding| supported_encoding.eq_ignore_ascii_case(encoding))
+        {
+            return Err(HttpCertificationError::UnsupportedContentEncoding(
+                encoding.to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that a list of certified or excluded header names only contains valid
+/// header name patterns, and that no header name is listed more than once.
+fn validate_header_names(header_names: &[Cow<'_, str>]) -> HttpCertificationResult {
+    for (i, header_name) in header_names.iter().enumerate() {
+        if !is_valid_header_name_pattern(header_name) {
+            return Err(HttpCertificationError::InvalidHeaderNamePattern(
+                header_name.to_string(),
+            ));
+        }
+
+        if header_names[..i]
+            .iter()
+            .any(|other_header_name| other_header_name.eq_ignore_ascii_case(header_name))
+        {
+            return Err(HttpCertificationError::DuplicateHeaderName(
+                header_name.to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    const CEL_EXPR: &str = "default_certification(ValidationArgs{no_certification:Empty{}})";
+
+    #[fixture]
+    fn request() -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            url: "/index.html?lang=en".to_string(),
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    #[fixture]
+    fn response() -> HttpResponse {
+        HttpResponse {
+            status_code: 200,
+            headers: vec![
+                ("IC-CertificateExpression".to_string(), CEL_EXPR.to_string()),
+                ("Cache-Control".to_string(), "no-cache".to_string()),
+                ("cache-control".to_string(), "no-store".to_string()),
+            ],
+            body: vec![],
+            upgrade: None,
+        }
+    }
+
+    #[rstest]
+    fn valid_request(request: HttpRequest) {
+        let request_certification =
+            DefaultRequestCertification::new(["Accept", "Accept-Encoding"], ["lang"]);
+
+        let result = validate_request(&request, &request_certification);
+
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    fn request_with_malformed_url(request: HttpRequest) {
+        let request = HttpRequest {
+            url: "http://[::1".to_string(),
+            ..request
+        };
+        let request_certification =
+            DefaultRequestCertification::new(Vec::<&str>::new(), Vec::<&str>::new());
+
+        let result = validate_request(&request, &request_certification);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::MalformedUrl(url)) if url == "http://[::1"
+        ));
+    }
+
+    #[rstest]
+    fn request_certification_with_duplicate_header_names(request: HttpRequest) {
+        let request_certification =
+            DefaultRequestCertification::new(["Accept", "Host", "accept"], Vec::<&str>::new());
+
+        let result = validate_request(&request, &request_certification);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::DuplicateHeaderName(header_name)) if header_name == "accept"
+        ));
+    }
+
+    #[rstest]
+    fn request_certification_with_invalid_header_name_pattern(request: HttpRequest) {
+        let request_certification =
+            DefaultRequestCertification::new(["Accept", "x-*-version"], Vec::<&str>::new());
+
+        let result = validate_request(&request, &request_certification);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::InvalidHeaderNamePattern(pattern)) if pattern == "x-*-version"
+        ));
+    }
+
+    #[rstest]
+    #[case::certified_headers(DefaultResponseCertification::certified_response_headers(&["Cache-Control"]))]
+    #[case::header_exclusions(DefaultResponseCertification::response_header_exclusions(&["IC-Certificate"]))]
+    fn valid_response(
+        response: HttpResponse,
+        #[case] response_certification: DefaultResponseCertification,
+    ) {
+        let result = validate_response(CEL_EXPR, &response, &response_certification);
+
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    fn response_without_certificate_expression_header(response: HttpResponse) {
+        let response = HttpResponse {
+            headers: vec![],
+            ..response
+        };
+
+        let result = validate_response(
+            CEL_EXPR,
+            &response,
+            &DefaultResponseCertification::default(),
+        );
+
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    fn response_with_certificate_header(mut response: HttpResponse) {
+        response
+            .headers
+            .push(("ic-certificate".to_string(), "certificate=::".to_string()));
+
+        let result = validate_response(
+            CEL_EXPR,
+            &response,
+            &DefaultResponseCertification::default(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::CertificateHeaderPresent)
+        ));
+    }
+
+    #[rstest]
+    fn response_with_mismatched_certificate_expression(response: HttpResponse) {
+        let cel_expr = "default_certification(ValidationArgs{certification:Certification{no_request_certification:Empty{},response_certification:ResponseCertification{certified_response_headers:ResponseHeaderList{headers:[]}}}})";
+
+        let result = validate_response(
+            cel_expr,
+            &response,
+            &DefaultResponseCertification::default(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::CertificateExpressionMismatch { expected, found })
+                if expected == cel_expr && found == CEL_EXPR
+        ));
+    }
+
+    #[rstest]
+    #[case::certificate_header("ic-certificate")]
+    #[case::certificate_expression_header("IC-CertificateExpression")]
+    fn response_certification_with_excluded_header(
+        response: HttpResponse,
+        #[case] header_name: &str,
+    ) {
+        let result = validate_response(
+            CEL_EXPR,
+            &response,
+            &DefaultResponseCertification::certified_response_headers(&[
+                "Cache-Control",
+                header_name,
+            ]),
+        );
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::ExcludedHeaderCertified(excluded_header_name))
+                if excluded_header_name == header_name
+        ));
+    }
+
+    #[rstest]
+    #[case::certified_headers(DefaultResponseCertification::certified_response_headers(&["ETag", "Cache-Control", "etag"]))]
+    #[case::header_exclusions(DefaultResponseCertification::response_header_exclusions(&["ETag", "Cache-Control", "etag"]))]
+    fn response_certification_with_duplicate_header_names(
+        response: HttpResponse,
+        #[case] response_certification: DefaultResponseCertification,
+    ) {
+        let result = validate_response(CEL_EXPR, &response, &response_certification);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::DuplicateHeaderName(header_name)) if header_name == "etag"
+        ));
+    }
+
+    #[rstest]
+    #[case::certified_headers(DefaultResponseCertification::certified_response_headers(&["x-app-*", "x-*-version"]))]
+    #[case::header_exclusions(DefaultResponseCertification::response_header_exclusions(&["x-app-*", "x-*-version"]))]
+    fn response_certification_with_invalid_header_name_pattern(
+        response: HttpResponse,
+        #[case] response_certification: DefaultResponseCertification,
+    ) {
+        let result = validate_response(CEL_EXPR, &response, &response_certification);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::InvalidHeaderNamePattern(pattern)) if pattern == "x-*-version"
+        ));
+    }
+
+    #[rstest]
+    #[case::gzip("gzip")]
+    #[case::case_insensitive("GZip")]
+    #[case::deflate("deflate")]
+    #[case::brotli("br")]
+    #[case::identity("identity")]
+    #[case::multiple_encodings("deflate, gzip")]
+    fn response_with_supported_content_encoding(
+        mut response: HttpResponse,
+        #[case] content_encoding: &str,
+    ) {
+        response
+            .headers
+            .push(("Content-Encoding".to_string(), content_encoding.to_string()));
+
+        let result = validate_response(
+            CEL_EXPR,
+            &response,
+            &DefaultResponseCertification::default(),
+        );
+
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    #[case::multiple_encodings("gzip, compress", "compress")]
+    #[case::empty("", "")]
+    fn response_with_unsupported_content_encoding(
+        mut response: HttpResponse,
+        #[case] content_encoding: &str,
+        #[case] expected_encoding: &str,
+    ) {
+        response
+            .headers
+            .push(("content-encoding".to_string(), content_encoding.to_string()));
+
+        let result = validate_response(
+            CEL_EXPR,
+            &response,
+            &DefaultResponseCertification::default(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::UnsupportedContentEncoding(encoding))
+                if encoding == expected_encoding
+        ));
+    }
+}
@@ -0,0 +1,713 @@
+use super::certification_tree_path::{
+    EXACT_PATH_TERMINATOR, PATH_PREFIX, WILDCARD_PATH_TERMINATOR,
+};
+use ic_certification::{
+    hash_tree::{Hash, HashTree, SubtreeLookupResult},
+    Label,
+};
+
+fn path_from_parts<T>(parts: &[T]) -> Vec<Label>
+where
+    T: AsRef<[u8]>,
+{
+    parts
+        .iter()
+        .map(|p| p.as_ref().to_vec())
+        .map(Label::from)
+        .collect()
+}
+
+fn path_might_exist_in_tree<Storage: Clone + AsRef<[u8]>>(
+    path: &[Label],
+    tree: &HashTree<Storage>,
+) -> bool {
+    !matches!(tree.lookup_subtree(path), SubtreeLookupResult::Absent)
+}
+
+fn path_exists_in_tree<Storage: Clone + AsRef<[u8]>>(
+    path: &[Label],
+    tree: &HashTree<Storage>,
+) -> bool {
+    matches!(tree.lookup_subtree(path), SubtreeLookupResult::Found(_))
+}
+
+fn is_wildcard_path_valid_for_request_path(
+    wildcard_path: &[Label],
+    request_path: &[Label],
+) -> bool {
+    if request_path.starts_with(wildcard_path) {
+        return true;
+    }
+
+    if wildcard_path.ends_with(&["".into()]) {
+        return request_path.starts_with(&wildcard_path[..wildcard_path.len() - 1]);
+    }
+
+    false
+}
+
+fn expr_path_has_valid_suffix(expr_path: &[String]) -> bool {
+    expr_path.ends_with(&[EXACT_PATH_TERMINATOR.to_string()])
+        || expr_path.ends_with(&[WILDCARD_PATH_TERMINATOR.to_string()])
+}
+
+/// Checks that an expression path is the most specific path in a [HashTree] that matches a request path,
+/// as required by the HTTP Gateway's response verification (version 2).
+///
+/// The tree is typically the pruned tree from the `IC-Certificate` header, or a
+/// [witness](crate::HttpCertificationTree::witness) produced by an [HttpCertificationTree](crate::HttpCertificationTree).
+/// Validation fails if the expression path does not exist in the tree, if it does not match the request path,
+/// or if the tree does not prove that there is no more specific path for the request path.
+pub fn validate_expr_path<Storage: Clone + AsRef<[u8]>>(
+    expr_path: &[String],
+    request_path: &str,
+    tree: &HashTree<Storage>,
+) -> bool {
+    // if a path does not end with a valid delimiter then it is invalid
+    if !expr_path_has_valid_suffix(expr_path) {
+        return false;
+    }
+
+    let mut request_url_parts = vec![PATH_PREFIX];
+    request_url_parts.extend(request_path.split('/').filter(|e| !e.is_empty()));
+
+    // make sure to treat a request for a directory and a file as different paths
+    // i.e. /app is not the same as /app/
+    // we do this by inserting an empty space for directory paths
+    if request_path.ends_with('/') {
+        request_url_parts.push("");
+    }
+
+    let original_path = path_from_parts(expr_path);
+    let mut potential_path = path_from_parts(expr_path);
+    let mut request_url_path = path_from_parts(&request_url_parts);
+
+    // if the expr_path matches the full URL, there can't be a more precise path in the tree
+    request_url_path.push(EXACT_PATH_TERMINATOR.into());
+    if potential_path.eq(&request_url_path) {
+        return path_exists_in_tree(&original_path, tree);
+    }
+
+    // at this point there are no more valid exact paths,
+    // so validation fails if the certified_path ends with an exact path suffix,
+    if potential_path.ends_with(&[Label::from(EXACT_PATH_TERMINATOR)]) {
+        return false;
+    }
+
+    // validation fails if the expr_path does not match full URL and the full URL exists in the tree
+    if path_might_exist_in_tree(&request_url_path, tree) {
+        return false;
+    }
+    request_url_path.pop(); // pop "<$>"
+
+    // if the expr_path matches the full URL with a wildcard
+    // there can't be a more precise path in the tree
+    request_url_path.push(WILDCARD_PATH_TERMINATOR.into());
+    if potential_path.eq(&request_url_path) {
+        return path_exists_in_tree(&original_path, tree);
+    }
+    request_url_path.pop(); // pop "<*>"
+    potential_path.pop(); // pop "<*>"
+
+    if !is_wildcard_path_valid_for_request_path(&potential_path, &request_url_path) {
+        return false;
+    }
+
+    // recursively check for partial URL matches with wildcards that are more precise than the expr_path
+    while request_url_path.len() > potential_path.len()
+        || request_url_path.last() != potential_path.last()
+    {
+        // check wildcard
+        request_url_path.push(WILDCARD_PATH_TERMINATOR.into());
+        if path_might_exist_in_tree(&request_url_path, tree) {
+            return false;
+        }
+        request_url_path.pop(); // pop "<*>"
+
+        if request_url_path.ends_with(&[Label::from("")]) {
+            request_url_path.pop(); // pop empty string
+        } else {
+            request_url_path.pop(); // pop the last segment of the path
+            request_url_path.push("".into()); // append empty string to check trailing slash
+        }
+    }
+
+    // if we haven't found a more specific path in the tree,
+    // then the provided path is valid if it exists in the tree
+    path_exists_in_tree(&original_path, tree)
+}
+
+/// Looks up the hash of a CEL expression under an expression path in a [HashTree], returning the subtree
+/// that contains the request and response hashes that are certified with that expression.
+pub fn validate_expr_hash<Storage: Clone + AsRef<[u8]>>(
+    expr_path: &[String],
+    expr_hash: &Hash,
+    tree: &HashTree<Storage>,
+) -> Option<HashTree<Storage>> {
+    let mut path = path_from_parts(expr_path);
+    path.push(expr_hash.into());
+
+    match tree.lookup_subtree(&path) {
+        SubtreeLookupResult::Found(expr_tree) => Some(expr_tree),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_certification::hash_tree::{fork, label, leaf, pruned};
+    use ic_representation_independent_hash::hash;
+
+    const CEL_EXPRESSION: &str = "default_certification(ValidationArgs{certification:Certification{no_request_certification:Empty{},response_certification:ResponseCertification{response_header_exclusions:ResponseHeaderList{headers:[]}}}})";
+    const NO_CERTIFICATION_CEL_EXPRESSION: &str =
+        "default_certification(ValidationArgs{no_certification:Empty{}})";
+
+    #[test]
+    fn validate_expr_hash_no_certification() {
+        let expr_hash = hash(NO_CERTIFICATION_CEL_EXPRESSION.as_bytes());
+        let expr_path = vec![
+            "http_expr".into(),
+            "assets".into(),
+            "js".into(),
+            "app.js".into(),
+            "<$>".into(),
+        ];
+        let tree = fork(
+            label(
+                "http_expr",
+                label(
+                    "assets",
+                    label(
+                        "js",
+                        label("app.js", label("<$>", label(expr_hash, leaf("")))),
+                    ),
+                ),
+            ),
+            create_pruned("ea7fd1a6b0cac1fe118016ca3026e58d5ae67a6965478acb561edba542732e24"),
+        );
+
+        let result = validate_expr_hash(&expr_path, &expr_hash, &tree);
+
+        assert_eq!(result, Some(leaf("")));
+    }
+
+    #[test]
+    fn validate_sibling_expr_hash() {
+        let no_certification_expr_hash = hash(NO_CERTIFICATION_CEL_EXPRESSION.as_bytes());
+        let expr_hash = hash(CEL_EXPRESSION.as_bytes());
+        let expr_path = vec![
+            "http_expr".into(),
+            "assets".into(),
+            "js".into(),
+            "app.js".into(),
+            "<$>".into(),
+        ];
+        let tree = fork(
+            label(
+                "http_expr",
+                label(
+                    "assets",
+                    label(
+                        "js",
+                        label(
+                            "app.js",
+                            label(
+                                "<$>",
+                                fork(
+                                    label(no_certification_expr_hash, leaf("")),
+                                    label(expr_hash, leaf("")),
+                                ),
+                            ),
+                        ),
+                    ),
+                ),
+            ),
+            create_pruned("ea7fd1a6b0cac1fe118016ca3026e58d5ae67a6965478acb561edba542732e24"),
+        );
+
+        let result = validate_expr_hash(&expr_path, &expr_hash, &tree);
+        let no_certification_result =
+            validate_expr_hash(&expr_path, &no_certification_expr_hash, &tree);
+
+        assert_eq!(result, Some(leaf("")));
+        assert_eq!(no_certification_result, Some(leaf("")));
+    }
+
+    #[test]
+    fn validate_expr_hash_does_not_exist() {
+        let expr_hash = hash(NO_CERTIFICATION_CEL_EXPRESSION.as_bytes());
+        let expr_path = vec![
+            "http_expr".into(),
+            "assets".into(),
+            "js".into(),
+            "app.js".into(),
+            "<$>".into(),
+        ];
+        let tree = fork(
+            label(
+                "http_expr",
+                label(
+                    "assets",
+                    label(
+                        "js",
+                        label("app.js", label("<$>", label(sha256_from_hex("02456594f95f4e8f35f14850d23bc05aa065ecc17eb4aeaff3c1819edaee0816"), leaf("")))),
+                    ),
+                ),
+            ),
+            create_pruned("ea7fd1a6b0cac1fe118016ca3026e58d5ae67a6965478acb561edba542732e24"),
+        );
+
+        let result = validate_expr_hash(&expr_path, &expr_hash, &tree);
+
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn validate_expr_path_that_is_most_precise_path_available() {
+        let expr_path = vec![
+            "http_expr".into(),
+            "assets".into(),
+            "js".into(),
+            "app.js".into(),
+            "<$>".into(),
+        ];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/assets/js/app.js").unwrap();
+        let tree = fork(
+            label(
+                "http_expr",
+                label(
+                    "assets",
+                    label("js", label("app.js", label("<$>", leaf("")))),
+                ),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(result);
+    }
+
+    #[test]
+    fn validation_expr_path_with_trailing_slash() {
+        let expr_path = vec!["http_expr".into(), "app".into(), "".into(), "<$>".into()];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/app/").unwrap();
+        let tree = fork(
+            label("http_expr", label("app", label("", label("<$>", leaf(""))))),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(result);
+    }
+
+    #[test]
+    fn validate_wildcard_expr_path_that_is_most_precise_path_available() {
+        let expr_path = vec![
+            "http_expr".into(),
+            "assets".into(),
+            "js".into(),
+            "<*>".into(),
+        ];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/assets/js/app.js").unwrap();
+        let tree = fork(
+            label(
+                "http_expr",
+                label("assets", label("js", label("<*>", leaf("")))),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(result);
+    }
+
+    #[test]
+    fn validate_trailing_slash_wildcard_expr_path_that_is_most_precise_path_available() {
+        let expr_path = vec!["http_expr".into(), "app".into(), "".into(), "<*>".into()];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/app/not-existing").unwrap();
+        let tree = fork(
+            label(
+                "http_expr",
+                label(
+                    "app",
+                    fork(label("", label("<*>", leaf(""))), label("<$>", leaf(""))),
+                ),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(result);
+    }
+
+    #[test]
+    fn validate_trailing_slash_wildcard_expr_path_that_is_not_most_precise_path_available() {
+        let expr_path = vec!["http_expr".into(), "".into(), "<*>".into()];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/app/not-existing").unwrap();
+        let tree = fork(
+            label(
+                "http_expr",
+                fork(
+                    label(
+                        "app",
+                        fork(label("", label("<*>", leaf(""))), label("<$>", leaf(""))),
+                    ),
+                    label("", label("<*>", leaf(""))),
+                ),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(!result);
+    }
+
+    #[test]
+    fn validate_expr_path_that_does_not_exist() {
+        let expr_path = vec![
+            "http_expr".into(),
+            "assets".into(),
+            "js".into(),
+            "app.js".into(),
+            "<$>".into(),
+        ];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/assets/js/app.js").unwrap();
+        let tree = fork(
+            label(
+                "http_expr",
+                label("assets", label("js", label("<*>", leaf("")))),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(!result);
+    }
+
+    #[test]
+    fn validate_expr_path_that_exists_but_does_not_match_request() {
+        let expr_path = vec![
+            "http_expr".into(),
+            "assets".into(),
+            "js".into(),
+            "<$>".into(),
+        ];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/assets/js/app.js").unwrap();
+        let tree = fork(
+            label(
+                "http_expr",
+                label("assets", label("js", label("<$>", leaf("")))),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(!result);
+    }
+
+    #[test]
+    fn validate_expr_path_that_does_not_match_request_but_exists() {
+        let expr_path = vec![
+            "http_expr".into(),
+            "assets".into(),
+            "css".into(),
+            "<*>".into(),
+        ];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/assets/js/app.js").unwrap();
+        let tree = fork(
+            label(
+                "http_expr",
+                label(
+                    "assets",
+                    fork(
+                        label("js", label("<*>", leaf(""))),
+                        label("css", label("<*>", leaf(""))),
+                    ),
+                ),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(!result);
+    }
+
+    #[test]
+    fn validate_expr_path_where_more_specific_path_is_pruned() {
+        let expr_path = vec!["http_expr".into(), "assets".into(), "<*>".into()];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/assets/js/app.js").unwrap();
+        let tree = fork(
+            label(
+                "http_expr",
+                label(
+                    "assets",
+                    fork(
+                        label("<*>", leaf("")),
+                        create_pruned(
+                            "c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3",
+                        ),
+                    ),
+                ),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(!result);
+    }
+
+    #[test]
+    fn validate_expr_path_that_has_more_precise_path_available() {
+        let expr_path = vec![
+            "http_expr".into(),
+            "assets".into(),
+            "js".into(),
+            "<*>".into(),
+        ];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/assets/js/app.js").unwrap();
+        let tree = fork(
+            label(
+                "http_expr",
+                label(
+                    "assets",
+                    label("js", label("app.js", label("<$>", leaf("")))),
+                ),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(!result);
+    }
+
+    #[test]
+    fn validate_expr_path_that_has_more_precise_wildcard_path_available() {
+        let expr_path = vec!["http_expr".into(), "assets".into(), "<*>".into()];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/assets/js/app.js").unwrap();
+        let tree = fork(
+            label(
+                "http_expr",
+                label("assets", label("js", label("<*>", leaf("")))),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(!result);
+    }
+
+    #[test]
+    fn validate_expr_path_that_does_not_begin_with_http_expr() {
+        let expr_path = vec![
+            "http_assets".into(),
+            "assets".into(),
+            "js".into(),
+            "app.js".into(),
+            "<$>".into(),
+        ];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/assets/js/app.js").unwrap();
+        let tree = fork(
+            label(
+                "http_assets",
+                label(
+                    "assets",
+                    label("js", label("app.js", label("<$>", leaf("")))),
+                ),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(!result);
+    }
+
+    #[test]
+    fn validate_expr_path_that_does_not_end_with_valid_suffix() {
+        let expr_path = vec!["http_expr".into(), "assets".into()];
+        let request_uri = ::http::Uri::try_from("https://dapp.com/assets/js/app.js").unwrap();
+        let tree = fork(
+            label("http_expr", label("<*>", leaf(""))),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let result = validate_expr_path(&expr_path, request_uri.path(), &tree);
+
+        assert!(!result);
+    }
+
+    #[test]
+    fn validate_various_expr_paths() {
+        let tree_a_b_slash = fork(
+            label(
+                "http_expr",
+                fork(
+                    fork(label("", label("<*>", leaf(""))), label("<*>", leaf(""))),
+                    label(
+                        "a",
+                        fork(
+                            fork(label("", label("<*>", leaf(""))), label("<*>", leaf(""))),
+                            label(
+                                "b",
+                                fork(label("", label("<*>", leaf(""))), label("<*>", leaf(""))),
+                            ),
+                        ),
+                    ),
+                ),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let tree_a_b = fork(
+            label(
+                "http_expr",
+                fork(
+                    fork(label("", label("<*>", leaf(""))), label("<*>", leaf(""))),
+                    label(
+                        "a",
+                        fork(
+                            fork(label("", label("<*>", leaf(""))), label("<*>", leaf(""))),
+                            label("b", label("<*>", leaf(""))),
+                        ),
+                    ),
+                ),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let tree_a_slash = fork(
+            label(
+                "http_expr",
+                fork(
+                    fork(label("", label("<*>", leaf(""))), label("<*>", leaf(""))),
+                    label(
+                        "a",
+                        fork(label("", label("<*>", leaf(""))), label("<*>", leaf(""))),
+                    ),
+                ),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let tree_a = fork(
+            label(
+                "http_expr",
+                fork(
+                    fork(label("", label("<*>", leaf(""))), label("<*>", leaf(""))),
+                    label("a", label("<*>", leaf(""))),
+                ),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let tree_slash = fork(
+            label(
+                "http_expr",
+                fork(label("", label("<*>", leaf(""))), label("<*>", leaf(""))),
+            ),
+            create_pruned("c01f7c0681a684be0a016b800981951832b53d5ffb55c49c27f6e83f7d2749c3"),
+        );
+
+        let tree_star = label("http_expr", label("<*>", leaf("")));
+
+        // validations that should be successful
+        for (request_uri, mut path, tree) in [
+            ("/a/b", vec!["a", "b", "<*>"], tree_a_b_slash.clone()),
+            ("/a/b/", vec!["a", "b", "", "<*>"], tree_a_b_slash.clone()),
+            ("/a/b", vec!["a", "b", "<*>"], tree_a_b.clone()),
+            ("/a/b/", vec!["a", "b", "<*>"], tree_a_b.clone()),
+            ("/a/b", vec!["a", "", "<*>"], tree_a_slash.clone()),
+            ("/a/b/", vec!["a", "", "<*>"], tree_a_slash.clone()),
+            ("/a/b", vec!["a", "<*>"], tree_a.clone()),
+            ("/a/b/", vec!["a", "<*>"], tree_a.clone()),
+            ("/a/b", vec!["", "<*>"], tree_slash.clone()),
+            ("/a/b/", vec!["", "<*>"], tree_slash.clone()),
+            ("/a/b", vec!["<*>"], tree_star.clone()),
+            ("/a/b/", vec!["<*>"], tree_star.clone()),
+            ("/", vec!["", "<*>"], tree_a_b_slash.clone()),
+            ("/", vec!["<*>"], tree_star.clone()),
+        ] {
+            path.insert(0, "http_expr");
+            let expr_path: Vec<String> = path.iter().map(|x| x.to_string()).collect();
+            let result = validate_expr_path(
+                &expr_path,
+                ::http::Uri::try_from(request_uri).unwrap().path(),
+                &tree,
+            );
+            assert!(result);
+        }
+
+        // validations that should fail
+        for (request_uri, mut path, tree) in [
+            ("/a/b", vec!["a", "", "<*>"], tree_a_b_slash.clone()),
+            ("/a/b/", vec!["a", "b", "<*>"], tree_a_b_slash.clone()),
+            ("/a/b", vec!["a", "", "<*>"], tree_a_b.clone()),
+            ("/a/b/", vec!["a", "", "<*>"], tree_a_b.clone()),
+            ("/a/b/", vec!["a", "b", "", "<*>"], tree_a_b.clone()),
+            ("/a/b", vec!["a", "<*>"], tree_a_slash.clone()),
+            ("/a/b", vec!["a", "b", "<*>"], tree_a_slash.clone()),
+            ("/a/b/", vec!["a", "<*>"], tree_a_slash.clone()),
+            ("/a/b/", vec!["a", "b", "<*>"], tree_a_slash.clone()),
+            ("/a/b/", vec!["a", "b", "", "<*>"], tree_a_slash.clone()),
+            ("/a/b", vec!["", "<*>"], tree_a.clone()),
+            ("/a/b", vec!["a", "", "<*>"], tree_a.clone()),
+            ("/a/b", vec!["a", "b", "<*>"], tree_a.clone()),
+            ("/a/b/", vec!["", "<*>"], tree_a.clone()),
+            ("/a/b/", vec!["a", "", "<*>"], tree_a.clone()),
+            ("/a/b/", vec!["a", "b", "<*>"], tree_a.clone()),
+            ("/a/b/", vec!["a", "b", "", "<*>"], tree_a.clone()),
+            ("/a/b", vec!["<*>"], tree_slash.clone()),
+            ("/a/b", vec!["a", "<*>"], tree_slash.clone()),
+            ("/a/b", vec!["a", "", "<*>"], tree_slash.clone()),
+            ("/a/b", vec!["a", "b", "<*>"], tree_slash.clone()),
+            ("/a/b/", vec!["<*>"], tree_slash.clone()),
+            ("/a/b/", vec!["a", "<*>"], tree_slash.clone()),
+            ("/a/b/", vec!["a", "", "<*>"], tree_slash.clone()),
+            ("/a/b/", vec!["a", "b", "<*>"], tree_slash.clone()),
+            ("/a/b/", vec!["a", "b", "", "<*>"], tree_slash.clone()),
+            ("/a/b", vec!["", "<*>"], tree_star.clone()),
+            ("/a/b", vec!["a", "<*>"], tree_star.clone()),
+            ("/a/b", vec!["a", "", "<*>"], tree_star.clone()),
+            ("/a/b", vec!["a", "b", "<*>"], tree_star.clone()),
+            ("/a/b/", vec!["", "<*>"], tree_star.clone()),
+            ("/a/b/", vec!["a", "<*>"], tree_star.clone()),
+            ("/a/b/", vec!["a", "", "<*>"], tree_star.clone()),
+            ("/a/b/", vec!["a", "b", "<*>"], tree_star.clone()),
+            ("/a/b/", vec!["a", "b", "", "<*>"], tree_star.clone()),
+            ("/", vec!["<*>"], tree_a_b_slash.clone()),
+            ("/", vec!["", "<*>"], tree_star.clone()),
+            ("/", vec!["a", "<*>"], tree_a_b_slash.clone()),
+            ("/a/b", vec!["a", "c", "<*>"], tree_a_b_slash.clone()),
+            ("/a/b", vec!["c", "b", "<*>"], tree_a_b_slash.clone()),
+        ] {
+            path.insert(0, "http_expr");
+            let expr_path: Vec<String> = path.iter().map(|x| x.to_string()).collect();
+            let result = validate_expr_path(
+                &expr_path,
+                ::http::Uri::try_from(request_uri).unwrap().path(),
+                &tree,
+            );
+            assert!(!result);
+        }
+    }
+
+    fn create_pruned(data: &str) -> HashTree<Vec<u8>> {
+        pruned(sha256_from_hex(data))
+    }
+
+    fn sha256_from_hex(data: &str) -> Hash {
+        hex::decode(data).unwrap().try_into().unwrap()
+    }
+}
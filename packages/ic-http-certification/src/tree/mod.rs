@@ -7,9 +7,16 @@
 mod certification;
 mod certification_tree;
 mod certification_tree_entry;
+mod certification_tree_item;
 mod certification_tree_path;
+mod certification_validation;
+mod expr_path_validation;
+mod self_verification;
 
 pub use certification::*;
 pub use certification_tree::*;
 pub use certification_tree_entry::*;
+pub use certification_tree_item::*;
 pub use certification_tree_path::*;
+pub use expr_path_validation::*;
+pub use self_verification::*;
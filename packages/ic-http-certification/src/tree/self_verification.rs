@@ -0,0 +1,403 @@
+use super::{
+    certification_tree::HttpCertificationTree,
+    certification_tree_entry::HttpCertificationTreeEntry,
+    certification_tree_item::HttpCertificationTreeItem,
+    certification_tree_path::HttpCertificationPath,
+    expr_path_validation::{validate_expr_hash, validate_expr_path},
+};
+use crate::{
+    cel::DefaultCelExpression, hash::CERTIFICATE_EXPRESSION_HEADER_NAME, request_hash,
+    response_hash_with_options, CelExpression, HttpCertification, HttpCertificationError,
+    HttpCertificationResult, HttpRequest, HttpResponse,
+};
+use ic_certification::LookupResult;
+use ic_representation_independent_hash::hash;
+
+/// Checks that a [response](crate::HttpResponse) will pass verification for a [request](crate::HttpRequest),
+/// using the [witness](HttpCertificationTree::witness) that the [HttpCertificationTree] produces for it.
+///
+/// This reproduces the steps of the HTTP Gateway's response verification (version 2) without needing a real
+/// certificate, so it can be used in unit tests and in debug builds of a canister, before the response is served:
+///
+/// 1. The most specific path in the tree that matches the request URL is selected as the expression path,
+/// and the witness is checked with [validate_expr_path] to prove that there is no more specific path in the tree.
+/// 2. The hash of the response's `IC-CertificateExpression` header must be present under the expression path,
+/// which is checked with [validate_expr_hash].
+/// 3. The request and response hashes, calculated according to the CEL expression in the header, must be
+/// present under the expression hash, unless the expression skips certification.
+///
+/// The root hash of the witness is compared to the [root hash](HttpCertificationTree::root_hash) of the tree in
+/// place of checking the certificate, so the canister's certified data must still be set to the same root hash.
+///
+/// On success, the [HttpCertificationTreeItem] that certifies the response is returned.
+pub fn debug_self_verify(
+    request: &HttpRequest,
+    response: &HttpResponse,
+    tree: &HttpCertificationTree,
+) -> HttpCertificationResult<HttpCertificationTreeItem> {
+    let request_path = request.get_path()?;
+
+    let Some((_, cel_expr)) = response.headers.iter().find(|(header_name, _)| {
+        header_name.eq_ignore_ascii_case(CERTIFICATE_EXPRESSION_HEADER_NAME)
+    }) else {
+        return Err(HttpCertificationError::MissingCertificateExpressionHeader);
+    };
+
+    let path = find_certification_path(tree, &request_path)
+        .ok_or_else(|| HttpCertificationError::MissingCertificationPath(request_path.clone()))?;
+    let expr_path = path.to_expr_path();
+
+    // verifiers hash the exact bytes of the header, rather than the parsed expression
+    let cel_expr_hash = hash(cel_expr.as_bytes());
+    let certification = match CelExpression::parse(cel_expr)?.evaluate(request, response)? {
+        DefaultCelExpression::Skip => HttpCertification::Skip { cel_expr_hash },
+        DefaultCelExpression::ResponseOnly(cel_expr) => HttpCertification::ResponseOnly {
+            cel_expr_hash,
+            response_hash: response_hash_with_options(
+                response,
+                &cel_expr.response,
+                &cel_expr.response_header_options,
+                None,
+            ),
+        },
+        DefaultCelExpression::Full(cel_expr) => HttpCertification::Full {
+            cel_expr_hash,
+            request_hash: request_hash(request, &cel_expr.request)?,
+            response_hash: response_hash_with_options(
+                response,
+                &cel_expr.response,
+                &cel_expr.response_header_options,
+                None,
+            ),
+        },
+    };
+
+    let witness = tree.witness(
+        &HttpCertificationTreeEntry::new(&path, &certification),
+        &request_path,
+    );
+    if witness.digest() != tree.root_hash() {
+        return Err(HttpCertificationError::WitnessRootHashMismatch);
+    }
+
+    if !validate_expr_path(&expr_path, &request_path, &witness) {
+        return Err(HttpCertificationError::InvalidExpressionPath(expr_path));
+    }
+
+    let Some(expr_tree) = validate_expr_hash(&expr_path, &cel_expr_hash, &witness) else {
+        return Err(HttpCertificationError::UncertifiedCertificateExpression(
+            expr_path,
+        ));
+    };
+
+    // the first segment of the certification's tree path is the expression hash
+    let certification_tree_path = certification.to_tree_path();
+    if !matches!(expr_tree.lookup_path(&certification_tree_path[1..]), LookupResult::Found(leaf) if leaf.is_empty())
+    {
+        return Err(HttpCertificationError::UncertifiedResponse(expr_path));
+    }
+
+    Ok(HttpCertificationTreeItem::new(
+        path.into_owned(),
+        certification,
+    ))
+}
+
+/// Finds the most specific path in the tree that matches a request path. Exact paths are more specific than
+/// wildcard paths, and longer wildcard paths are more specific than shorter ones, with a trailing slash being
+/// more specific than the same path without one.
+fn find_certification_path(
+    tree: &HttpCertificationTree,
+    request_path: &str,
+) -> Option<HttpCertificationPath<'static>> {
+    let exact_path = HttpCertificationPath::exact(request_path.to_string());
+    if !tree.get(&exact_path).is_empty() {
+        return Some(exact_path);
+    }
+
+    let mut wildcard_path = request_path;
+    loop {
+        let path = HttpCertificationPath::wildcard(wildcard_path.to_string());
+        if !tree.get(&path).is_empty() {
+            return Some(path);
+        }
+
+        wildcard_path = match (wildcard_path.strip_suffix('/'), wildcard_path.rfind('/')) {
+            (Some(path), _) => path,
+            (None, Some(index)) => &wildcard_path[..=index],
+            (None, None) if !wildcard_path.is_empty() => "",
+            (None, None) => return None,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DefaultCelBuilder, DefaultResponseCertification, DefaultResponseOnlyCelExpression,
+    };
+    use rstest::*;
+
+    #[fixture]
+    fn cel_expr() -> DefaultResponseOnlyCelExpression<'static> {
+        DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                &["Cache-Control"],
+            ))
+            .build()
+    }
+
+    fn request(url: &str) -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    fn response(cel_expr: &str, body: &[u8]) -> HttpResponse {
+        HttpResponse {
+            status_code: 200,
+            headers: vec![
+                ("Cache-Control".to_string(), "no-cache".to_string()),
+                ("IC-CertificateExpression".to_string(), cel_expr.to_string()),
+            ],
+            body: body.to_vec(),
+            upgrade: None,
+        }
+    }
+
+    fn certify(
+        tree: &mut HttpCertificationTree,
+        path: &HttpCertificationPath,
+        cel_expr: &DefaultResponseOnlyCelExpression,
+        response: &HttpResponse,
+    ) -> HttpCertification {
+        let certification = HttpCertification::response_only(cel_expr, response, None).unwrap();
+        tree.insert(&HttpCertificationTreeEntry::new(path, &certification));
+
+        certification
+    }
+
+    #[rstest]
+    #[case::exact(HttpCertificationPath::exact("/js/app.js"), "/js/app.js?v=1")]
+    #[case::wildcard(HttpCertificationPath::wildcard("/js"), "/js/lib/app.js")]
+    #[case::wildcard_trailing_slash(HttpCertificationPath::wildcard("/js/"), "/js/app.js")]
+    #[case::root_wildcard(HttpCertificationPath::wildcard(""), "/css/app.css")]
+    fn self_verifies_certified_response(
+        cel_expr: DefaultResponseOnlyCelExpression,
+        #[case] path: HttpCertificationPath,
+        #[case] request_url: &str,
+    ) {
+        let mut tree = HttpCertificationTree::default();
+        let response = response(&cel_expr.to_string(), b"console.log('hello');");
+        let certification = certify(&mut tree, &path, &cel_expr, &response);
+        certify(
+            &mut tree,
+            &HttpCertificationPath::exact("/index.html"),
+            &cel_expr,
+            &self::response(&cel_expr.to_string(), b"<html></html>"),
+        );
+
+        let item = debug_self_verify(&request(request_url), &response, &tree).unwrap();
+
+        assert_eq!(item.path(), &path.into_owned());
+        assert_eq!(item.certification(), &certification);
+    }
+
+    #[rstest]
+    fn self_verifies_full_certification() {
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(&["Accept"])
+            .with_request_query_parameters(&["lang"])
+            .build();
+        let request = HttpRequest {
+            headers: vec![("Accept".to_string(), "text/html".to_string())],
+            ..request("/index.html?lang=en")
+        };
+        let response = response(&cel_expr.to_string(), b"<html></html>");
+        let certification = HttpCertification::full(&cel_expr, &request, &response, None).unwrap();
+        let path = HttpCertificationPath::exact("/index.html");
+        let mut tree = HttpCertificationTree::default();
+        tree.insert(&HttpCertificationTreeEntry::new(&path, &certification));
+
+        let item = debug_self_verify(&request, &response, &tree).unwrap();
+        assert_eq!(item.certification(), &certification);
+
+        let request = HttpRequest {
+            url: "/index.html?lang=de".to_string(),
+            ..request
+        };
+        let result = debug_self_verify(&request, &response, &tree);
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::UncertifiedResponse(expr_path)) if expr_path == path.to_expr_path()
+        ));
+    }
+
+    #[rstest]
+    fn self_verifies_skipped_certification() {
+        let cel_expr = DefaultCelBuilder::skip_certification().to_string();
+        let path = HttpCertificationPath::wildcard("/api");
+        let mut tree = HttpCertificationTree::default();
+        tree.insert(&HttpCertificationTreeEntry::new(
+            &path,
+            &HttpCertification::skip(),
+        ));
+
+        let item = debug_self_verify(
+            &request("/api/users"),
+            &response(&cel_expr.to_string(), b"[]"),
+            &tree,
+        )
+        .unwrap();
+
+        assert_eq!(item.certification(), &HttpCertification::skip());
+    }
+
+    #[rstest]
+    fn response_without_certificate_expression_header(cel_expr: DefaultResponseOnlyCelExpression) {
+        let mut tree = HttpCertificationTree::default();
+        let mut response = response(&cel_expr.to_string(), b"<html></html>");
+        certify(
+            &mut tree,
+            &HttpCertificationPath::exact("/index.html"),
+            &cel_expr,
+            &response,
+        );
+        response.headers.pop();
+
+        let result = debug_self_verify(&request("/index.html"), &response, &tree);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::MissingCertificateExpressionHeader)
+        ));
+    }
+
+    #[rstest]
+    fn response_without_certification_path(cel_expr: DefaultResponseOnlyCelExpression) {
+        let mut tree = HttpCertificationTree::default();
+        let response = response(&cel_expr.to_string(), b"<html></html>");
+        certify(
+            &mut tree,
+            &HttpCertificationPath::wildcard("/js"),
+            &cel_expr,
+            &response,
+        );
+
+        let result = debug_self_verify(&request("/index.html"), &response, &tree);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::MissingCertificationPath(request_path)) if request_path == "/index.html"
+        ));
+    }
+
+    #[rstest]
+    fn response_shadowed_by_more_specific_path(cel_expr: DefaultResponseOnlyCelExpression) {
+        let mut tree = HttpCertificationTree::default();
+        let response = response(&cel_expr.to_string(), b"console.log('hello');");
+        certify(
+            &mut tree,
+            &HttpCertificationPath::wildcard("/"),
+            &cel_expr,
+            &response,
+        );
+        // the verifier will use the more specific wildcard, which does not certify this expression
+        tree.insert(&HttpCertificationTreeEntry::new(
+            &HttpCertificationPath::wildcard("/js"),
+            &HttpCertification::skip(),
+        ));
+
+        let result = debug_self_verify(&request("/js/app.js"), &response, &tree);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::UncertifiedCertificateExpression(expr_path))
+                if expr_path == HttpCertificationPath::wildcard("/js").to_expr_path()
+        ));
+    }
+
+    #[rstest]
+    fn response_with_uncertified_certificate_expression(
+        cel_expr: DefaultResponseOnlyCelExpression,
+    ) {
+        let mut tree = HttpCertificationTree::default();
+        let response = response(&cel_expr.to_string(), b"<html></html>");
+        certify(
+            &mut tree,
+            &HttpCertificationPath::exact("/index.html"),
+            &cel_expr,
+            &response,
+        );
+        // the header is equivalent to the certified expression, but it is not byte for byte the same
+        let modified_cel_expr = format!(" {}", cel_expr.to_string());
+        let response = self::response(&modified_cel_expr, b"<html></html>");
+
+        let result = debug_self_verify(&request("/index.html"), &response, &tree);
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::UncertifiedCertificateExpression(_))
+        ));
+    }
+
+    #[rstest]
+    fn response_with_modified_body(cel_expr: DefaultResponseOnlyCelExpression) {
+        let mut tree = HttpCertificationTree::default();
+        certify(
+            &mut tree,
+            &HttpCertificationPath::exact("/index.html"),
+            &cel_expr,
+            &response(&cel_expr.to_string(), b"<html></html>"),
+        );
+
+        let result = debug_self_verify(
+            &request("/index.html"),
+            &response(&cel_expr.to_string(), b"<html>modified</html>"),
+            &tree,
+        );
+
+        assert!(matches!(
+            result,
+            Err(HttpCertificationError::UncertifiedResponse(_))
+        ));
+    }
+
+    #[rstest]
+    #[case::exact(&["http_expr", "js", "app.js", "<$>"], true)]
+    #[case::exact_missing(&["http_expr", "js", "index.js", "<$>"], false)]
+    #[case::less_specific_wildcard(&["http_expr", "<*>"], false)]
+    #[case::wildcard_for_other_path(&["http_expr", "css", "<*>"], false)]
+    #[case::invalid_terminator(&["http_expr", "js", "app.js"], false)]
+    fn validates_expr_path(#[case] expr_path: &[&str], #[case] expected: bool) {
+        let mut tree = HttpCertificationTree::default();
+        let entries = [
+            HttpCertificationPath::exact("/js/app.js"),
+            HttpCertificationPath::wildcard(""),
+            HttpCertificationPath::wildcard("/css"),
+        ];
+        for path in entries.iter() {
+            tree.insert(&HttpCertificationTreeEntry::new(
+                path,
+                &HttpCertification::skip(),
+            ));
+        }
+        let witness = tree.witness(
+            &HttpCertificationTreeEntry::new(&entries[0], &HttpCertification::skip()),
+            "/js/app.js",
+        );
+        let expr_path: Vec<_> = expr_path
+            .iter()
+            .map(|segment| segment.to_string())
+            .collect();
+
+        assert_eq!(
+            validate_expr_path(&expr_path, "/js/app.js", &witness),
+            expected
+        );
+    }
+}
@@ -1,13 +1,6 @@
 //! Utilities for parsing CEL expressions into Rust consumable types.
+//!
+//! The parser itself lives in the `ic-http-certification` crate, so that canisters can use it too.
+//! See [CelExpression::parse](ic_http_certification::CelExpression::parse()) for more details.
 
-mod error;
-pub use error::*;
-
-mod ast_mapping;
-mod parser;
-
-pub(crate) use ast_mapping::map_cel_ast;
-pub(crate) use parser::parse_cel_expression;
-
-#[cfg(test)]
-mod tests;
+pub use ic_http_certification::cel::CelParserError;
@@ -1,6 +1,10 @@
-use ic_certification::{Certificate, HashTree, LookupResult};
+use ic_certification::{hash_tree::HashTree, Certificate, LookupResult};
 
-pub fn validate_tree(canister_id: &[u8], certificate: &Certificate, tree: &HashTree) -> bool {
+pub fn validate_tree<Storage: AsRef<[u8]>>(
+    canister_id: &[u8],
+    certificate: &Certificate,
+    tree: &HashTree<Storage>,
+) -> bool {
     let certified_data_path = [
         "canister".as_bytes(),
         canister_id,
@@ -1,6 +1,13 @@
-use ic_certification::{hash_tree::Hash, HashTree, LookupResult};
-
-pub fn validate_body(tree: &HashTree, request_path: &str, body_sha: &Hash) -> bool {
+use ic_certification::{
+    hash_tree::{Hash, HashTree},
+    LookupResult,
+};
+
+pub fn validate_body<Storage: AsRef<[u8]>>(
+    tree: &HashTree<Storage>,
+    request_path: &str,
+    body_sha: &Hash,
+) -> bool {
     let asset_path = ["http_assets".as_bytes(), request_path.as_bytes()];
     let index_fallback_path = ["http_assets".as_bytes(), "/index.html".as_bytes()];
 
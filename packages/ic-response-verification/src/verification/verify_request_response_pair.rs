@@ -1,24 +1,24 @@
 use super::{body::decode_body, certificate_header::CertificateHeader};
 use crate::{
-    cel::{map_cel_ast, parse_cel_expression},
     error::{ResponseVerificationError, ResponseVerificationResult},
     types::{VerificationInfo, VerifiedResponse},
-    validation::{
-        validate_body, validate_expr_hash, validate_expr_path, validate_hashes, validate_tree,
-    },
+    validation::{validate_body, validate_hashes, validate_tree},
+};
+use ic_cbor::{
+    parse_borrowed_hash_tree, parse_cbor_string_array, BorrowedHashTree, CertificateToCbor,
 };
-use ic_cbor::{parse_cbor_string_array, CertificateToCbor, HashTreeToCbor};
 use ic_certificate_verification::{validate_certificate_time, VerifyCertificate};
-use ic_certification::{hash_tree::Hash, Certificate, HashTree};
+use ic_certification::{hash_tree::Hash, Certificate};
 use ic_http_certification::{
     cel::{
         CelExpression, DefaultCelExpression, DefaultFullCelExpression,
         DefaultResponseOnlyCelExpression,
     },
-    filter_response_headers, request_hash, response_headers_hash, HttpRequest, HttpResponse,
+    filter_response_headers, request_hash, response_headers_hash_with_options, validate_expr_hash,
+    validate_expr_path, HttpRequest, HttpResponse,
 };
 use ic_representation_independent_hash::hash;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// The minimum verification version supported by this package.
 pub const MIN_VERIFICATION_VERSION: u8 = 1;
@@ -36,6 +36,72 @@ pub fn verify_request_response_pair(
     ic_public_key: &[u8],
     min_requested_verification_version: u8,
 ) -> ResponseVerificationResult<VerificationInfo> {
+    let opts = VerificationOpts {
+        canister_id,
+        current_time_ns,
+        max_cert_time_offset_ns,
+        ic_public_key,
+        min_requested_verification_version,
+    };
+
+    verify_request_response_pair_impl(request, response, &opts, &mut HashSet::new())
+}
+
+/// Verifies multiple request and response pairs that were certified together, for example using a single
+/// [witness](ic_http_certification::HttpCertificationTree::witness_many) for several responses.
+/// Each response is verified with respect to its request, in the same way as
+/// [verify_request_response_pair], and the results are returned in the same order as the pairs.
+///
+/// Every response must still contain its own `IC-Certificate` header, but the signature of a certificate that is
+/// shared by several responses is only verified once. Verification fails as soon as one of the pairs fails verification.
+pub fn verify_request_response_pairs(
+    request_response_pairs: Vec<(HttpRequest, HttpResponse)>,
+    canister_id: &[u8],
+    current_time_ns: u128,
+    max_cert_time_offset_ns: u128,
+    ic_public_key: &[u8],
+    min_requested_verification_version: u8,
+) -> ResponseVerificationResult<Vec<VerificationInfo>> {
+    let opts = VerificationOpts {
+        canister_id,
+        current_time_ns,
+        max_cert_time_offset_ns,
+        ic_public_key,
+        min_requested_verification_version,
+    };
+    let mut verified_certificates = HashSet::new();
+
+    request_response_pairs
+        .into_iter()
+        .map(|(request, response)| {
+            verify_request_response_pair_impl(request, response, &opts, &mut verified_certificates)
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+struct VerificationOpts<'a> {
+    canister_id: &'a [u8],
+    current_time_ns: u128,
+    max_cert_time_offset_ns: u128,
+    ic_public_key: &'a [u8],
+    min_requested_verification_version: u8,
+}
+
+fn verify_request_response_pair_impl(
+    request: HttpRequest,
+    response: HttpResponse,
+    opts: &VerificationOpts<'_>,
+    verified_certificates: &mut HashSet<Vec<u8>>,
+) -> ResponseVerificationResult<VerificationInfo> {
+    let VerificationOpts {
+        canister_id,
+        current_time_ns,
+        max_cert_time_offset_ns,
+        ic_public_key,
+        min_requested_verification_version,
+    } = *opts;
+
     let headers: HashMap<_, _> = response
         .headers
         .iter()
@@ -54,19 +120,17 @@ pub fn verify_request_response_pair(
 
     let Some(tree) = certificate_header
         .tree
-        .map(|tree| HashTree::from_cbor(&tree))
+        .as_deref()
+        .map(parse_borrowed_hash_tree)
         .transpose()?
     else {
         return Err(ResponseVerificationError::MissingTree);
     };
 
-    let Some(certificate) = certificate_header
-        .certificate
-        .map(|certificate| Certificate::from_cbor(&certificate))
-        .transpose()?
-    else {
+    let Some(certificate_cbor) = certificate_header.certificate else {
         return Err(ResponseVerificationError::MissingCertificate);
     };
+    let certificate = Certificate::from_cbor(&certificate_cbor)?;
 
     let version = certificate_header
         .version
@@ -87,6 +151,8 @@ pub fn verify_request_response_pair(
             max_cert_time_offset_ns,
             tree,
             certificate,
+            certificate_cbor,
+            verified_certificates,
             encoding,
             ic_public_key,
         }),
@@ -100,8 +166,7 @@ pub fn verify_request_response_pair(
                     return Err(ResponseVerificationError::MissingCertificateExpressionPath);
                 };
 
-                let cel_ast = parse_cel_expression(certificate_expression_header)?;
-                let certification = map_cel_ast(&cel_ast)?;
+                let certification = CelExpression::parse(certificate_expression_header)?;
                 let expr_hash = hash(certificate_expression_header.as_bytes());
 
                 v2_verification(V2VerificationOpts {
@@ -112,6 +177,8 @@ pub fn verify_request_response_pair(
                     max_cert_time_offset_ns,
                     tree,
                     certificate,
+                    certificate_cbor,
+                    verified_certificates,
                     expr_path,
                     expr_hash,
                     certification,
@@ -128,14 +195,35 @@ pub fn verify_request_response_pair(
     }
 }
 
+fn verify_certificate(
+    certificate: &Certificate,
+    certificate_cbor: Vec<u8>,
+    canister_id: &[u8],
+    ic_public_key: &[u8],
+    verified_certificates: &mut HashSet<Vec<u8>>,
+) -> ResponseVerificationResult {
+    // the same certificate can be shared by multiple responses,
+    // so there is no need to verify its signature more than once
+    if verified_certificates.contains(&certificate_cbor) {
+        return Ok(());
+    }
+
+    certificate.verify(canister_id, ic_public_key)?;
+    verified_certificates.insert(certificate_cbor);
+
+    Ok(())
+}
+
 struct V1VerificationOpts<'a> {
     request: HttpRequest,
     response: HttpResponse,
     canister_id: &'a [u8],
     current_time_ns: u128,
     max_cert_time_offset_ns: u128,
-    tree: HashTree,
+    tree: BorrowedHashTree<'a>,
     certificate: Certificate,
+    certificate_cbor: Vec<u8>,
+    verified_certificates: &'a mut HashSet<Vec<u8>>,
     encoding: Option<&'a str>,
     ic_public_key: &'a [u8],
 }
@@ -149,12 +237,20 @@ fn v1_verification(
         max_cert_time_offset_ns,
         tree,
         certificate,
+        certificate_cbor,
+        verified_certificates,
         encoding,
         ic_public_key,
     }: V1VerificationOpts<'_>,
 ) -> ResponseVerificationResult<VerificationInfo> {
     validate_certificate_time(&certificate, &current_time_ns, &max_cert_time_offset_ns)?;
-    certificate.verify(canister_id, ic_public_key)?;
+    verify_certificate(
+        &certificate,
+        certificate_cbor,
+        canister_id,
+        ic_public_key,
+        verified_certificates,
+    )?;
 
     let request_path = request.get_path()?;
     let decoded_body = decode_body(&response.body, encoding)?;
@@ -190,8 +286,10 @@ struct V2VerificationOpts<'a> {
     canister_id: &'a [u8],
     current_time_ns: u128,
     max_cert_time_offset_ns: u128,
-    tree: HashTree,
+    tree: BorrowedHashTree<'a>,
     certificate: Certificate,
+    certificate_cbor: Vec<u8>,
+    verified_certificates: &'a mut HashSet<Vec<u8>>,
     expr_path: Vec<String>,
     expr_hash: Hash,
     certification: CelExpression<'a>,
@@ -207,6 +305,8 @@ fn v2_verification(
         max_cert_time_offset_ns,
         tree,
         certificate,
+        certificate_cbor,
+        verified_certificates,
         expr_path,
         expr_hash,
         certification,
@@ -216,7 +316,13 @@ fn v2_verification(
     let request_path = request.get_path()?;
 
     validate_certificate_time(&certificate, &current_time_ns, &max_cert_time_offset_ns)?;
-    certificate.verify(canister_id, ic_public_key)?;
+    verify_certificate(
+        &certificate,
+        certificate_cbor,
+        canister_id,
+        ic_public_key,
+        verified_certificates,
+    )?;
 
     if !validate_tree(canister_id, &certificate, &tree) {
         return Err(ResponseVerificationError::InvalidTree);
@@ -226,24 +332,29 @@ fn v2_verification(
         return Err(ResponseVerificationError::InvalidExpressionPath);
     }
 
-    let (request_certification, response_certification) = match &certification {
-        CelExpression::Default(DefaultCelExpression::Skip) => {
-            return match validate_expr_hash(&expr_path, &expr_hash, &tree).is_some() {
-                true => Ok(VerificationInfo {
-                    response: None,
-                    verification_version: 2,
-                }),
-                false => Err(ResponseVerificationError::InvalidExpressionPath),
-            };
-        }
-        CelExpression::Default(DefaultCelExpression::ResponseOnly(
-            DefaultResponseOnlyCelExpression { response },
-        )) => (None, response),
-        CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
-            request,
-            response,
-        })) => (Some(request), response),
-    };
+    let certification = certification.evaluate(&request, &response)?;
+
+    let (request_certification, response_certification, response_header_options) =
+        match certification {
+            DefaultCelExpression::Skip => {
+                return match validate_expr_hash(&expr_path, &expr_hash, &tree).is_some() {
+                    true => Ok(VerificationInfo {
+                        response: None,
+                        verification_version: 2,
+                    }),
+                    false => Err(ResponseVerificationError::InvalidExpressionPath),
+                };
+            }
+            DefaultCelExpression::ResponseOnly(DefaultResponseOnlyCelExpression {
+                response,
+                response_header_options,
+            }) => (None, response, response_header_options),
+            DefaultCelExpression::Full(DefaultFullCelExpression {
+                request,
+                response,
+                response_header_options,
+            }) => (Some(request), response, response_header_options),
+        };
 
     let request_hash = request_certification
         .as_ref()
@@ -252,8 +363,11 @@ fn v2_verification(
 
     let body_hash = hash(&response.body);
     let response_headers = filter_response_headers(&response, response_certification);
-    let response_headers_hash =
-        response_headers_hash(&response.status_code.into(), &response_headers);
+    let response_headers_hash = response_headers_hash_with_options(
+        &response.status_code.into(),
+        &response_headers,
+        response_header_options,
+    );
     let response_hash = hash([response_headers_hash, body_hash].concat().as_slice());
 
     let are_hashes_valid = validate_hashes(
@@ -262,7 +376,7 @@ fn v2_verification(
         &response_hash,
         &expr_path,
         &tree,
-        &certification,
+        certification,
     );
 
     match are_hashes_valid {